@@ -1,4 +1,7 @@
 fn main() {
+    // 更新签名公钥在编译时嵌入，变化时需要重新编译
+    println!("cargo:rerun-if-env-changed=CLOUD_PE_UPDATE_PUBLIC_KEY");
+
     let mut windows_attributes = tauri_build::WindowsAttributes::new();
 
     // 读取你的 app.manifest 文件
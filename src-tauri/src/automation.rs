@@ -0,0 +1,568 @@
+use anyhow::Result;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{command, AppHandle, Emitter, Listener, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch, Mutex};
+
+use crate::credentials::{protect_data, unprotect_data};
+use crate::paths::app_data_dir;
+use crate::updater::EXTRACT_PROGRESS_EVENT;
+use crate::{plugins, usb_api};
+
+const SETTINGS_FILE: &str = "automation.json";
+const DEFAULT_PORT: u16 = 17321;
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+// 转发给客户端的前端事件及对应的通知方法名
+const FORWARDED_EVENTS: [(&str, &str); 2] = [
+    ("download://progress", "download.progress"),
+    (EXTRACT_PROGRESS_EVENT, "update.extractProgress"),
+];
+
+// JSON-RPC 错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const COMMAND_FAILED: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+
+// 自动化接口设置，默认关闭
+#[derive(Clone)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+// 保存到磁盘的设置，令牌经 DPAPI 加密后以 base64 保存
+#[derive(Serialize, Deserialize)]
+struct StoredSettings {
+    enabled: bool,
+    port: u16,
+    #[serde(default, rename = "protectedToken")]
+    protected_token: String,
+    // 旧版本以明文保存的令牌，读取后迁移为加密保存
+    #[serde(default, skip_serializing)]
+    token: String,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+// 返回给前端的状态
+#[derive(Clone, Serialize)]
+pub struct AutomationStatus {
+    enabled: bool,
+    running: bool,
+    address: String,
+    token: String,
+    // 最近一次启动失败的原因（如端口被占用）
+    error: Option<String>,
+}
+
+struct ServerHandle {
+    shutdown: watch::Sender<bool>,
+    port: u16,
+}
+
+lazy_static::lazy_static! {
+    static ref SERVER: std::sync::Mutex<Option<ServerHandle>> = std::sync::Mutex::new(None);
+    static ref LAST_ERROR: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+    // 推送给已认证客户端的通知
+    static ref NOTIFICATIONS: broadcast::Sender<Value> = broadcast::channel(256).0;
+}
+
+fn settings_path() -> Result<PathBuf> {
+    Ok(app_data_dir()?.join(SETTINGS_FILE))
+}
+
+fn load_settings() -> AutomationSettings {
+    let stored: StoredSettings = match settings_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+    {
+        Some(stored) => stored,
+        None => return AutomationSettings::default(),
+    };
+
+    let mut settings = AutomationSettings {
+        enabled: stored.enabled,
+        port: stored.port,
+        token: stored.token,
+    };
+
+    if !stored.protected_token.is_empty() {
+        match decrypt_token(&stored.protected_token) {
+            Ok(token) => settings.token = token,
+            Err(e) => eprintln!("解密自动化接口令牌失败: {}", e),
+        }
+    } else if !settings.token.is_empty() {
+        if let Err(e) = save_settings(&settings) {
+            eprintln!("迁移自动化接口令牌失败: {}", e);
+        }
+    }
+    settings
+}
+
+fn save_settings(settings: &AutomationSettings) -> Result<()> {
+    let protected_token = if settings.token.is_empty() {
+        String::new()
+    } else {
+        base64::engine::general_purpose::STANDARD.encode(protect_data(settings.token.as_bytes())?)
+    };
+    let stored = StoredSettings {
+        enabled: settings.enabled,
+        port: settings.port,
+        protected_token,
+        token: String::new(),
+    };
+
+    let path = settings_path()?;
+    fs::write(path, serde_json::to_string_pretty(&stored)?)?;
+    Ok(())
+}
+
+fn decrypt_token(protected_token: &str) -> Result<String> {
+    let encrypted = base64::engine::general_purpose::STANDARD.decode(protected_token)?;
+    Ok(String::from_utf8(unprotect_data(&encrypted)?)?)
+}
+
+fn generate_token() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 常量时间比较，避免通过响应时间猜测令牌
+fn token_matches(expected: &str, provided: &str) -> bool {
+    if expected.is_empty() || expected.len() != provided.len() {
+        return false;
+    }
+    expected
+        .bytes()
+        .zip(provided.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn current_status(settings: &AutomationSettings) -> AutomationStatus {
+    let running_port = SERVER.lock().unwrap().as_ref().map(|s| s.port);
+    AutomationStatus {
+        enabled: settings.enabled,
+        running: running_port.is_some(),
+        address: format!("127.0.0.1:{}", running_port.unwrap_or(settings.port)),
+        token: if settings.enabled {
+            settings.token.clone()
+        } else {
+            String::new()
+        },
+        error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+fn emit_status(app: &AppHandle, settings: &AutomationSettings) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("automation://status", current_status(settings));
+    }
+}
+
+// 推送通知给已认证的客户端，没有客户端连接时直接丢弃
+pub fn notify<T: Serialize>(method: &str, params: &T) {
+    if NOTIFICATIONS.receiver_count() == 0 {
+        return;
+    }
+    if let Ok(params) = serde_json::to_value(params) {
+        let _ = NOTIFICATIONS.send(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }));
+    }
+}
+
+// 程序启动时调用：转发下载、部署和更新进度，并在已启用时启动服务
+pub fn init(app: &AppHandle) {
+    for (event_name, method) in FORWARDED_EVENTS {
+        app.listen_any(event_name, move |event| {
+            if let Ok(params) = serde_json::from_str::<Value>(event.payload()) {
+                notify(method, &params);
+            }
+        });
+    }
+
+    let settings = load_settings();
+    if settings.enabled {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = launch_server(&app, &settings).await {
+                eprintln!("启动自动化接口失败: {}", e);
+                emit_status(&app, &settings);
+            }
+        });
+    }
+}
+
+// 启动服务并记录结果，失败原因通过状态显示在设置页面
+async fn launch_server(app: &AppHandle, settings: &AutomationSettings) -> Result<(), String> {
+    let result = start_server(app.clone(), settings)
+        .await
+        .map_err(|e| e.to_string());
+    *LAST_ERROR.lock().unwrap() = result.as_ref().err().cloned();
+    result
+}
+
+async fn start_server(app: AppHandle, settings: &AutomationSettings) -> Result<()> {
+    if stop_server() {
+        // 等待旧的监听任务释放端口
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+
+    // 只监听本机回环地址
+    let listener = TcpListener::bind(("127.0.0.1", settings.port)).await?;
+    let port = listener.local_addr()?.port();
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    let token = Arc::new(settings.token.clone());
+
+    *SERVER.lock().unwrap() = Some(ServerHandle {
+        shutdown: shutdown_tx,
+        port,
+    });
+    println!("自动化接口已启动: 127.0.0.1:{}", port);
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.changed() => break,
+                accepted = listener.accept() => {
+                    match accepted {
+                        Ok((stream, peer)) => {
+                            if !peer.ip().is_loopback() {
+                                continue;
+                            }
+                            let app = app.clone();
+                            let token = token.clone();
+                            let shutdown = shutdown_rx.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = handle_connection(app, stream, token, shutdown).await {
+                                    eprintln!("自动化接口连接异常: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => eprintln!("自动化接口接受连接失败: {}", e),
+                    }
+                }
+            }
+        }
+        println!("自动化接口已停止");
+    });
+
+    Ok(())
+}
+
+fn stop_server() -> bool {
+    // 通知监听任务和所有已建立的连接退出
+    match SERVER.lock().unwrap().take() {
+        Some(handle) => {
+            let _ = handle.shutdown.send(true);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn write_message(writer: &Arc<Mutex<OwnedWriteHalf>>, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    let mut writer = writer.lock().await;
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+// 每行一个 JSON-RPC 2.0 消息；连接后必须先调用 authenticate
+async fn handle_connection(
+    app: AppHandle,
+    stream: TcpStream,
+    token: Arc<String>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    let (reader, writer) = stream.into_split();
+    let writer = Arc::new(Mutex::new(writer));
+    let mut reader = BufReader::new(reader);
+    let mut authenticated = false;
+    let mut forwarder: Option<tokio::task::JoinHandle<()>> = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let mut limited = (&mut reader).take(MAX_REQUEST_SIZE as u64);
+        let read = tokio::select! {
+            _ = shutdown.changed() => break,
+            read = limited.read_line(&mut line) => read?,
+        };
+        if read == 0 {
+            break;
+        }
+        // 超过大小限制仍未读到换行，剩余内容无法再按行对齐，直接断开
+        if read >= MAX_REQUEST_SIZE && !line.ends_with('\n') {
+            write_message(&writer, &error_response(Value::Null, INVALID_REQUEST, "请求过大")).await?;
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                write_message(&writer, &error_response(Value::Null, PARSE_ERROR, e.to_string())).await?;
+                continue;
+            }
+        };
+
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = match request.get("method").and_then(|m| m.as_str()) {
+            Some(method) => method.to_string(),
+            None => {
+                write_message(&writer, &error_response(id, INVALID_REQUEST, "缺少 method")).await?;
+                continue;
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+
+        if method == "authenticate" {
+            let provided = params.get("token").and_then(|t| t.as_str()).unwrap_or("");
+            if !token_matches(&token, provided) {
+                write_message(&writer, &error_response(id, UNAUTHORIZED, "令牌无效")).await?;
+                break;
+            }
+
+            authenticated = true;
+            if forwarder.is_none() {
+                forwarder = Some(spawn_notification_forwarder(writer.clone()));
+            }
+            write_message(&writer, &json!({ "jsonrpc": "2.0", "id": id, "result": true })).await?;
+            continue;
+        }
+
+        if !authenticated {
+            write_message(&writer, &error_response(id, UNAUTHORIZED, "请先调用 authenticate")).await?;
+            continue;
+        }
+
+        // 命令可能耗时较长（如下载），放到独立任务中执行，期间仍可推送进度通知
+        let app = app.clone();
+        let writer = writer.clone();
+        tokio::spawn(async move {
+            let response = match dispatch(&app, &method, params).await {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => error_response(id, code, message),
+            };
+            if let Err(e) = write_message(&writer, &response).await {
+                eprintln!("自动化接口写入响应失败: {}", e);
+            }
+        });
+    }
+
+    if let Some(forwarder) = forwarder {
+        forwarder.abort();
+    }
+    Ok(())
+}
+
+fn spawn_notification_forwarder(writer: Arc<Mutex<OwnedWriteHalf>>) -> tokio::task::JoinHandle<()> {
+    let mut notifications = NOTIFICATIONS.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(message) => {
+                    if write_message(&writer, &message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+type RpcError = (i64, String);
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+fn to_result<T: Serialize>(result: Result<T, String>) -> Result<Value, RpcError> {
+    let value = result.map_err(|e| (COMMAND_FAILED, e))?;
+    serde_json::to_value(value).map_err(|e| (COMMAND_FAILED, e.to_string()))
+}
+
+#[derive(Deserialize)]
+struct DriveParams {
+    drive_letter: String,
+}
+
+#[derive(Deserialize)]
+struct PluginFileParams {
+    drive_letter: String,
+    file_name: String,
+    #[serde(default)]
+    force: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct DownloadFileParams {
+    url: String,
+    save_path: String,
+    thread: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct DownloadPluginParams {
+    url: String,
+    path: String,
+    file_name: Option<String>,
+    threads: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct UpdatePluginParams {
+    url: String,
+    path: String,
+    old_file_name: String,
+    new_file_name: String,
+    threads: Option<u32>,
+}
+
+async fn dispatch(app: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "get_usb_devices" => to_result(usb_api::get_usb_devices().await),
+        "get_system_boot_mode" => to_result(usb_api::get_system_boot_mode().await),
+        "deploy_to_usb" => {
+            let p: DriveParams = parse_params(params)?;
+            to_result(usb_api::deploy_to_usb(p.drive_letter).await)
+        }
+        "download_file_to_path" => {
+            let p: DownloadFileParams = parse_params(params)?;
+            to_result(crate::download_file_to_path(app.clone(), p.url, p.save_path, p.thread).await)
+        }
+        "get_plugin_files" => {
+            let p: DriveParams = parse_params(params)?;
+            to_result(plugins::get_plugin_files(p.drive_letter))
+        }
+        "enable_plugin" => {
+            let p: PluginFileParams = parse_params(params)?;
+            to_result(plugins::enable_plugin(p.drive_letter, p.file_name))
+        }
+        "disable_plugin" => {
+            let p: PluginFileParams = parse_params(params)?;
+            to_result(plugins::disable_plugin(p.drive_letter, p.file_name, p.force))
+        }
+        "download_plugin" => {
+            let p: DownloadPluginParams = parse_params(params)?;
+            to_result(plugins::download_plugin(p.url, p.path, p.file_name, p.threads).await)
+        }
+        "update_plugin" => {
+            let p: UpdatePluginParams = parse_params(params)?;
+            to_result(
+                plugins::update_plugin(p.url, p.path, p.old_file_name, p.new_file_name, p.threads)
+                    .await,
+            )
+        }
+        "check_boot_drive" => to_result(crate::check_boot_drive().await),
+        "check_all_boot_drives" => to_result(crate::check_all_boot_drives().await),
+        "get_drive_info" => {
+            let p: DriveParams = parse_params(params)?;
+            to_result(crate::get_drive_info(p.drive_letter).await)
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("未知方法: {}", method))),
+    }
+}
+
+#[command]
+pub fn get_automation_status() -> AutomationStatus {
+    current_status(&load_settings())
+}
+
+#[command]
+pub async fn set_automation_enabled(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+) -> Result<AutomationStatus, String> {
+    let mut settings = load_settings();
+    settings.enabled = enabled;
+    if let Some(port) = port {
+        settings.port = port;
+    }
+
+    if enabled {
+        if settings.token.is_empty() {
+            settings.token = generate_token();
+        }
+        if let Err(e) = launch_server(&app, &settings).await {
+            emit_status(&app, &settings);
+            return Err(format!("启动自动化接口失败: {}", e));
+        }
+    } else {
+        stop_server();
+        *LAST_ERROR.lock().unwrap() = None;
+    }
+
+    if let Err(e) = save_settings(&settings) {
+        // 新令牌没有保存下来，不能让服务继续以它运行，界面按磁盘上的设置显示
+        if enabled {
+            stop_server();
+        }
+        emit_status(&app, &load_settings());
+        return Err(format!("保存自动化接口设置失败: {}", e));
+    }
+    emit_status(&app, &settings);
+    Ok(current_status(&settings))
+}
+
+#[command]
+pub async fn regenerate_automation_token(app: AppHandle) -> Result<AutomationStatus, String> {
+    let mut settings = load_settings();
+    settings.token = generate_token();
+    save_settings(&settings).map_err(|e| e.to_string())?;
+
+    // 重启服务，断开使用旧令牌的连接；端口被占用等失败时服务已停止，需要通知设置页面
+    if settings.enabled {
+        if let Err(e) = launch_server(&app, &settings).await {
+            emit_status(&app, &settings);
+            return Err(format!("重启自动化接口失败: {}", e));
+        }
+    }
+
+    emit_status(&app, &settings);
+    Ok(current_status(&settings))
+}
@@ -0,0 +1,377 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use crate::download::{self, DownloadConfig, DownloadEventType};
+use crate::credentials::MirrorAuthSet;
+use crate::{plugins, single_instance, usb_api};
+
+// 命令行退出码
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const SUBCOMMANDS: &[&str] = &[
+    "download",
+    "list-usb",
+    "boot-mode",
+    "deploy",
+    "install-ventoy",
+    "plugins",
+    "check-boot-drives",
+    "help",
+    "--help",
+    "-h",
+];
+
+const USAGE: &str = "\
+Cloud-PE One 命令行模式
+
+用法:
+  cloud-pe-one download <url> <path> [--threads <n>] [--sha256 <hash>]
+  cloud-pe-one list-usb
+  cloud-pe-one boot-mode
+  cloud-pe-one deploy <drive>
+  cloud-pe-one install-ventoy <phydrive> [--gpt]
+  cloud-pe-one plugins list <drive>
+  cloud-pe-one plugins enable <drive> <file>
+  cloud-pe-one plugins disable <drive> <file> [--force]
+  cloud-pe-one check-boot-drives
+
+所有命令以 JSON 输出结果，退出码 0 表示成功，1 表示执行失败，2 表示参数错误。
+--threads 为 0 时自动调整连接数；指定 --sha256 时下载完成后校验文件哈希。
+download、deploy、install-ventoy 以及 plugins enable/disable 不能在主程序运行时执行。";
+
+// 带参数值的选项，解析位置参数时跳过其值
+const VALUE_FLAGS: &[&str] = &["--threads", "--sha256"];
+
+// 参数错误与执行失败分开处理，对应不同的退出码
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(e: String) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+// 如果参数是命令行子命令则执行并返回退出码，否则返回 None 继续启动界面
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?;
+    if !SUBCOMMANDS.contains(&command.as_str()) {
+        return None;
+    }
+
+    attach_parent_console();
+
+    if matches!(command.as_str(), "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return Some(EXIT_OK);
+    }
+
+    // 修改启动盘或写入文件的命令不能与主程序同时执行，持有实例互斥量直到退出
+    if is_mutating(command, &args[1..]) && !single_instance::hold_instance_lock() {
+        print_error("Cloud-PE One 正在运行，请关闭主程序后再执行此命令");
+        return Some(EXIT_FAILURE);
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            print_error(&format!("创建运行时失败: {}", e));
+            return Some(EXIT_FAILURE);
+        }
+    };
+
+    // 现有命令会向 stdout 打印日志，执行期间转到 stderr，保证 stdout 只有 JSON 结果
+    let original_stdout = redirect_stdout_to_stderr();
+    let result = runtime.block_on(dispatch(command, &args[1..]));
+    restore_stdout(original_stdout);
+
+    Some(match result {
+        Ok(data) => {
+            print_json(&json!({ "success": true, "data": data }));
+            EXIT_OK
+        }
+        Err(CliError::Usage(message)) => {
+            print_error(&message);
+            eprintln!("{}", USAGE);
+            EXIT_USAGE
+        }
+        Err(CliError::Failed(message)) => {
+            print_error(&message);
+            EXIT_FAILURE
+        }
+    })
+}
+
+fn is_mutating(command: &str, args: &[String]) -> bool {
+    match command {
+        "download" | "deploy" | "install-ventoy" => true,
+        "plugins" => matches!(args.first().map(String::as_str), Some("enable" | "disable")),
+        _ => false,
+    }
+}
+
+async fn dispatch(command: &str, args: &[String]) -> Result<Value, CliError> {
+    match command {
+        "download" => cmd_download(args).await,
+        "list-usb" => to_value(usb_api::get_usb_devices().await?),
+        "boot-mode" => to_value(usb_api::get_system_boot_mode().await?),
+        "deploy" => {
+            let drive = positional(args, 0, "drive")?;
+            to_value(usb_api::deploy_to_usb(drive).await?)
+        }
+        "install-ventoy" => cmd_install_ventoy(args).await,
+        "plugins" => cmd_plugins(args),
+        "check-boot-drives" => to_value(crate::check_all_boot_drives().await?),
+        _ => Err(CliError::Usage(format!("未知命令: {}", command))),
+    }
+}
+
+async fn cmd_download(args: &[String]) -> Result<Value, CliError> {
+    let url = positional(args, 0, "url")?;
+    let path = positional(args, 1, "path")?;
+    let threads = match flag_value(args, "--threads")? {
+        Some(value) => value
+            .parse::<u16>()
+            .map_err(|_| CliError::Usage(format!("无效的线程数: {}", value)))?,
+        None => 8,
+    };
+    let expected_sha256 = flag_value(args, "--sha256")?;
+
+    let config = DownloadConfig {
+        url,
+        save_path: PathBuf::from(path),
+        thread_count: threads,
+        event_type: DownloadEventType::FileDownload,
+        app_handle: None,
+        auth: MirrorAuthSet::load(),
+        expected_sha256,
+    };
+
+    let file_path = download::download(config)
+        .await
+        .map_err(|e| CliError::Failed(format!("下载失败: {}", e)))?;
+
+    Ok(json!({ "path": file_path }))
+}
+
+async fn cmd_install_ventoy(args: &[String]) -> Result<Value, CliError> {
+    let phydrive = positional(args, 0, "phydrive")?;
+    let phydrive = phydrive
+        .parse::<u32>()
+        .map_err(|_| CliError::Usage(format!("无效的物理驱动器编号: {}", phydrive)))?;
+
+    let boot_mode = if args.iter().any(|a| a == "--gpt") {
+        "UEFI"
+    } else {
+        "MBR"
+    };
+
+    to_value(crate::install_ventoy(phydrive, boot_mode.to_string()).await?)
+}
+
+fn cmd_plugins(args: &[String]) -> Result<Value, CliError> {
+    let action = positional(args, 0, "list|enable|disable")?;
+    let drive = positional(args, 1, "drive")?;
+
+    match action.as_str() {
+        "list" => to_value(plugins::get_plugin_files(drive)?),
+        "enable" => {
+            let file = positional(args, 2, "file")?;
+            to_value(plugins::enable_plugin(drive, file)?)
+        }
+        "disable" => {
+            let file = positional(args, 2, "file")?;
+            let force = args.iter().any(|a| a == "--force");
+            to_value(plugins::disable_plugin(drive, file, Some(force))?)
+        }
+        _ => Err(CliError::Usage(format!("未知的插件操作: {}", action))),
+    }
+}
+
+// 获取第 index 个位置参数（跳过 --xxx 选项及其值）
+fn positional(args: &[String], index: usize, name: &str) -> Result<String, CliError> {
+    let mut values = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            iter.next();
+        } else if !arg.starts_with("--") {
+            values.push(arg);
+        }
+    }
+
+    values
+        .get(index)
+        .map(|v| v.to_string())
+        .ok_or_else(|| CliError::Usage(format!("缺少参数 <{}>", name)))
+}
+
+fn flag_value(args: &[String], flag: &str) -> Result<Option<String>, CliError> {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => args
+            .get(i + 1)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| CliError::Usage(format!("{} 缺少参数值", flag))),
+        None => Ok(None),
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, CliError> {
+    serde_json::to_value(value).map_err(|e| CliError::Failed(e.to_string()))
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+    );
+}
+
+fn print_error(message: &str) {
+    print_json(&json!({ "success": false, "error": message }));
+}
+
+// 发布版使用 windows 子系统，需要附加到父进程控制台才能输出
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_parent_console() {}
+
+#[cfg(target_os = "windows")]
+type StdHandle = winapi::um::winnt::HANDLE;
+
+#[cfg(not(target_os = "windows"))]
+type StdHandle = ();
+
+#[cfg(target_os = "windows")]
+fn redirect_stdout_to_stderr() -> Option<StdHandle> {
+    use std::io::Write;
+    use winapi::um::processenv::{GetStdHandle, SetStdHandle};
+    use winapi::um::winbase::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+
+    std::io::stdout().flush().ok();
+    unsafe {
+        let stdout = GetStdHandle(STD_OUTPUT_HANDLE);
+        let stderr = GetStdHandle(STD_ERROR_HANDLE);
+        if SetStdHandle(STD_OUTPUT_HANDLE, stderr) == 0 {
+            return None;
+        }
+        Some(stdout)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn restore_stdout(original: Option<StdHandle>) {
+    use std::io::Write;
+    use winapi::um::processenv::SetStdHandle;
+    use winapi::um::winbase::STD_OUTPUT_HANDLE;
+
+    std::io::stdout().flush().ok();
+    if let Some(handle) = original {
+        unsafe {
+            SetStdHandle(STD_OUTPUT_HANDLE, handle);
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn redirect_stdout_to_stderr() -> Option<StdHandle> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+fn restore_stdout(_original: Option<StdHandle>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        flag_value, is_mutating, positional, run, CliError, EXIT_FAILURE, EXIT_OK, EXIT_USAGE,
+    };
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn positional_skips_flags_and_their_values() {
+        let a = args(&[
+            "--threads",
+            "4",
+            "https://example.com/a.iso",
+            "--gpt",
+            "D:\\a.iso",
+        ]);
+        assert_eq!(
+            positional(&a, 0, "url").ok().as_deref(),
+            Some("https://example.com/a.iso")
+        );
+        assert_eq!(positional(&a, 1, "path").ok().as_deref(), Some("D:\\a.iso"));
+        assert!(matches!(
+            positional(&a, 2, "extra"),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn flag_value_requires_a_value() {
+        let a = args(&["url", "path", "--sha256", "abc"]);
+        assert_eq!(
+            flag_value(&a, "--sha256").ok().flatten().as_deref(),
+            Some("abc")
+        );
+        assert!(matches!(flag_value(&a, "--threads"), Ok(None)));
+        assert!(matches!(
+            flag_value(&args(&["url", "--threads"]), "--threads"),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn mutating_commands_need_the_instance_lock() {
+        assert!(is_mutating("install-ventoy", &args(&["1"])));
+        assert!(is_mutating("deploy", &args(&["E:"])));
+        assert!(is_mutating("download", &args(&["url", "path"])));
+        assert!(is_mutating("plugins", &args(&["disable", "E:", "a.ce"])));
+        assert!(!is_mutating("plugins", &args(&["list", "E:"])));
+        assert!(!is_mutating("list-usb", &[]));
+        assert!(!is_mutating("check-boot-drives", &[]));
+    }
+
+    #[test]
+    fn non_subcommands_start_the_gui() {
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&args(&["--minimized"])), None);
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(run(&args(&["help"])), Some(EXIT_OK));
+        assert_eq!(run(&args(&["download"])), Some(EXIT_USAGE));
+        assert_eq!(
+            run(&args(&["download", "url", "path", "--threads", "many"])),
+            Some(EXIT_USAGE)
+        );
+        assert_eq!(run(&args(&["install-ventoy", "disk0"])), Some(EXIT_USAGE));
+        assert_eq!(
+            run(&args(&["plugins", "frobnicate", "Z:"])),
+            Some(EXIT_USAGE)
+        );
+        // 启动盘不存在属于执行失败而不是参数错误
+        assert_eq!(
+            run(&args(&["plugins", "list", "/nonexistent-drive"])),
+            Some(EXIT_FAILURE)
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::Result;
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+use url::Url;
+
+use crate::paths::app_data_dir;
+
+const CREDENTIALS_FILE: &str = "mirror_credentials.dat";
+
+// 私有镜像认证方式
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MirrorAuth {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+}
+
+// 单个主机的请求头与认证信息
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HostCredential {
+    pub host: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub auth: Option<MirrorAuth>,
+}
+
+// 返回给前端的摘要信息，不包含任何密钥
+#[derive(Serialize)]
+pub struct HostCredentialSummary {
+    host: String,
+    #[serde(rename = "headerNames")]
+    header_names: Vec<String>,
+    #[serde(rename = "authType")]
+    auth_type: Option<String>,
+}
+
+// 下载时使用的按主机匹配的认证集合
+#[derive(Clone, Default)]
+pub struct MirrorAuthSet {
+    entries: Vec<HostCredential>,
+}
+
+// 调试输出只显示主机名，避免凭据进入日志
+impl fmt::Debug for MirrorAuthSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hosts: Vec<&str> = self.entries.iter().map(|e| e.host.as_str()).collect();
+        f.debug_struct("MirrorAuthSet").field("hosts", &hosts).finish()
+    }
+}
+
+impl MirrorAuthSet {
+    // 从加密存储中加载，失败时返回空集合
+    pub fn load() -> Self {
+        match load_credentials() {
+            Ok(entries) => Self { entries },
+            Err(e) => {
+                eprintln!("加载镜像认证信息失败: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    // 将 URL 中内嵌的用户名密码转换为 Basic 认证，并从 URL 中移除
+    pub fn take_url_credentials(&mut self, url: &mut Url) {
+        if url.username().is_empty() {
+            return;
+        }
+
+        let host = match url_host_key(url) {
+            Some(host) => host,
+            None => return,
+        };
+
+        let username = percent_encoding::percent_decode_str(url.username())
+            .decode_utf8_lossy()
+            .to_string();
+        let password = percent_encoding::percent_decode_str(url.password().unwrap_or(""))
+            .decode_utf8_lossy()
+            .to_string();
+
+        url.set_username("").ok();
+        url.set_password(None).ok();
+
+        self.entries.retain(|e| !e.host.eq_ignore_ascii_case(&host));
+        self.entries.push(HostCredential {
+            host,
+            headers: HashMap::new(),
+            auth: Some(MirrorAuth::Basic { username, password }),
+        });
+    }
+
+    // 为请求附加与目标主机匹配的请求头和认证
+    pub fn apply(&self, mut request: RequestBuilder, url: &Url) -> RequestBuilder {
+        let entry = match self.find(url) {
+            Some(entry) => entry,
+            None => return request,
+        };
+
+        for (name, value) in &entry.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        match &entry.auth {
+            Some(MirrorAuth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password))
+            }
+            Some(MirrorAuth::Bearer { token }) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn find(&self, url: &Url) -> Option<&HostCredential> {
+        let host = url.host_str()?;
+        let host_with_port = url_host_key(url)?;
+
+        // 优先匹配带端口的配置
+        self.entries
+            .iter()
+            .find(|e| e.host.eq_ignore_ascii_case(&host_with_port))
+            .or_else(|| self.entries.iter().find(|e| e.host.eq_ignore_ascii_case(host)))
+    }
+}
+
+fn url_host_key(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+// 规范化用户输入的主机名（允许直接粘贴完整地址）
+fn normalize_host(host: &str) -> Result<String> {
+    let host = host.trim();
+    if host.is_empty() {
+        anyhow::bail!("主机名不能为空");
+    }
+
+    if host.contains("://") {
+        let url = Url::parse(host)?;
+        return url_host_key(&url).ok_or_else(|| anyhow::anyhow!("无法解析主机名: {}", host));
+    }
+
+    Ok(host.trim_end_matches('/').to_lowercase())
+}
+
+fn credentials_path() -> Result<PathBuf> {
+    Ok(app_data_dir()?.join(CREDENTIALS_FILE))
+}
+
+fn load_credentials() -> Result<Vec<HostCredential>> {
+    let path = credentials_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let encrypted = fs::read(&path)?;
+    let plain = unprotect_data(&encrypted)?;
+    Ok(serde_json::from_slice(&plain)?)
+}
+
+fn save_credentials(entries: &[HostCredential]) -> Result<()> {
+    let path = credentials_path()?;
+    let plain = serde_json::to_vec(entries)?;
+    let encrypted = protect_data(&plain)?;
+    fs::write(&path, encrypted)?;
+    Ok(())
+}
+
+// 使用 DPAPI 按当前用户加密
+#[cfg(target_os = "windows")]
+pub(crate) fn protect_data(data: &[u8]) -> Result<Vec<u8>> {
+    use winapi::um::dpapi::{CryptProtectData, CRYPTPROTECT_UI_FORBIDDEN};
+    use winapi::um::winbase::LocalFree;
+    use winapi::um::wincrypt::DATA_BLOB;
+
+    unsafe {
+        let mut input = DATA_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output: DATA_BLOB = std::mem::zeroed();
+
+        let ok = CryptProtectData(
+            &mut input,
+            std::ptr::null(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        );
+        if ok == 0 {
+            anyhow::bail!("加密认证信息失败");
+        }
+
+        let result = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        LocalFree(output.pbData as *mut _);
+        Ok(result)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn unprotect_data(data: &[u8]) -> Result<Vec<u8>> {
+    use winapi::um::dpapi::{CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN};
+    use winapi::um::winbase::LocalFree;
+    use winapi::um::wincrypt::DATA_BLOB;
+
+    unsafe {
+        let mut input = DATA_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output: DATA_BLOB = std::mem::zeroed();
+
+        let ok = CryptUnprotectData(
+            &mut input,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        );
+        if ok == 0 {
+            anyhow::bail!("解密认证信息失败");
+        }
+
+        let result = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+        LocalFree(output.pbData as *mut _);
+        Ok(result)
+    }
+}
+
+// 其他平台没有 DPAPI，拒绝保存认证信息，避免以明文落盘
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn protect_data(_data: &[u8]) -> Result<Vec<u8>> {
+    anyhow::bail!("当前平台不支持加密存储认证信息")
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn unprotect_data(_data: &[u8]) -> Result<Vec<u8>> {
+    anyhow::bail!("当前平台不支持加密存储认证信息")
+}
+
+#[command]
+pub fn list_mirror_credentials() -> Result<Vec<HostCredentialSummary>, String> {
+    let entries = load_credentials().map_err(|e| e.to_string())?;
+
+    Ok(entries
+        .into_iter()
+        .map(|e| HostCredentialSummary {
+            host: e.host,
+            header_names: e.headers.into_keys().collect(),
+            auth_type: e.auth.map(|a| match a {
+                MirrorAuth::Basic { .. } => "basic".to_string(),
+                MirrorAuth::Bearer { .. } => "bearer".to_string(),
+            }),
+        })
+        .collect())
+}
+
+#[command]
+pub fn set_mirror_credential(
+    host: String,
+    headers: Option<HashMap<String, String>>,
+    auth: Option<MirrorAuth>,
+) -> Result<bool, String> {
+    let host = normalize_host(&host).map_err(|e| e.to_string())?;
+    let headers = headers.unwrap_or_default();
+
+    for (name, value) in &headers {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("无效的请求头名称: {}", name))?;
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| format!("请求头 {} 的值无效", name))?;
+    }
+
+    let mut entries = load_credentials().map_err(|e| e.to_string())?;
+    entries.retain(|e| !e.host.eq_ignore_ascii_case(&host));
+    entries.push(HostCredential { host, headers, auth });
+
+    save_credentials(&entries).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+#[command]
+pub fn remove_mirror_credential(host: String) -> Result<bool, String> {
+    let host = normalize_host(&host).map_err(|e| e.to_string())?;
+
+    let mut entries = load_credentials().map_err(|e| e.to_string())?;
+    let before = entries.len();
+    entries.retain(|e| !e.host.eq_ignore_ascii_case(&host));
+
+    if entries.len() == before {
+        return Ok(false);
+    }
+
+    save_credentials(&entries).map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,390 @@
+use anyhow::Result;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+use url::Url;
+
+use crate::download::{
+    fetch_bytes, parse_download_source, update_status_slot, DownloadEventType, DownloadSource,
+    DownloadStatus,
+};
+use crate::signature::{signature_url, verify_update_package_from};
+use crate::update_service::{compare_versions, CURRENT_VERSION};
+
+const DELTA_MANIFEST_SUFFIX: &str = ".delta.json";
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+const MAX_DELTA_FILE_SIZE: u64 = 512 * 1024 * 1024;
+// 应用补丁后生成的单个文件大小上限
+const MAX_PATCHED_FILE_SIZE: usize = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeltaAction {
+    // 文件未变化，直接复用已安装的文件
+    Keep,
+    // 下载补丁并应用到已安装的文件
+    Patch,
+    // 新增或变化过大的文件，下载完整文件
+    Add,
+}
+
+// 增量清单中的单个文件
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeltaEntry {
+    pub path: String,
+    pub action: DeltaAction,
+    // 应用补丁前已安装文件的哈希
+    #[serde(default)]
+    pub old_sha256: Option<String>,
+    // 新版本文件的哈希
+    pub sha256: String,
+    // 补丁或完整文件的地址，相对地址以清单地址为基准
+    #[serde(default)]
+    pub url: Option<String>,
+    // 需要下载的字节数（补丁或完整文件），用于计算进度
+    #[serde(default)]
+    pub size: u64,
+}
+
+// 增量更新清单，与完整更新包放在一起（<更新包地址>.delta.json）
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeltaManifest {
+    pub from_version: String,
+    pub to_version: String,
+    pub files: Vec<DeltaEntry>,
+}
+
+pub fn delta_manifest_url(package_url: &str) -> String {
+    match Url::parse(package_url) {
+        Ok(mut url) if url.scheme() != "file" => {
+            let path = format!("{}{}", url.path(), DELTA_MANIFEST_SUFFIX);
+            url.set_path(&path);
+            url.to_string()
+        }
+        _ => format!("{}{}", package_url, DELTA_MANIFEST_SUFFIX),
+    }
+}
+
+// 补丁地址相对清单地址解析
+fn resolve_entry_url(manifest_url: &str, entry_url: &str) -> Result<String> {
+    if Url::parse(entry_url).is_ok() || Path::new(entry_url).is_absolute() {
+        return Ok(entry_url.to_string());
+    }
+
+    match parse_download_source(manifest_url)? {
+        DownloadSource::Http(base) => Ok(base.join(entry_url)?.to_string()),
+        DownloadSource::Local(path) => {
+            let parent = path.parent().unwrap_or_else(|| Path::new(""));
+            Ok(parent.join(entry_url).to_string_lossy().to_string())
+        }
+    }
+}
+
+// 只允许相对安装目录的普通路径
+fn safe_relative_path(path: &str) -> Result<PathBuf> {
+    let rel = PathBuf::from(path.replace('\\', "/"));
+    if rel.as_os_str().is_empty() || rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+        anyhow::bail!("增量清单包含无效路径: {}", path);
+    }
+    Ok(rel)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn set_progress(event_type: &DownloadEventType, done: u64, total: u64, started: Instant) {
+    let progress = (done * 100).checked_div(total).unwrap_or(100);
+    let elapsed = started.elapsed().as_secs_f64();
+    let speed = if elapsed > 0.0 {
+        done as f64 / elapsed / 1024.0 / 1024.0
+    } else {
+        0.0
+    };
+    *update_status_slot(event_type).lock().unwrap() = Some(DownloadStatus {
+        progress: progress.min(100),
+        speed: format!("{:.2}", speed),
+    });
+}
+
+async fn fetch_manifest(manifest_url: &str, target_version: &str) -> Result<DeltaManifest> {
+    let bytes = fetch_bytes(manifest_url, MAX_MANIFEST_SIZE).await?;
+
+    // 清单本身带签名，其中的哈希用来校验每个生成的文件
+    let temp_path =
+        std::env::temp_dir().join(format!("cloud-pe-delta-{}.json", std::process::id()));
+    fs::write(&temp_path, &bytes)?;
+    let verified =
+        verify_update_package_from(&temp_path, &signature_url(manifest_url), target_version).await;
+    fs::remove_file(&temp_path).ok();
+    verified?;
+
+    let manifest: DeltaManifest = serde_json::from_slice(&bytes)?;
+    if compare_versions(&manifest.to_version, target_version) != std::cmp::Ordering::Equal {
+        anyhow::bail!(
+            "增量包目标版本 {} 与 {} 不一致",
+            manifest.to_version,
+            target_version
+        );
+    }
+    Ok(manifest)
+}
+
+// 校验已安装的文件是否与清单记录的旧版本一致
+fn verify_installed(manifest: &DeltaManifest, app_dir: &Path) -> Result<()> {
+    for entry in &manifest.files {
+        if entry.action == DeltaAction::Add {
+            continue;
+        }
+        let expected = match entry.action {
+            DeltaAction::Keep => &entry.sha256,
+            _ => entry
+                .old_sha256
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("补丁缺少旧文件哈希: {}", entry.path))?,
+        };
+
+        let installed = app_dir.join(safe_relative_path(&entry.path)?);
+        let actual = sha256_file(&installed)
+            .map_err(|e| anyhow::anyhow!("读取已安装文件失败 {}: {}", entry.path, e))?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!("已安装文件与清单不一致: {}", entry.path);
+        }
+    }
+    Ok(())
+}
+
+// 应用 bsdiff 补丁（bsdiff-rs 格式，无文件头、不压缩）。补丁由若干段组成，每段以 24 字节的
+// 控制数据开头：混合长度、复制长度（u64 小端）和旧文件偏移（符号位加绝对值的 i64 小端），
+// 随后是与旧文件逐字节相加的混合数据和直接写入的复制数据
+fn apply_patch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>> {
+    let mut new = Vec::new();
+    let mut old_pos: i64 = 0;
+    let mut rest = patch;
+
+    while !rest.is_empty() {
+        if rest.len() < 24 {
+            anyhow::bail!("补丁控制数据不完整");
+        }
+        let mix_len = read_u64(&rest[0..8]);
+        let copy_len = read_u64(&rest[8..16]);
+        let seek = read_offset(&rest[16..24]);
+        rest = &rest[24..];
+
+        let data_len = mix_len
+            .checked_add(copy_len)
+            .filter(|len| *len <= rest.len() as u64)
+            .ok_or_else(|| anyhow::anyhow!("补丁数据不完整"))? as usize;
+        if new.len() + data_len > MAX_PATCHED_FILE_SIZE {
+            anyhow::bail!("补丁生成的文件过大");
+        }
+        let (mix, copy) = rest[..data_len].split_at(mix_len as usize);
+        rest = &rest[data_len..];
+
+        let old_slice = usize::try_from(old_pos)
+            .ok()
+            .and_then(|start| old.get(start..start.checked_add(mix.len())?))
+            .ok_or_else(|| anyhow::anyhow!("补丁引用的位置超出原文件范围"))?;
+        new.extend(mix.iter().zip(old_slice).map(|(d, o)| d.wrapping_add(*o)));
+        new.extend_from_slice(copy);
+
+        old_pos = old_pos
+            .checked_add(mix_len as i64)
+            .and_then(|pos| pos.checked_add(seek))
+            .ok_or_else(|| anyhow::anyhow!("补丁偏移无效"))?;
+    }
+
+    Ok(new)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap())
+}
+
+fn read_offset(bytes: &[u8]) -> i64 {
+    let value = read_u64(bytes);
+    let magnitude = (value & !(1 << 63)) as i64;
+    if value & (1 << 63) == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+async fn build_staging(
+    manifest: &DeltaManifest,
+    manifest_url: &str,
+    app_dir: &Path,
+    staging_dir: &Path,
+    event_type: &DownloadEventType,
+) -> Result<()> {
+    let total: u64 = manifest
+        .files
+        .iter()
+        .filter(|e| e.action != DeltaAction::Keep)
+        .map(|e| e.size)
+        .sum();
+    let mut done = 0u64;
+    let started = Instant::now();
+    set_progress(event_type, 0, total, started);
+
+    for (i, entry) in manifest.files.iter().enumerate() {
+        let rel = safe_relative_path(&entry.path)?;
+        let target = staging_dir.join(&rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let data = match entry.action {
+            DeltaAction::Keep => fs::read(app_dir.join(&rel))?,
+            DeltaAction::Patch | DeltaAction::Add => {
+                let url = entry
+                    .url
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("清单缺少下载地址: {}", entry.path))?;
+                let source = resolve_entry_url(manifest_url, url)?;
+                let downloaded = fetch_bytes(&source, MAX_DELTA_FILE_SIZE).await?;
+                done += downloaded.len() as u64;
+
+                if entry.action == DeltaAction::Add {
+                    downloaded
+                } else {
+                    let old = fs::read(app_dir.join(&rel))?;
+                    apply_patch(&old, &downloaded)
+                        .map_err(|e| anyhow::anyhow!("应用补丁失败 {}: {}", entry.path, e))?
+                }
+            }
+        };
+
+        if !sha256_hex(&data).eq_ignore_ascii_case(&entry.sha256) {
+            anyhow::bail!("生成的文件哈希不匹配: {}", entry.path);
+        }
+        fs::write(&target, &data)?;
+
+        println!(
+            "增量更新进度: {}/{} {:?} {}",
+            i + 1,
+            manifest.files.len(),
+            entry.action,
+            entry.path
+        );
+        set_progress(event_type, done, total, started);
+    }
+
+    set_progress(event_type, total, total, started);
+    Ok(())
+}
+
+// 尝试通过增量包准备新版本文件到 staging_dir。
+// 返回 Ok(true) 表示已准备完成；没有适用的增量包时返回 Ok(false)；
+// 出错时清理 staging_dir 并返回错误，由调用方回退到完整更新包
+pub async fn prepare_delta_update(
+    package_url: &str,
+    target_version: &str,
+    app_dir: &Path,
+    staging_dir: &Path,
+    event_type: DownloadEventType,
+) -> Result<bool> {
+    let manifest_url = delta_manifest_url(package_url);
+    let manifest = match fetch_manifest(&manifest_url, target_version).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            println!("没有可用的增量更新: {}", e);
+            return Ok(false);
+        }
+    };
+
+    if compare_versions(&manifest.from_version, CURRENT_VERSION) != std::cmp::Ordering::Equal {
+        println!(
+            "增量包基于 {}，当前版本为 {}，使用完整更新包",
+            manifest.from_version, CURRENT_VERSION
+        );
+        return Ok(false);
+    }
+
+    println!(
+        "使用增量更新: {} -> {}，共 {} 个文件",
+        manifest.from_version,
+        manifest.to_version,
+        manifest.files.len()
+    );
+
+    verify_installed(&manifest, app_dir)?;
+
+    if staging_dir.exists() {
+        fs::remove_dir_all(staging_dir)?;
+    }
+    fs::create_dir_all(staging_dir)?;
+
+    let built = build_staging(&manifest, &manifest_url, app_dir, staging_dir, &event_type).await;
+    if let Err(e) = built {
+        fs::remove_dir_all(staging_dir).ok();
+        return Err(e);
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::apply_patch;
+
+    // 构造一段补丁：混合数据为新旧字节之差
+    fn segment(old: &[u8], new_mix: &[u8], copy: &[u8], seek: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(new_mix.len() as u64).to_le_bytes());
+        out.extend_from_slice(&(copy.len() as u64).to_le_bytes());
+        let offset = if seek < 0 {
+            (seek.unsigned_abs()) | (1 << 63)
+        } else {
+            seek as u64
+        };
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend(new_mix.iter().zip(old).map(|(n, o)| n.wrapping_sub(*o)));
+        out.extend_from_slice(copy);
+        out
+    }
+
+    #[test]
+    fn applies_mix_copy_and_seek() {
+        let old = b"hello world, hello patch";
+        // "hello " 原样保留，"world" 改为 "WORLD"，插入 "!!"，再回到开头复用 "hello"
+        let mut patch = segment(&old[..6], b"hello ", b"", 0);
+        patch.extend(segment(&old[6..11], b"WORLD", b"!!", -11));
+        patch.extend(segment(&old[..5], b"hello", b" again", 0));
+
+        assert_eq!(
+            apply_patch(old, &patch).unwrap(),
+            b"hello WORLD!!hello again".to_vec()
+        );
+    }
+
+    #[test]
+    fn empty_patch_produces_empty_file() {
+        assert!(apply_patch(b"old", b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_truncated_and_out_of_range_patches() {
+        let old = b"abc";
+        let patch = segment(old, b"abc", b"def", 0);
+        assert!(apply_patch(old, &patch[..10]).is_err());
+        assert!(apply_patch(old, &patch[..patch.len() - 1]).is_err());
+        // 混合数据超出原文件
+        assert!(apply_patch(b"ab", &patch).is_err());
+        // 偏移到原文件开头之前
+        let mut patch = segment(old, b"a", b"", -5);
+        patch.extend(segment(old, b"a", b"", 0));
+        assert!(apply_patch(old, &patch).is_err());
+    }
+}
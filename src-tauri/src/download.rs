@@ -11,6 +11,21 @@ use tokio::sync::{mpsc, Mutex, Semaphore};
 use tokio::time::{interval, Duration, Instant};
 use url::Url;
 use tauri::{AppHandle, Emitter, Manager};
+use crate::credentials::MirrorAuthSet;
+use crate::delta_update::sha256_file;
+
+// 线程数为 0 表示自动模式，根据实测吞吐量动态调整连接数
+pub const AUTO_THREAD_COUNT: u16 = 0;
+const AUTO_INITIAL_CONNECTIONS: usize = 4;
+const AUTO_MIN_CONNECTIONS: usize = 2;
+const AUTO_MAX_CONNECTIONS: usize = 64;
+const AUTO_CONNECTION_STEP: usize = 4;
+const AUTO_MIN_CHUNK_SIZE: u64 = 1024 * 1024;
+const AUTO_TUNE_INTERVAL: Duration = Duration::from_secs(3);
+// 出错或速度回落后暂停增加连接的调节周期数
+const AUTO_COOLDOWN_TICKS: u32 = 3;
+// 速度稳定持续这么多个调节周期后重新尝试增加连接
+const AUTO_PROBE_TICKS: u32 = 5;
 
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36 Edg/138.0.0.0";
 
@@ -61,12 +76,16 @@ pub struct DownloadConfig {
     pub thread_count: u16,
     pub event_type: DownloadEventType,
     pub app_handle: Option<AppHandle>,
+    pub auth: MirrorAuthSet,
+    // 已知时在下载或复制完成后校验 sha256
+    pub expected_sha256: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub enum DownloadEventType {
     FileDownload,    // 普通文件下载
     UpdateDownload,  // 更新包下载
+    UpdatePrefetch,  // 后台预下载更新包
     PluginDownload,  // 插件下载
 }
 
@@ -74,6 +93,152 @@ pub enum DownloadEventType {
 lazy_static::lazy_static! {
     pub static ref UPDATE_DOWNLOAD_STATUS: Arc<std::sync::Mutex<Option<DownloadStatus>>> = 
         Arc::new(std::sync::Mutex::new(None));
+    // 后台预下载单独记录，不影响界面显示的更新进度
+    pub static ref PREFETCH_DOWNLOAD_STATUS: Arc<std::sync::Mutex<Option<DownloadStatus>>> =
+        Arc::new(std::sync::Mutex::new(None));
+}
+
+// 按事件类型取对应的更新下载状态
+pub fn update_status_slot(event_type: &DownloadEventType) -> &'static std::sync::Mutex<Option<DownloadStatus>> {
+    match event_type {
+        DownloadEventType::UpdatePrefetch => &PREFETCH_DOWNLOAD_STATUS,
+        _ => &UPDATE_DOWNLOAD_STATUS,
+    }
+}
+
+// 下载源：HTTP(S) 地址或本地/网络共享路径
+#[derive(Debug, Clone)]
+pub enum DownloadSource {
+    Http(Url),
+    Local(PathBuf),
+}
+
+// 解析下载源，支持 http(s)://、file:// 以及本地路径和 UNC 路径
+pub fn parse_download_source(raw: &str) -> Result<DownloadSource> {
+    let raw = raw.trim();
+
+    if is_local_path(raw) {
+        return Ok(DownloadSource::Local(PathBuf::from(raw)));
+    }
+
+    let url = Url::parse(raw)?;
+    match url.scheme() {
+        "http" | "https" => Ok(DownloadSource::Http(url)),
+        "file" => {
+            let path = file_url_to_path(&url)?;
+            Ok(DownloadSource::Local(path))
+        }
+        scheme => anyhow::bail!("不支持的下载协议: {}", scheme),
+    }
+}
+
+// 判断是否为本地路径（C:\xxx、C:/xxx、\\server\share\xxx 或 /xxx）
+fn is_local_path(raw: &str) -> bool {
+    let bytes = raw.as_bytes();
+
+    if raw.starts_with("\\\\") || raw.starts_with("//") {
+        return true;
+    }
+
+    if bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/')
+    {
+        return true;
+    }
+
+    raw.starts_with('/')
+}
+
+// file:// 地址转换为路径，带主机名的地址视为 UNC 共享
+fn file_url_to_path(url: &Url) -> Result<PathBuf> {
+    if let Ok(path) = url.to_file_path() {
+        return Ok(path);
+    }
+
+    let decoded = percent_encoding::percent_decode_str(url.path())
+        .decode_utf8()
+        .map_err(|e| anyhow::anyhow!("无法解析文件路径: {}", e))?
+        .to_string();
+
+    match url.host_str() {
+        Some(host) if !host.is_empty() => Ok(PathBuf::from(format!(
+            "\\\\{}{}",
+            host,
+            decoded.replace('/', "\\")
+        ))),
+        _ => Ok(PathBuf::from(decoded.trim_start_matches('/'))),
+    }
+}
+
+// 获取下载源的文件名（用于插件等需要预先确定文件名的场景）
+pub async fn get_source_filename(client: &Client, raw: &str) -> Result<String> {
+    match parse_download_source(raw)? {
+        DownloadSource::Http(mut url) => {
+            let mut auth = MirrorAuthSet::load();
+            auth.take_url_credentials(&mut url);
+            let (_, filename, _, _) = get_file_info(client, &url, &auth).await?;
+            Ok(filename)
+        }
+        DownloadSource::Local(path) => local_file_info(&path).map(|(filename, _)| filename),
+    }
+}
+
+// 读取小文件的完整内容（签名、清单等），支持 HTTP 和本地路径
+pub async fn fetch_bytes(raw: &str, max_size: u64) -> Result<Vec<u8>> {
+    match parse_download_source(raw)? {
+        DownloadSource::Local(path) => {
+            let (_, size) = local_file_info(&path)?;
+            if size > max_size {
+                anyhow::bail!("文件过大: {} 字节", size);
+            }
+            Ok(std::fs::read(&path)?)
+        }
+        DownloadSource::Http(mut url) => {
+            let mut auth = MirrorAuthSet::load();
+            auth.take_url_credentials(&mut url);
+
+            let client = build_client()?;
+            let response = auth
+                .apply(client.get(url.as_str()), &url)
+                .timeout(Duration::from_secs(30))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                anyhow::bail!("请求失败: {}", response.status());
+            }
+
+            let mut body = Vec::new();
+            let mut stream = response.bytes_stream();
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                if body.len() as u64 + chunk.len() as u64 > max_size {
+                    anyhow::bail!("文件过大，超过 {} 字节", max_size);
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok(body)
+        }
+    }
+}
+
+// 获取本地文件信息
+fn local_file_info(path: &Path) -> Result<(String, u64)> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| anyhow::anyhow!("无法访问源文件 {}: {}", path.display(), e))?;
+
+    if !metadata.is_file() {
+        anyhow::bail!("源路径不是文件: {}", path.display());
+    }
+
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "download".to_string());
+
+    Ok((filename, metadata.len()))
 }
 
 // 文件名解析相关函数
@@ -244,12 +409,16 @@ fn load_download_state(state_file: &Path) -> Result<Vec<WorkerInfo>> {
 }
 
 // 获取文件信息 - 增强版，带重试和回退机制
-pub async fn get_file_info(client: &Client, url: &Url) -> Result<(Url, String, u64, bool)> {
+pub async fn get_file_info(
+    client: &Client,
+    url: &Url,
+    auth: &MirrorAuthSet,
+) -> Result<(Url, String, u64, bool)> {
     let mut retries = 0;
     const MAX_RETRIES: u32 = 3;
     
     loop {
-        match get_file_info_attempt(client, url).await {
+        match get_file_info_attempt(client, url, auth).await {
             Ok(result) => return Ok(result),
             Err(e) => {
                 retries += 1;
@@ -263,10 +432,14 @@ pub async fn get_file_info(client: &Client, url: &Url) -> Result<(Url, String, u
     }
 }
 
-async fn get_file_info_attempt(client: &Client, url: &Url) -> Result<(Url, String, u64, bool)> {
+async fn get_file_info_attempt(
+    client: &Client,
+    url: &Url,
+    auth: &MirrorAuthSet,
+) -> Result<(Url, String, u64, bool)> {
     // 首先尝试 HEAD 请求
-    let head_result = client
-        .head(url.as_str())
+    let head_result = auth
+        .apply(client.head(url.as_str()), url)
         .timeout(Duration::from_secs(10))
         .send()
         .await;
@@ -296,8 +469,8 @@ async fn get_file_info_attempt(client: &Client, url: &Url) -> Result<(Url, Strin
             // HEAD 请求失败，尝试使用 GET 请求
             eprintln!("HEAD 请求失败，尝试 GET 请求");
             
-            let response = client
-                .get(url.as_str())
+            let response = auth
+                .apply(client.get(url.as_str()), url)
                 .header("Range", "bytes=0-0")
                 .timeout(Duration::from_secs(10))
                 .send()
@@ -340,6 +513,7 @@ async fn get_file_info_attempt(client: &Client, url: &Url) -> Result<(Url, Strin
 async fn download_chunk_part(
     client: &Client,
     url: &Url,
+    auth: &MirrorAuthSet,
     file: Arc<Mutex<File>>,
     worker: &mut WorkerInfo,
     worker_id: usize,
@@ -352,8 +526,8 @@ async fn download_chunk_part(
     
     let range = format!("bytes={}-{}", worker.current_pos, worker.end_pos - 1);
 
-    let response = client
-        .get(url.as_str())
+    let response = auth
+        .apply(client.get(url.as_str()), url)
         .header("Range", range.clone())
         .timeout(Duration::from_secs(60))
         .send()
@@ -450,18 +624,20 @@ async fn download_chunk_part(
 async fn download_chunk(
     client: &Client,
     url: &Url,
+    auth: &MirrorAuthSet,
     file: Arc<Mutex<File>>,
     mut worker: WorkerInfo,
     worker_id: usize,
     progress_tx: mpsc::Sender<ProgressUpdate>,
     worker_tx: mpsc::Sender<(usize, WorkerInfo)>,
+    error_count: Arc<AtomicU64>,
 ) -> Result<()> {
     let mut retry_count = 0;
     const MAX_RETRIES: u32 = 10;
     const INITIAL_RETRY_DELAY: u64 = 2;
 
     while worker.current_pos < worker.end_pos {
-        match download_chunk_part(client, url, file.clone(), &mut worker, worker_id, &progress_tx).await {
+        match download_chunk_part(client, url, auth, file.clone(), &mut worker, worker_id, &progress_tx).await {
             Ok(_) => {
                 retry_count = 0;
                 worker_tx.send((worker_id, worker.clone())).await.ok();
@@ -473,6 +649,7 @@ async fn download_chunk(
             }
             Err(e) => {
                 retry_count += 1;
+                error_count.fetch_add(1, Ordering::Relaxed);
                 eprintln!(
                     "Worker {} 下载失败 (重试 {}/{}): {}", 
                     worker_id, retry_count, MAX_RETRIES, e
@@ -504,7 +681,7 @@ fn emit_download_event(config: &DownloadConfig, event: DownloadEvent) {
             }
         }
         DownloadEvent::UpdateProgress(status) => {
-            let mut global_status = UPDATE_DOWNLOAD_STATUS.lock().unwrap();
+            let mut global_status = update_status_slot(&config.event_type).lock().unwrap();
             *global_status = Some(status);
         }
     }
@@ -559,11 +736,14 @@ async fn multi_thread_download_impl(
     }
 
     let total_downloaded = Arc::new(AtomicU64::new(already_downloaded));
+    let current_speed = Arc::new(AtomicU64::new(0)); // 字节/秒，供自动模式调整连接数
+    let error_count = Arc::new(AtomicU64::new(0));
     let (progress_tx, mut progress_rx) = mpsc::channel::<ProgressUpdate>(10000);
 
     // 启动进度显示任务
     let progress_handle = {
         let total_downloaded_clone = total_downloaded.clone();
+        let current_speed_clone = current_speed.clone();
         let workers_state_clone = workers_state.clone();
         let state_file_clone = state_file.to_path_buf();
         let config_clone = config.clone();
@@ -616,6 +796,7 @@ async fn multi_thread_download_impl(
                         0.0
                     };
 
+                    current_speed_clone.store((speed * 1024.0 * 1024.0) as u64, Ordering::Relaxed);
                     let display_speed = speed;
                     let progress = (current_total as f64 / file_size as f64) * 100.0;
                     
@@ -629,7 +810,7 @@ async fn multi_thread_download_impl(
                             };
                             emit_download_event(&config_clone, DownloadEvent::Progress(info));
                         }
-                        DownloadEventType::UpdateDownload => {
+                        DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
                             let status = DownloadStatus {
                                 progress: progress as u64,
                                 speed: format!("{:.2}", display_speed),
@@ -666,7 +847,7 @@ async fn multi_thread_download_impl(
                             };
                             emit_download_event(&config_clone, DownloadEvent::Progress(final_info));
                         }
-                        DownloadEventType::UpdateDownload => {
+                        DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
                             let final_status = DownloadStatus {
                                 progress: 100,
                                 speed: "0.00".to_string(),
@@ -692,7 +873,27 @@ async fn multi_thread_download_impl(
         }
     });
 
-    let semaphore = Arc::new(Semaphore::new(workers.len().min(16))); // 限制并发数
+    // 固定模式按用户选择的线程数并发；自动模式从少量连接开始，由调节任务逐步增减
+    let adaptive = config.thread_count == AUTO_THREAD_COUNT;
+    let initial_permits = if adaptive {
+        AUTO_INITIAL_CONNECTIONS.min(workers.len()).max(1)
+    } else {
+        workers.len()
+    };
+    let semaphore = Arc::new(Semaphore::new(initial_permits));
+
+    let tuner_handle = if adaptive {
+        Some(tokio::spawn(auto_tune_connections(
+            semaphore.clone(),
+            current_speed.clone(),
+            error_count.clone(),
+            initial_permits,
+            workers.len().min(AUTO_MAX_CONNECTIONS),
+        )))
+    } else {
+        None
+    };
+
     let mut tasks = Vec::new();
 
     for (i, worker) in workers.into_iter().enumerate() {
@@ -702,21 +903,25 @@ async fn multi_thread_download_impl(
 
         let client = client.clone();
         let url = url.clone();
+        let auth = config.auth.clone();
         let file = file.clone();
         let semaphore = semaphore.clone();
         let progress_tx = progress_tx.clone();
         let worker_tx = worker_tx.clone();
+        let error_count = error_count.clone();
 
         let task = tokio::spawn(async move {
             let _permit = semaphore.acquire().await?;
             download_chunk(
                 &client,
                 &url,
+                &auth,
                 file,
                 worker,
                 i,
                 progress_tx,
                 worker_tx,
+                error_count,
             ).await
         });
 
@@ -739,6 +944,11 @@ async fn multi_thread_download_impl(
         }
     }
 
+    if let Some(handle) = tuner_handle {
+        handle.abort();
+    }
+    semaphore.close();
+
     if !download_errors.is_empty() {
         return Err(anyhow::anyhow!("部分下载任务失败: {:?}", download_errors));
     }
@@ -766,6 +976,170 @@ async fn multi_thread_download_impl(
     Ok(file_path.display().to_string())
 }
 
+// 自动模式连接数调节：吞吐量持续上升时增加连接，出现错误或速度回落时减少连接
+// 自动模式下每个调节周期的决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TuneAction {
+    Keep,
+    Increase(usize),
+    Decrease(usize),
+}
+
+// 自动调节连接数的状态：速度提升就继续增加连接，出错或速度回落时减少，
+// 冷却和稳定一段时间后重新探测，网络好转时仍能增加连接
+#[derive(Debug)]
+struct ConnectionTuner {
+    active: usize,
+    max_connections: usize,
+    best_speed: u64,
+    last_errors: u64,
+    last_step: usize,
+    cooldown: u32,
+    stable_ticks: u32,
+}
+
+impl ConnectionTuner {
+    fn new(initial: usize, max_connections: usize) -> Self {
+        Self {
+            active: initial,
+            max_connections,
+            best_speed: 0,
+            last_errors: 0,
+            last_step: 0,
+            cooldown: 0,
+            stable_ticks: 0,
+        }
+    }
+
+    fn grow(&mut self) -> TuneAction {
+        if self.active >= self.max_connections {
+            return TuneAction::Keep;
+        }
+        let step = AUTO_CONNECTION_STEP.min(self.max_connections - self.active);
+        self.active += step;
+        self.last_step = step;
+        self.stable_ticks = 0;
+        TuneAction::Increase(step)
+    }
+
+    fn shrink_to(&mut self, target: usize) -> TuneAction {
+        let target = target.max(AUTO_MIN_CONNECTIONS).min(self.active);
+        let removed = self.active - target;
+        self.active = target;
+        self.last_step = 0;
+        self.stable_ticks = 0;
+        self.cooldown = AUTO_COOLDOWN_TICKS;
+        if removed > 0 {
+            TuneAction::Decrease(removed)
+        } else {
+            TuneAction::Keep
+        }
+    }
+
+    // 根据本周期的速度和累计错误数决定连接数的变化
+    fn next(&mut self, speed: u64, errors: u64) -> TuneAction {
+        const GROWTH_THRESHOLD: f64 = 1.10; // 速度提升超过 10% 视为有效
+        const DROP_THRESHOLD: f64 = 0.85; // 速度回落超过 15% 视为过载
+
+        if errors > self.last_errors {
+            // 服务器开始报错，连接数减半，冷却后重新测量速度
+            self.last_errors = errors;
+            self.best_speed = 0;
+            return self.shrink_to(self.active / 2);
+        }
+
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            if self.cooldown == 0 {
+                self.best_speed = speed;
+            }
+            return TuneAction::Keep;
+        }
+
+        let speed_f = speed as f64;
+        let best_f = self.best_speed as f64;
+        if speed_f > best_f * GROWTH_THRESHOLD {
+            self.best_speed = speed;
+            return self.grow();
+        }
+        if speed_f < best_f * DROP_THRESHOLD {
+            if self.last_step > 0 {
+                // 上次增加的连接使速度下降，撤回
+                return self.shrink_to(self.active - self.last_step);
+            }
+            // 网络整体变慢，以当前速度为基准，恢复时可以继续增加
+            self.best_speed = speed;
+        }
+
+        // 速度稳定，持续一段时间后再试探增加连接
+        self.last_step = 0;
+        self.stable_ticks += 1;
+        if self.stable_ticks >= AUTO_PROBE_TICKS {
+            return self.grow();
+        }
+        TuneAction::Keep
+    }
+}
+
+async fn auto_tune_connections(
+    semaphore: Arc<Semaphore>,
+    current_speed: Arc<AtomicU64>,
+    error_count: Arc<AtomicU64>,
+    initial: usize,
+    max_connections: usize,
+) {
+    let mut tuner = ConnectionTuner::new(initial, max_connections);
+    // 尚未回收的许可数，正在运行的分块归还许可后再逐个回收
+    let mut pending_reduction = 0usize;
+
+    let mut ticker = interval(AUTO_TUNE_INTERVAL);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        reduce_permits(&semaphore, &mut pending_reduction);
+
+        let speed = current_speed.load(Ordering::Relaxed);
+        let errors = error_count.load(Ordering::Relaxed);
+        match tuner.next(speed, errors) {
+            TuneAction::Keep => {}
+            TuneAction::Increase(step) => {
+                // 先抵消尚未回收的许可，剩余部分再新增
+                let restored = step.min(pending_reduction);
+                pending_reduction -= restored;
+                semaphore.add_permits(step - restored);
+                eprintln!(
+                    "自动模式: 速度 {:.2} MB/s，连接数增加到 {}",
+                    speed as f64 / 1024.0 / 1024.0,
+                    tuner.active
+                );
+            }
+            TuneAction::Decrease(removed) => {
+                eprintln!(
+                    "自动模式: 检测到错误或速度回落，连接数 {} -> {}",
+                    tuner.active + removed,
+                    tuner.active
+                );
+                pending_reduction += removed;
+                reduce_permits(&semaphore, &mut pending_reduction);
+            }
+        }
+    }
+}
+
+// 回收当前空闲的信号量许可，不等待正在运行的分块，未回收的部分留到下次调节
+fn reduce_permits(semaphore: &Semaphore, pending: &mut usize) {
+    while *pending > 0 {
+        match semaphore.try_acquire() {
+            Ok(permit) => {
+                permit.forget();
+                *pending -= 1;
+            }
+            Err(_) => break,
+        }
+    }
+}
+
 // 单线程下载实现（增强版）
 async fn single_thread_download_impl(
     config: DownloadConfig,
@@ -797,8 +1171,9 @@ async fn single_thread_download_attempt(
     url: &Url,
     file_path: &Path,
 ) -> Result<String> {
-    let response = client
-        .get(url.as_str())
+    let response = config
+        .auth
+        .apply(client.get(url.as_str()), url)
         .timeout(Duration::from_secs(300))
         .send()
         .await?;
@@ -873,7 +1248,7 @@ async fn single_thread_download_attempt(
                         };
                         emit_download_event(&config, DownloadEvent::Progress(info));
                     }
-                    DownloadEventType::UpdateDownload => {
+                    DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
                         let status = DownloadStatus {
                             progress: progress as u64,
                             speed: format!("{:.2}", display_speed),
@@ -905,7 +1280,7 @@ async fn single_thread_download_attempt(
             };
             emit_download_event(&config, DownloadEvent::Progress(final_info));
         }
-        DownloadEventType::UpdateDownload => {
+        DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
             let final_status = DownloadStatus {
                 progress: 100,
                 speed: "0.00".to_string(),
@@ -917,18 +1292,221 @@ async fn single_thread_download_attempt(
     Ok(file_path.display().to_string())
 }
 
+// 发送进度事件（本地复制使用）
+fn emit_progress(config: &DownloadConfig, downloaded: u64, total: u64, speed: f64) {
+    let progress = if total > 0 {
+        (downloaded as f64 / total as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    match config.event_type {
+        DownloadEventType::FileDownload | DownloadEventType::PluginDownload => {
+            let info = DownloadInfo {
+                progress: format!("{:.1}%", progress),
+                speed: format!("{:.2}MB/s", speed),
+                downloading: true,
+            };
+            emit_download_event(config, DownloadEvent::Progress(info));
+        }
+        DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
+            let status = DownloadStatus {
+                progress: progress as u64,
+                speed: format!("{:.2}", speed),
+            };
+            emit_download_event(config, DownloadEvent::UpdateProgress(status));
+        }
+    }
+}
+
+// 发送完成事件（本地复制使用）
+fn emit_finished(config: &DownloadConfig) {
+    match config.event_type {
+        DownloadEventType::FileDownload | DownloadEventType::PluginDownload => {
+            let final_info = DownloadInfo {
+                progress: "100%".to_string(),
+                speed: "0.00MB/s".to_string(),
+                downloading: false,
+            };
+            emit_download_event(config, DownloadEvent::Progress(final_info));
+        }
+        DownloadEventType::UpdateDownload | DownloadEventType::UpdatePrefetch => {
+            let final_status = DownloadStatus {
+                progress: 100,
+                speed: "0.00".to_string(),
+            };
+            emit_download_event(config, DownloadEvent::UpdateProgress(final_status));
+        }
+    }
+}
+
+// 校验下载结果，哈希不符时删除文件，避免下次被当作已完成的文件续传
+fn verify_sha256(file_path: &Path, expected: &str) -> Result<()> {
+    let actual = sha256_file(file_path)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        std::fs::remove_file(file_path).ok();
+        anyhow::bail!("文件哈希不匹配：期望 {}，实际 {}", expected, actual);
+    }
+    Ok(())
+}
+
+// 本地/网络共享文件复制实现（支持断点续传）
+async fn local_copy_impl(
+    config: DownloadConfig,
+    source_path: &Path,
+    file_path: &Path,
+    file_size: u64,
+) -> Result<String> {
+    let source_path = source_path.to_path_buf();
+    let file_path = file_path.to_path_buf();
+
+    // 文件读写和计算哈希都是阻塞操作，放到后台线程，避免占用异步运行时
+    tokio::task::spawn_blocking(move || {
+        local_copy_blocking(&config, &source_path, &file_path, file_size)?;
+
+        // 与 HTTP 下载一样校验内容：有期望哈希时对比期望值，否则对比源文件
+        let expected = match &config.expected_sha256 {
+            Some(expected) => expected.clone(),
+            None => sha256_file(&source_path)?,
+        };
+        verify_sha256(&file_path, &expected)?;
+
+        emit_finished(&config);
+        Ok(file_path.display().to_string())
+    })
+    .await?
+}
+
+fn local_copy_blocking(
+    config: &DownloadConfig,
+    source_path: &Path,
+    file_path: &Path,
+    file_size: u64,
+) -> Result<()> {
+    use std::io::Read;
+
+    let state_file = file_path.with_extension("download");
+
+    // 复用多线程下载的状态文件格式，只记录一个 worker
+    let mut worker = if state_file.exists() && file_path.exists() {
+        match load_download_state(&state_file) {
+            Ok(saved) if saved.len() == 1 && saved[0].end_pos == file_size => {
+                eprintln!("从状态文件恢复复制进度");
+                saved[0].clone()
+            }
+            _ => create_workers(file_size, 1).remove(0),
+        }
+    } else {
+        create_workers(file_size, 1).remove(0)
+    };
+
+    let mut source = File::open(source_path)
+        .map_err(|e| anyhow::anyhow!("无法打开源文件 {}: {}", source_path.display(), e))?;
+    let mut target = OpenOptions::new()
+        .write(true)
+        .create(true)
+        // 续传时保留已复制的部分
+        .truncate(false)
+        .open(file_path)?;
+
+    if worker.current_pos == 0 {
+        target.set_len(0)?;
+    }
+    source.seek(SeekFrom::Start(worker.current_pos))?;
+    target.seek(SeekFrom::Start(worker.current_pos))?;
+
+    const BUFFER_SIZE: usize = 1024 * 1024; // 1MB 缓冲区
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut speed_history: Vec<(Instant, u64)> = Vec::new();
+    const SPEED_WINDOW: Duration = Duration::from_secs(2);
+    let mut last_update = Instant::now();
+    let mut last_save = Instant::now();
+
+    loop {
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        target.write_all(&buffer[..read])?;
+        worker.current_pos += read as u64;
+
+        let now = Instant::now();
+        speed_history.push((now, worker.current_pos));
+        speed_history.retain(|(t, _)| now.duration_since(*t) < SPEED_WINDOW);
+
+        if now.duration_since(last_update) >= Duration::from_millis(250) {
+            let speed = match speed_history.first() {
+                Some(oldest) if speed_history.len() >= 2 => {
+                    let time_diff = now.duration_since(oldest.0).as_secs_f64();
+                    if time_diff > 0.0 {
+                        (worker.current_pos - oldest.1) as f64 / time_diff / (1024.0 * 1024.0)
+                    } else {
+                        0.0
+                    }
+                }
+                _ => 0.0,
+            };
+            emit_progress(config, worker.current_pos, file_size, speed);
+            last_update = now;
+        }
+
+        if last_save.elapsed() >= Duration::from_secs(5) {
+            target.flush()?;
+            save_download_state(&state_file, std::slice::from_ref(&worker)).ok();
+            last_save = Instant::now();
+        }
+    }
+
+    target.flush()?;
+    target.sync_all()?;
+
+    // 验证文件完整性
+    let metadata = target.metadata()?;
+    if metadata.len() != file_size {
+        save_download_state(&state_file, std::slice::from_ref(&worker)).ok();
+        anyhow::bail!("文件大小不匹配：期望 {} 字节，实际 {} 字节", file_size, metadata.len());
+    }
+
+    std::fs::remove_file(&state_file).ok();
+    Ok(())
+}
+
 // 通用下载接口
-pub async fn download(config: DownloadConfig) -> Result<String> {
-    let url = Url::parse(&config.url)?;
+pub async fn download(mut config: DownloadConfig) -> Result<String> {
+    let source = parse_download_source(&config.url)?;
     let save_path = &config.save_path;
 
     if let Some(parent) = save_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
+    let mut url = match source {
+        DownloadSource::Http(url) => url,
+        DownloadSource::Local(source_path) => {
+            let (filename, file_size) = local_file_info(&source_path)?;
+            let file_path = if save_path.is_dir() {
+                save_path.join(&filename)
+            } else {
+                save_path.to_path_buf()
+            };
+
+            if matches!(config.event_type, DownloadEventType::FileDownload) {
+                emit_progress(&config, 0, file_size, 0.0);
+            }
+
+            eprintln!("使用本地复制模式: {}", source_path.display());
+            return local_copy_impl(config, &source_path, &file_path, file_size).await;
+        }
+    };
+
+    // URL 中的用户名密码转为认证信息，避免出现在日志和错误信息中
+    config.auth.take_url_credentials(&mut url);
+
     let client = build_client()?;
 
-    let (final_url, filename, file_size, supports_range) = get_file_info(&client, &url).await?;
+    let (final_url, filename, file_size, supports_range) =
+        get_file_info(&client, &url, &config.auth).await?;
 
     let final_filename = if save_path.is_dir() {
         filename
@@ -957,12 +1535,37 @@ pub async fn download(config: DownloadConfig) -> Result<String> {
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
 
-    if !supports_range || file_size == 0 || config.thread_count == 1 {
+    let expected_sha256 = config.expected_sha256.clone();
+    let downloaded = if !supports_range || file_size == 0 || config.thread_count == 1 {
         eprintln!("使用单线程下载模式");
-        return single_thread_download_impl(config, &client, &final_url, &file_path).await;
+        single_thread_download_impl(config, &client, &final_url, &file_path).await?
+    } else {
+        multi_thread_download(config, &client, &final_url, &file_path, file_size).await?
+    };
+
+    if let Some(expected) = expected_sha256 {
+        let path = file_path.clone();
+        tokio::task::spawn_blocking(move || verify_sha256(&path, &expected)).await??;
     }
+    Ok(downloaded)
+}
 
-    eprintln!("使用多线程下载模式，线程数: {}", config.thread_count);
+async fn multi_thread_download(
+    config: DownloadConfig,
+    client: &Client,
+    final_url: &Url,
+    file_path: &Path,
+    file_size: u64,
+) -> Result<String> {
+    // 自动模式预先切分为较多分块，便于调节任务按需增减并发连接
+    let chunk_count = if config.thread_count == AUTO_THREAD_COUNT {
+        let chunks = (file_size / AUTO_MIN_CHUNK_SIZE).clamp(1, AUTO_MAX_CONNECTIONS as u64);
+        eprintln!("使用多线程下载模式，自动调整连接数，分块数: {}", chunks);
+        chunks as u16
+    } else {
+        eprintln!("使用多线程下载模式，线程数: {}", config.thread_count);
+        config.thread_count
+    };
     
     let state_file = file_path.with_extension("download");
     let workers = if state_file.exists() {
@@ -973,18 +1576,18 @@ pub async fn download(config: DownloadConfig) -> Result<String> {
             }
             Err(e) => {
                 eprintln!("加载状态文件失败: {}，重新开始下载", e);
-                create_workers(file_size, config.thread_count)
+                create_workers(file_size, chunk_count)
             }
         }
     } else {
-        create_workers(file_size, config.thread_count)
+        create_workers(file_size, chunk_count)
     };
 
     multi_thread_download_impl(
         config,
-        &client,
-        &final_url,
-        &file_path,
+        client,
+        final_url,
+        file_path,
         file_size,
         workers,
     ).await
@@ -1005,6 +1608,8 @@ pub async fn download_file_with_progress(
         thread_count,
         event_type: DownloadEventType::FileDownload,
         app_handle: Some(app),
+        auth: MirrorAuthSet::load(),
+        expected_sha256: None,
     };
 
     download(config).await
@@ -1015,10 +1620,11 @@ pub async fn download_update_package(
     url: String,
     save_dir: PathBuf,
     thread_count: u16,
+    event_type: DownloadEventType,
 ) -> Result<String> {
     // 重置下载状态
     {
-        let mut status = UPDATE_DOWNLOAD_STATUS.lock().unwrap();
+        let mut status = update_status_slot(&event_type).lock().unwrap();
         *status = Some(DownloadStatus {
             progress: 0,
             speed: "0.00".to_string(),
@@ -1029,8 +1635,10 @@ pub async fn download_update_package(
         url,
         save_path: save_dir,
         thread_count,
-        event_type: DownloadEventType::UpdateDownload,
+        event_type,
         app_handle: None,
+        auth: MirrorAuthSet::load(),
+        expected_sha256: None,
     };
 
     download(config).await
@@ -1048,6 +1656,8 @@ pub async fn download_plugin_file(
         thread_count,
         event_type: DownloadEventType::PluginDownload,
         app_handle: None,
+        auth: MirrorAuthSet::load(),
+        expected_sha256: None,
     };
 
     download(config).await
@@ -1056,4 +1666,76 @@ pub async fn download_plugin_file(
 // 获取更新下载状态
 pub fn get_update_download_status() -> Option<DownloadStatus> {
     UPDATE_DOWNLOAD_STATUS.lock().unwrap().clone()
+}
+
+// 标记更新下载完成：使用已预下载的更新包或增量文件时没有下载过程，界面据此开始安装
+pub fn mark_update_download_complete(event_type: &DownloadEventType) {
+    *update_status_slot(event_type).lock().unwrap() = Some(DownloadStatus {
+        progress: 100,
+        speed: "0.00".to_string(),
+    });
+}
+
+// 获取后台预下载状态
+pub fn get_prefetch_download_status() -> Option<DownloadStatus> {
+    PREFETCH_DOWNLOAD_STATUS.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionTuner, TuneAction, AUTO_COOLDOWN_TICKS, AUTO_PROBE_TICKS};
+
+    #[test]
+    fn grows_while_speed_improves() {
+        let mut tuner = ConnectionTuner::new(4, 64);
+        assert_eq!(tuner.next(100, 0), TuneAction::Increase(4));
+        assert_eq!(tuner.next(150, 0), TuneAction::Increase(4));
+        assert_eq!(tuner.active, 12);
+    }
+
+    #[test]
+    fn reverts_a_step_that_slowed_down_and_grows_again_after_cooldown() {
+        let mut tuner = ConnectionTuner::new(4, 64);
+        tuner.next(100, 0);
+        assert_eq!(tuner.next(50, 0), TuneAction::Decrease(4));
+        assert_eq!(tuner.active, 4);
+
+        for _ in 0..AUTO_COOLDOWN_TICKS {
+            assert_eq!(tuner.next(500, 0), TuneAction::Keep);
+        }
+        // 冷却结束后网络好转，继续增加连接
+        assert_eq!(tuner.next(800, 0), TuneAction::Increase(4));
+    }
+
+    #[test]
+    fn halves_on_errors_and_recovers() {
+        let mut tuner = ConnectionTuner::new(16, 64);
+        assert_eq!(tuner.next(100, 3), TuneAction::Decrease(8));
+        assert_eq!(tuner.active, 8);
+        for _ in 0..AUTO_COOLDOWN_TICKS {
+            tuner.next(100, 3);
+        }
+        assert_eq!(tuner.next(200, 3), TuneAction::Increase(4));
+    }
+
+    #[test]
+    fn probes_again_after_a_stable_interval() {
+        let mut tuner = ConnectionTuner::new(4, 64);
+        tuner.next(100, 0);
+        for _ in 0..AUTO_PROBE_TICKS - 1 {
+            assert_eq!(tuner.next(100, 0), TuneAction::Keep);
+        }
+        assert_eq!(tuner.next(100, 0), TuneAction::Increase(4));
+    }
+
+    #[test]
+    fn respects_connection_limits() {
+        let mut tuner = ConnectionTuner::new(6, 8);
+        assert_eq!(tuner.next(100, 0), TuneAction::Increase(2));
+        assert_eq!(tuner.next(200, 0), TuneAction::Keep);
+        assert_eq!(tuner.next(200, 1), TuneAction::Decrease(4));
+        let mut tuner = ConnectionTuner::new(2, 8);
+        assert_eq!(tuner.next(100, 1), TuneAction::Keep);
+        assert_eq!(tuner.active, 2);
+    }
 }
\ No newline at end of file
@@ -3,19 +3,57 @@
     windows_subsystem = "windows"
 )]
 
+mod automation;
+mod cli;
+mod credentials;
+mod delta_update;
 mod download;
+mod paths;
+mod plugin_batch;
+mod plugin_catalog;
+mod plugin_export;
+mod plugin_inspect;
+mod plugin_library;
+mod plugin_manifest;
+mod plugin_profiles;
+mod plugin_recycle;
 mod plugins;
+mod signature;
+mod single_instance;
+mod update_helper;
+mod update_prefetch;
+mod update_service;
 mod updater;
 mod usb_api;
 
-use plugins::{disable_plugin, download_plugin, enable_plugin, get_plugin_files, update_plugin};
+use plugins::{
+    apply_plugin_plan, cleanup_duplicate_plugins, disable_plugin, download_plugin, enable_plugin,
+    find_duplicate_plugins, get_plugin_files, plan_plugin_disable, plan_plugin_enable,
+    plan_plugin_install, uninstall_plugin, uninstall_plugins, update_plugin,
+};
 use tauri::Manager;
-use updater::{download_update, get_app_download_status, install_update};
+use updater::{
+    download_update, get_app_download_status, get_update_result, install_update,
+    inspect_update_package, install_update_from_file, rollback_update,
+};
 use std::process::Command;
 use std::path::Path;
 
 fn main() {
+    // 带子命令启动时以命令行模式运行，不打开窗口；作为更新助手启动时只执行更新
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(code) = update_helper::run_from_args(&args) {
+        std::process::exit(code);
+    }
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    // 单实例插件需要最先注册，重复启动时把参数转发给已运行的实例后直接退出
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(
+            single_instance::on_second_instance,
+        ))
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -23,11 +61,46 @@ fn main() {
             download_update,
             get_app_download_status,
             install_update,
+            inspect_update_package,
+            install_update_from_file,
+            rollback_update,
+            get_update_result,
+            update_service::check_app_update,
+            update_service::get_update_settings,
+            update_service::set_update_settings,
             download_plugin,
             update_plugin,
             get_plugin_files,
             enable_plugin,
             disable_plugin,
+            plan_plugin_install,
+            plan_plugin_enable,
+            plan_plugin_disable,
+            apply_plugin_plan,
+            find_duplicate_plugins,
+            cleanup_duplicate_plugins,
+            uninstall_plugin,
+            uninstall_plugins,
+            plugin_batch::install_plugin_batch,
+            plugin_batch::get_plugin_batch_status,
+            plugin_export::export_plugin_set,
+            plugin_export::import_plugin_set,
+            plugin_inspect::inspect_plugin,
+            plugin_inspect::find_damaged_plugins,
+            plugin_library::get_plugin_library,
+            plugin_library::download_to_plugin_library,
+            plugin_library::add_drive_plugins_to_library,
+            plugin_library::sync_plugin_library,
+            plugin_library::remove_library_plugin,
+            plugin_profiles::list_plugin_profiles,
+            plugin_profiles::save_plugin_profile,
+            plugin_profiles::delete_plugin_profile,
+            plugin_profiles::apply_plugin_profile,
+            plugin_recycle::list_recycled_plugins,
+            plugin_recycle::restore_plugin,
+            plugin_recycle::empty_plugin_recycle,
+            plugin_catalog::get_plugin_catalog,
+            plugin_catalog::check_plugin_updates,
             check_boot_drive,
             check_all_boot_drives,
             get_all_drives,
@@ -45,11 +118,30 @@ fn main() {
             check_mica_support,
             check_transparency_enabled,
             open_devtools,
-            exit_app
+            exit_app,
+            credentials::list_mirror_credentials,
+            credentials::set_mirror_credential,
+            credentials::remove_mirror_credential,
+            automation::get_automation_status,
+            automation::set_automation_enabled,
+            automation::regenerate_automation_token
         ])
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
             window.hide().unwrap();
+
+            // 命令行正在修改启动盘时不能同时打开主程序
+            if !single_instance::hold_instance_lock() {
+                use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+                app.dialog()
+                    .message("命令行模式正在执行操作，请等待其完成后再打开")
+                    .title("Cloud-PE One 正在运行")
+                    .kind(MessageDialogKind::Warning)
+                    .blocking_show();
+
+                std::process::exit(0);
+            }
     
             let exe_path = std::env::current_exe().map_err(|e| format!("获取exe路径失败: {}", e))?;
             let app_dir = exe_path.parent().ok_or("无法获取exe父目录")?.to_path_buf();
@@ -68,6 +160,11 @@ fn main() {
             }
     
             window.show().unwrap();
+            updater::report_update_health();
+            update_helper::cleanup_stale_helpers();
+
+            automation::init(app.handle());
+            update_prefetch::start_prefetch();
 
             #[cfg(debug_assertions)]
             {
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use std::fs;
+use std::path::PathBuf;
+
+// 用户数据目录（%APPDATA%\Cloud-PE One），不可用时回退到程序目录
+pub fn app_data_dir() -> Result<PathBuf> {
+    let base = match std::env::var("APPDATA") {
+        Ok(dir) => PathBuf::from(dir).join("Cloud-PE One"),
+        Err(_) => app_install_dir()?,
+    };
+
+    fs::create_dir_all(&base)?;
+    Ok(base)
+}
+
+// 程序安装目录（exe 所在目录）
+pub fn app_install_dir() -> Result<PathBuf> {
+    let exe_path = std::env::current_exe()?;
+    Ok(exe_path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("无法获取exe父目录"))?
+        .to_path_buf())
+}
+
+// 路径所在磁盘的可用空间（字节），无法获取时返回 None
+#[cfg(target_os = "windows")]
+pub fn available_space(path: &std::path::Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::fileapi::GetDiskFreeSpaceExW;
+    use winapi::um::winnt::ULARGE_INTEGER;
+
+    // 目录可能尚未创建，向上找到第一个存在的目录
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let wide: Vec<u16> = existing.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    unsafe {
+        let mut available: ULARGE_INTEGER = std::mem::zeroed();
+        let ok = GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        if ok == 0 {
+            return None;
+        }
+        Some(*available.QuadPart())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn available_space(_path: &std::path::Path) -> Option<u64> {
+    None
+}
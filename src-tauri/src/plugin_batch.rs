@@ -0,0 +1,464 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::command;
+
+use crate::automation;
+use crate::delta_update::sha256_file;
+use crate::download::download_plugin_file;
+use crate::plugin_inspect::inspect_package;
+use crate::plugin_manifest::{load_plugin_manifest, sidecar_path};
+use crate::plugins::{ce_apps_path, PlannedInstall, PluginFileGuard};
+
+// 批量安装先把所有插件下载到启动盘上的暂存目录，全部校验通过后再统一替换，
+// 替换过程中任何一步失败都会按日志把文件恢复原状
+const STAGING_DIR: &str = "cloud-pe\\staging";
+const JOURNAL_FILE: &str = "journal.json";
+const BACKUP_DIR: &str = "backup";
+
+lazy_static::lazy_static! {
+    static ref BATCH_STATUS: Mutex<Option<PluginBatchStatus>> = Mutex::new(None);
+    // 同一时间只允许一个批量安装，避免清理暂存目录时误删正在使用的目录
+    static ref BATCH_RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchStage {
+    Downloading,
+    Verifying,
+    Swapping,
+    RollingBack,
+    Finished,
+    Failed,
+}
+
+// 批量安装的整体进度，前端轮询获取
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginBatchStatus {
+    pub stage: BatchStage,
+    pub total: usize,
+    pub completed: usize,
+    pub current: Option<String>,
+    pub progress: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginBatchResult {
+    pub installed: Vec<String>,
+    // 被新版本替换掉的旧文件
+    pub replaced: Vec<String>,
+    pub downloaded_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileMove {
+    from: PathBuf,
+    to: PathBuf,
+}
+
+// 替换日志：记录每一次移动，程序中途退出时下次可以据此回滚
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SwapJournal {
+    moves: Vec<FileMove>,
+    committed: bool,
+}
+
+fn staging_root(drive_letter: &str) -> PathBuf {
+    PathBuf::from(format!("{}\\{}", drive_letter, STAGING_DIR))
+}
+
+fn update_status(update: impl FnOnce(&mut PluginBatchStatus)) {
+    if let Some(status) = BATCH_STATUS.lock().unwrap().as_mut() {
+        update(status);
+        automation::notify("plugin.batchProgress", status);
+    }
+}
+
+fn read_journal(dir: &Path) -> Option<SwapJournal> {
+    let content = fs::read_to_string(dir.join(JOURNAL_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+struct Swap {
+    dir: PathBuf,
+    journal: SwapJournal,
+}
+
+impl Swap {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            journal: SwapJournal::default(),
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(
+            self.dir.join(JOURNAL_FILE),
+            serde_json::to_string_pretty(&self.journal)?,
+        )?;
+        Ok(())
+    }
+
+    fn move_file(&mut self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to).map_err(|e| anyhow::anyhow!("移动 {} 失败: {}", from.display(), e))?;
+        self.journal.moves.push(FileMove {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+        self.save()
+    }
+
+    // 倒序撤销移动，每撤销一步就更新日志，失败时保留剩余部分供下次继续
+    fn roll_back(&mut self) -> Result<()> {
+        while let Some(last) = self.journal.moves.last() {
+            if last.to.exists() {
+                fs::rename(&last.to, &last.from)
+                    .map_err(|e| anyhow::anyhow!("恢复 {} 失败: {}", last.from.display(), e))?;
+            }
+            self.journal.moves.pop();
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        self.journal.committed = true;
+        self.save()
+    }
+}
+
+// 处理上次中途退出留下的暂存目录：未完成的替换先回滚，再删除目录
+fn recover_interrupted(drive_letter: &str) {
+    let entries = match fs::read_dir(staging_root(drive_letter)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        if let Some(journal) = read_journal(&dir) {
+            if !journal.committed && !journal.moves.is_empty() {
+                println!("发现未完成的插件批量安装，正在回滚: {}", dir.display());
+                let mut swap = Swap {
+                    dir: dir.clone(),
+                    journal,
+                };
+                if let Err(e) = swap.roll_back() {
+                    println!("回滚失败，保留暂存目录: {}", e);
+                    continue;
+                }
+            }
+        }
+        fs::remove_dir_all(&dir).ok();
+    }
+}
+
+// 插件文件名来自前端、导入的清单或插件市场，只允许 ce-apps 下的单个 .ce/.CBK 文件
+pub(crate) fn validate_plugin_file_name(file_name: &str) -> Result<(), String> {
+    let invalid = || format!("无效的插件文件名: {}", file_name);
+    if file_name.is_empty()
+        || file_name.contains(['\\', '/', ':', '*', '?', '"', '<', '>', '|'])
+        || file_name.chars().any(|c| c.is_control())
+        || file_name.starts_with('.')
+        || file_name.ends_with(['.', ' '])
+    {
+        return Err(invalid());
+    }
+    let path = Path::new(file_name);
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(name)), None) if name == file_name => {}
+        _ => return Err(invalid()),
+    }
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if extension != "ce" && extension != "cbk" {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+// file:// 地址表示从本机插件库复制，不需要下载
+fn local_source(url: &str) -> Option<PathBuf> {
+    url::Url::parse(url)
+        .ok()
+        .filter(|u| u.scheme() == "file")
+        .and_then(|u| u.to_file_path().ok())
+}
+
+// 校验下载的文件：哈希（已知时）、容器是否完整、能否读出插件信息
+pub(crate) fn verify_staged(path: &Path, item: &PlannedInstall) -> Result<()> {
+    if fs::metadata(path)?.len() == 0 {
+        anyhow::bail!("文件为空");
+    }
+
+    if let Some(expected) = &item.sha256 {
+        let actual = sha256_file(path)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!("哈希不匹配，期望 {}，实际 {}", expected, actual);
+        }
+    }
+
+    let inspection = inspect_package(path, false)?;
+    if let Some(problem) = inspection.problems.first() {
+        anyhow::bail!("{}", problem);
+    }
+
+    let (manifest, _) = load_plugin_manifest(path)?;
+    if !item.id.is_empty() && manifest.id != item.id {
+        anyhow::bail!("下载的插件 {} 与预期的 {} 不符", manifest.id, item.id);
+    }
+    Ok(())
+}
+
+// 把旧文件移到备份目录，再把暂存的新文件移入 ce-apps
+fn swap_all(
+    swap: &mut Swap,
+    dir_path: &Path,
+    items: &[PlannedInstall],
+    result: &mut PluginBatchResult,
+) -> Result<()> {
+    let backup = swap.dir.join(BACKUP_DIR);
+    fs::create_dir_all(&backup)?;
+
+    for (index, item) in items.iter().enumerate() {
+        update_status(|s| s.current = Some(item.name.clone()));
+        let target = dir_path.join(&item.file_name);
+
+        // 同名文件直接覆盖；旧版本文件连同附属清单一起移走
+        let mut displaced = vec![target.clone()];
+        if let Some(old_file) = &item.replaces {
+            if !old_file.eq_ignore_ascii_case(&item.file_name) {
+                let old_path = dir_path.join(old_file);
+                displaced.push(sidecar_path(&old_path));
+                displaced.push(old_path);
+            }
+        }
+        for path in displaced.iter().filter(|p| p.is_file()) {
+            let name = path.file_name().unwrap().to_string_lossy();
+            swap.move_file(path, &backup.join(format!("{}_{}", index, name)))?;
+            if item.replaces.as_deref().is_some_and(|old| name == old) {
+                result.replaced.push(name.to_string());
+            }
+        }
+
+        swap.move_file(&swap.dir.join(&item.file_name), &target)?;
+        result.installed.push(item.file_name.clone());
+    }
+    Ok(())
+}
+
+async fn run_batch(
+    dir_path: &Path,
+    staging: &Path,
+    items: &[PlannedInstall],
+    thread_count: u16,
+) -> Result<PluginBatchResult, String> {
+    let total = items.len();
+    let mut result = PluginBatchResult::default();
+
+    // 下载占整体进度的 90%
+    for (index, item) in items.iter().enumerate() {
+        update_status(|s| {
+            s.current = Some(item.name.clone());
+            s.completed = index;
+            s.progress = (index * 90 / total) as u64;
+        });
+        println!(
+            "下载插件 {} {} ({}/{})",
+            item.name,
+            item.version,
+            index + 1,
+            total
+        );
+        let staged = staging.join(&item.file_name);
+        match local_source(&item.url) {
+            Some(source) => fs::copy(&source, &staged)
+                .map(|_| ())
+                .map_err(|e| format!("复制插件 {} 失败: {}", item.name, e))?,
+            None => download_plugin_file(item.url.clone(), staged, thread_count)
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("下载插件 {} 失败: {}", item.name, e))?,
+        }
+    }
+
+    update_status(|s| {
+        s.stage = BatchStage::Verifying;
+        s.completed = total;
+        s.progress = 90;
+    });
+    for item in items {
+        update_status(|s| s.current = Some(item.name.clone()));
+        let staged = staging.join(&item.file_name);
+        verify_staged(&staged, item).map_err(|e| format!("插件 {} 校验失败: {}", item.name, e))?;
+        result.downloaded_bytes += fs::metadata(&staged).map(|m| m.len()).unwrap_or(0);
+    }
+
+    update_status(|s| {
+        s.stage = BatchStage::Swapping;
+        s.progress = 95;
+    });
+    let mut swap = Swap::new(staging);
+    if let Err(e) = swap_all(&mut swap, dir_path, items, &mut result) {
+        println!("替换插件失败，正在回滚: {}", e);
+        update_status(|s| s.stage = BatchStage::RollingBack);
+        return match swap.roll_back() {
+            Ok(()) => Err(format!("替换插件失败，已恢复原有插件: {}", e)),
+            Err(rollback_error) => Err(format!(
+                "替换插件失败: {}；恢复未完成: {}，将在下次安装时继续恢复",
+                e, rollback_error
+            )),
+        };
+    }
+    swap.commit().map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+// 批量安装或更新插件：全部下载并校验通过后才会改动 ce-apps
+pub async fn install_batch(
+    drive_letter: &str,
+    items: &[PlannedInstall],
+    thread_count: u16,
+) -> Result<PluginBatchResult, String> {
+    // 任何一个文件名无效都拒绝整个批次，避免写入或删除 ce-apps 以外的文件
+    for item in items {
+        validate_plugin_file_name(&item.file_name)?;
+        if let Some(old_file) = &item.replaces {
+            validate_plugin_file_name(old_file)?;
+        }
+    }
+
+    let dir_path = ce_apps_path(drive_letter)?;
+    let _running = BATCH_RUNNING
+        .try_lock()
+        .map_err(|_| "已有插件批量安装正在进行".to_string())?;
+    recover_interrupted(drive_letter);
+    if items.is_empty() {
+        return Ok(PluginBatchResult::default());
+    }
+
+    let mut names = HashSet::new();
+    if let Some(item) = items
+        .iter()
+        .find(|i| !names.insert(i.file_name.to_lowercase()))
+    {
+        return Err(format!("批量安装中存在重复的文件: {}", item.file_name));
+    }
+
+    let mut locked: Vec<PathBuf> = Vec::new();
+    for item in items {
+        locked.push(dir_path.join(&item.file_name));
+        if let Some(old_file) = &item.replaces {
+            if !old_file.eq_ignore_ascii_case(&item.file_name) {
+                locked.push(dir_path.join(old_file));
+            }
+        }
+    }
+    let locked: Vec<&Path> = locked.iter().map(|p| p.as_path()).collect();
+    let _guard = PluginFileGuard::acquire(&locked)?;
+
+    // 暂存目录与 ce-apps 在同一个盘上，替换时只需要重命名
+    let staging =
+        staging_root(drive_letter).join(chrono::Local::now().timestamp_millis().to_string());
+    fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+
+    *BATCH_STATUS.lock().unwrap() = Some(PluginBatchStatus {
+        stage: BatchStage::Downloading,
+        total: items.len(),
+        completed: 0,
+        current: None,
+        progress: 0,
+        error: None,
+    });
+
+    let result = run_batch(&dir_path, &staging, items, thread_count).await;
+    update_status(|s| {
+        s.current = None;
+        match &result {
+            Ok(_) => {
+                s.stage = BatchStage::Finished;
+                s.progress = 100;
+            }
+            Err(e) => {
+                s.stage = BatchStage::Failed;
+                s.error = Some(e.clone());
+            }
+        }
+    });
+
+    // 回滚未完成时保留暂存目录和日志，下次安装前继续恢复
+    let pending = read_journal(&staging)
+        .map(|j| !j.committed && !j.moves.is_empty())
+        .unwrap_or(false);
+    if !pending {
+        fs::remove_dir_all(&staging).ok();
+    }
+
+    if let Ok(result) = &result {
+        println!(
+            "批量安装完成: 安装 {} 个插件，替换 {} 个旧版本",
+            result.installed.len(),
+            result.replaced.len()
+        );
+    }
+    result
+}
+
+#[command]
+pub async fn install_plugin_batch(
+    drive_letter: String,
+    items: Vec<PlannedInstall>,
+    threads: Option<u32>,
+) -> Result<PluginBatchResult, String> {
+    install_batch(&drive_letter, &items, threads.unwrap_or(8) as u16).await
+}
+
+#[command]
+pub fn get_plugin_batch_status() -> Option<PluginBatchStatus> {
+    BATCH_STATUS.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_plugin_file_name;
+
+    #[test]
+    fn accepts_plugin_files() {
+        assert!(validate_plugin_file_name("7-Zip_23.01_Cloud-PE_压缩工具.ce").is_ok());
+        assert!(validate_plugin_file_name("Tool_1.0_Author_Desc.CBK").is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_and_other_extensions() {
+        for name in [
+            "",
+            "..",
+            "..\\..\\x.ce",
+            "../x.ce",
+            "C:\\Windows\\x.ce",
+            "C:x.ce",
+            "\\\\server\\share\\x.ce",
+            "/etc/x.ce",
+            "sub/x.ce",
+            "x.exe",
+            "x.ce.",
+            "x",
+        ] {
+            assert!(validate_plugin_file_name(name).is_err(), "{}", name);
+        }
+    }
+}
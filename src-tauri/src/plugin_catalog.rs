@@ -0,0 +1,283 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+
+use crate::paths::app_data_dir;
+use crate::plugin_batch::validate_plugin_file_name;
+use crate::plugin_manifest::{generate_plugin_id, parse_plugin_version};
+use crate::plugins::{ce_apps_path, scan_local_plugins, PlannedInstall};
+
+const CATALOG_API_URL: &str = "https://api.cloud-pe.cn/GetPlugins/";
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36 Edg/138.0.0.0";
+// 最近一次成功获取的插件市场数据，离线时使用
+const SNAPSHOT_FILE: &str = "plugin_catalog.json";
+
+// 插件市场中的单个插件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogPlugin {
+    pub name: String,
+    #[serde(default)]
+    pub size: String,
+    pub version: String,
+    pub author: String,
+    #[serde(default)]
+    pub describe: String,
+    #[serde(default)]
+    pub file: String,
+    pub link: String,
+}
+
+impl CatalogPlugin {
+    pub fn id(&self) -> String {
+        generate_plugin_id(&self.name, &self.author)
+    }
+
+    // 与插件市场页面下载时使用的文件名一致
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}.ce",
+            self.name, self.version, self.author, self.describe
+        )
+    }
+
+    // 转换为批量安装项，replaces 为需要替换的旧版本文件
+    pub fn planned_install(&self, replaces: Option<String>) -> PlannedInstall {
+        PlannedInstall {
+            id: self.id(),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            url: self.link.clone(),
+            file_name: self.file_name(),
+            replaces,
+            sha256: None,
+        }
+    }
+
+    // 文件名由服务器返回的字段拼接而成，含路径分隔符或保留字符的插件直接丢弃
+    fn is_valid(&self) -> bool {
+        validate_plugin_file_name(&self.file_name()).is_ok()
+            && !self.name.trim().is_empty()
+            && !self.author.trim().is_empty()
+            && !self.link.trim().is_empty()
+            && parse_plugin_version(&self.version).is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogCategory {
+    pub class: String,
+    #[serde(default)]
+    pub list: Vec<CatalogPlugin>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogResponse {
+    code: i64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    data: Vec<CatalogCategory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogSnapshot {
+    pub categories: Vec<CatalogCategory>,
+    pub fetched_at: i64,
+    // 是否来自本地快照（获取失败时）
+    #[serde(default)]
+    pub offline: bool,
+}
+
+impl CatalogSnapshot {
+    pub fn plugins(&self) -> impl Iterator<Item = &CatalogPlugin> {
+        self.categories.iter().flat_map(|c| c.list.iter())
+    }
+
+    // 同一插件在多个分类中出现时取最高版本
+    pub fn latest(&self, id: &str) -> Option<&CatalogPlugin> {
+        self.plugins()
+            .filter(|p| p.id() == id)
+            .max_by(|a, b| parse_plugin_version(&a.version).cmp(&parse_plugin_version(&b.version)))
+    }
+}
+
+// 已安装插件的可用更新
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginUpdate {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub enabled: bool,
+    pub current_version: String,
+    pub latest_version: String,
+    pub link: String,
+    pub file_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginUpdateReport {
+    pub updates: Vec<PluginUpdate>,
+    pub fetched_at: i64,
+    pub offline: bool,
+}
+
+fn snapshot_path() -> Result<PathBuf> {
+    Ok(app_data_dir()?.join(SNAPSHOT_FILE))
+}
+
+fn read_snapshot() -> Option<CatalogSnapshot> {
+    let content = fs::read_to_string(snapshot_path().ok()?).ok()?;
+    let mut snapshot: CatalogSnapshot = serde_json::from_str(&content).ok()?;
+    // 旧版本保存的快照可能含有未经校验的条目
+    for category in &mut snapshot.categories {
+        category.list.retain(|p| p.is_valid());
+    }
+    Some(snapshot)
+}
+
+fn write_snapshot(snapshot: &CatalogSnapshot) -> Result<()> {
+    fs::write(snapshot_path()?, serde_json::to_string(snapshot)?)?;
+    Ok(())
+}
+
+// 获取并校验插件市场数据，丢弃缺少必要字段的条目
+async fn fetch_catalog() -> Result<CatalogSnapshot> {
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .build()?;
+
+    let response: CatalogResponse = client
+        .get(CATALOG_API_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if response.code != 200 {
+        anyhow::bail!("API返回错误: {}", response.message);
+    }
+
+    let mut categories = response.data;
+    let mut dropped = 0;
+    for category in &mut categories {
+        let before = category.list.len();
+        category.list.retain(|p| p.is_valid());
+        dropped += before - category.list.len();
+    }
+    if dropped > 0 {
+        println!("插件市场中有 {} 个条目信息不完整，已忽略", dropped);
+    }
+
+    Ok(CatalogSnapshot {
+        categories,
+        fetched_at: chrono::Local::now().timestamp(),
+        offline: false,
+    })
+}
+
+// 优先在线获取并更新快照，失败时回退到最近一次成功的快照
+pub async fn load_catalog() -> Result<CatalogSnapshot> {
+    match fetch_catalog().await {
+        Ok(snapshot) => {
+            if let Err(e) = write_snapshot(&snapshot) {
+                println!("保存插件市场快照失败: {}", e);
+            }
+            Ok(snapshot)
+        }
+        Err(e) => {
+            println!("获取插件市场失败，使用本地快照: {}", e);
+            let mut snapshot =
+                read_snapshot().ok_or_else(|| anyhow::anyhow!("获取插件列表失败: {}", e))?;
+            snapshot.offline = true;
+            Ok(snapshot)
+        }
+    }
+}
+
+// cached 为 true 时只读取本地快照，不访问网络
+#[command]
+pub async fn get_plugin_catalog(cached: Option<bool>) -> Result<CatalogSnapshot, String> {
+    if cached.unwrap_or(false) {
+        let mut snapshot = read_snapshot().ok_or("没有可用的插件市场快照")?;
+        snapshot.offline = true;
+        return Ok(snapshot);
+    }
+    load_catalog().await.map_err(|e| e.to_string())
+}
+
+// 对比启动盘上的插件与插件市场，返回有新版本的插件
+#[command]
+pub async fn check_plugin_updates(
+    drive_letter: String,
+    cached: Option<bool>,
+) -> Result<PluginUpdateReport, String> {
+    let catalog = get_plugin_catalog(cached).await?;
+    let local = scan_local_plugins(&ce_apps_path(&drive_letter)?)?;
+
+    let mut updates = Vec::new();
+    for plugin in &local {
+        let latest = match catalog.latest(&plugin.manifest.id) {
+            Some(latest) => latest,
+            None => continue,
+        };
+        let newer = match (
+            plugin.manifest.semver(),
+            parse_plugin_version(&latest.version),
+        ) {
+            (Some(current), Some(available)) => available.cmp(&current) == Ordering::Greater,
+            _ => false,
+        };
+        if newer {
+            updates.push(PluginUpdate {
+                id: plugin.manifest.id.clone(),
+                name: plugin.manifest.name.clone(),
+                file: plugin.file.clone(),
+                enabled: plugin.enabled,
+                current_version: plugin.manifest.version.clone(),
+                latest_version: latest.version.clone(),
+                link: latest.link.clone(),
+                file_name: latest.file_name(),
+            });
+        }
+    }
+
+    Ok(PluginUpdateReport {
+        updates,
+        fetched_at: catalog.fetched_at,
+        offline: catalog.offline,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CatalogPlugin;
+
+    fn plugin(describe: &str) -> CatalogPlugin {
+        CatalogPlugin {
+            name: "7-Zip".to_string(),
+            size: String::new(),
+            version: "23.01".to_string(),
+            author: "Cloud-PE".to_string(),
+            describe: describe.to_string(),
+            file: String::new(),
+            link: "https://example.com/7zip.ce".to_string(),
+        }
+    }
+
+    #[test]
+    fn rejects_unsafe_file_names() {
+        assert!(plugin("压缩工具").is_valid());
+        for describe in ["..\\..\\evil", "a/b", "C:x", "a|b", "a\nb"] {
+            assert!(!plugin(describe).is_valid(), "{}", describe);
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+use crate::delta_update::sha256_file;
+use crate::download::download_plugin_file;
+use crate::plugin_batch::{install_batch, validate_plugin_file_name};
+use crate::plugin_catalog::load_catalog;
+use crate::plugin_manifest::{parse_plugin_version, read_embedded_manifest};
+use crate::plugins::{
+    ce_apps_path, disable_plugin, enable_plugin, scan_local_plugins, LocalPlugin, PlannedInstall,
+};
+
+// 插件清单格式版本，格式不兼容时递增
+const PLUGIN_SET_FORMAT: u32 = 1;
+
+// 导出的单个插件，导入时按 sha256 校验下载的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSetEntry {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    // 启用状态下的文件名（.ce）
+    pub file_name: String,
+    pub enabled: bool,
+    pub size: u64,
+    pub sha256: String,
+    // 插件市场中同一版本的下载地址，没有时只能使用目标盘上已有的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+}
+
+// 可以在多个启动盘之间共享的插件清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSetManifest {
+    pub format: u32,
+    pub exported_at: i64,
+    pub plugins: Vec<PluginSetEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSetImportResult {
+    pub installed: Vec<String>,
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+    // 目标盘上已有相同文件，不需要下载
+    pub unchanged: Vec<String>,
+    // 目标盘上没有且清单中没有下载地址的插件 ID
+    pub missing: Vec<String>,
+    // 下载地址不是 http(s)，需要用户允许后才会复制的插件 ID
+    pub blocked: Vec<String>,
+}
+
+fn enabled_file_name(file: &str) -> String {
+    Path::new(file)
+        .with_extension("ce")
+        .to_string_lossy()
+        .to_string()
+}
+
+fn same_version(a: &str, b: &str) -> bool {
+    match (parse_plugin_version(a), parse_plugin_version(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// 插件包可能很大，在后台线程计算哈希，避免阻塞异步运行时
+async fn hash_file(path: PathBuf) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || sha256_file(&path).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 导出启动盘上的全部插件及其启用状态
+#[command]
+pub async fn export_plugin_set(
+    drive_letter: String,
+    path: String,
+) -> Result<PluginSetManifest, String> {
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let local = scan_local_plugins(&dir_path)?;
+
+    // 插件市场不可用时仍然导出，只是清单中没有下载地址
+    let catalog = match load_catalog().await {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            println!("获取插件市场失败，导出的清单不含下载地址: {}", e);
+            None
+        }
+    };
+
+    let mut plugins = Vec::new();
+    for plugin in &local {
+        let file_path = dir_path.join(&plugin.file);
+        let link = catalog.as_ref().and_then(|catalog| {
+            catalog
+                .plugins()
+                .find(|p| {
+                    p.id() == plugin.manifest.id
+                        && same_version(&p.version, &plugin.manifest.version)
+                })
+                .map(|p| p.link.clone())
+        });
+        let sha256 = hash_file(file_path.clone())
+            .await
+            .map_err(|e| format!("计算 {} 的哈希失败: {}", plugin.file, e))?;
+
+        plugins.push(PluginSetEntry {
+            id: plugin.manifest.id.clone(),
+            name: plugin.manifest.name.clone(),
+            version: plugin.manifest.version.clone(),
+            author: plugin.manifest.author.clone(),
+            file_name: enabled_file_name(&plugin.file),
+            enabled: plugin.enabled,
+            size: fs::metadata(&file_path).map(|m| m.len()).unwrap_or(0),
+            sha256,
+            link,
+        });
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name).then(a.version.cmp(&b.version)));
+
+    let manifest = PluginSetManifest {
+        format: PLUGIN_SET_FORMAT,
+        exported_at: chrono::Local::now().timestamp(),
+        plugins,
+    };
+    let content = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("写入插件清单失败: {}", e))?;
+
+    let without_link = manifest.plugins.iter().filter(|p| p.link.is_none()).count();
+    println!(
+        "已导出 {} 个插件到 {}，其中 {} 个没有下载地址",
+        manifest.plugins.len(),
+        path,
+        without_link
+    );
+    Ok(manifest)
+}
+
+// 清单文件可能来自他人分享，规划安装前逐项校验
+fn validate_entry(entry: &PluginSetEntry) -> Result<(), String> {
+    if entry.id.trim().is_empty() || entry.version.trim().is_empty() {
+        return Err(format!("插件清单中的条目 {} 缺少 ID 或版本", entry.name));
+    }
+    if entry.sha256.len() != 64 || !entry.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("插件清单中 {} 的哈希无效", entry.name));
+    }
+    Ok(())
+}
+
+// 默认只允许从 http(s) 地址下载，本机路径、共享路径和 file:// 需要用户明确允许
+fn is_remote_link(link: &str) -> bool {
+    url::Url::parse(link)
+        .map(|u| u.scheme() == "http" || u.scheme() == "https")
+        .unwrap_or(false)
+}
+
+// 插件市场中没有的插件先下载到临时目录，按哈希校验后从内嵌清单得到文件名，
+// 不使用清单文件中记录的文件名
+async fn prefetch_entry(
+    temp_dir: &Path,
+    index: usize,
+    entry: &PluginSetEntry,
+    link: &str,
+    thread_count: u16,
+) -> Result<PlannedInstall, String> {
+    let temp_path = temp_dir.join(format!("{}.ce", index));
+    download_plugin_file(link.to_string(), temp_path.clone(), thread_count)
+        .await
+        .map_err(|e| format!("下载插件 {} 失败: {}", entry.name, e))?;
+
+    let actual = hash_file(temp_path.clone()).await?;
+    if !actual.eq_ignore_ascii_case(&entry.sha256) {
+        return Err(format!("插件 {} 的哈希与清单不符", entry.name));
+    }
+    let manifest = read_embedded_manifest(&temp_path)
+        .map_err(|e| format!("插件 {} 的内嵌清单无效: {}", entry.name, e))?
+        .ok_or_else(|| format!("插件 {} 没有内嵌清单，无法确定文件名", entry.name))?;
+    if manifest.id != entry.id || !same_version(&manifest.version, &entry.version) {
+        return Err(format!("插件 {} 的内容与清单记录不符", entry.name));
+    }
+
+    let file_name = format!(
+        "{}_{}_{}_{}.ce",
+        manifest.name, manifest.version, manifest.author, manifest.description
+    );
+    validate_plugin_file_name(&file_name)?;
+    let url = url::Url::from_file_path(&temp_path)
+        .map_err(|_| format!("无效的插件路径: {}", temp_path.display()))?;
+    Ok(PlannedInstall {
+        id: manifest.id,
+        name: manifest.name,
+        version: manifest.version,
+        url: url.to_string(),
+        file_name,
+        replaces: None,
+        sha256: Some(entry.sha256.clone()),
+    })
+}
+
+// 导入插件清单：下载目标盘上缺少的插件并校验哈希，再按清单设置启用状态。
+// exclusive 为 true 时禁用清单以外的插件；allow_local_sources 为 true 时允许从本机或共享路径复制
+#[command]
+pub async fn import_plugin_set(
+    drive_letter: String,
+    path: String,
+    exclusive: Option<bool>,
+    allow_local_sources: Option<bool>,
+    threads: Option<u32>,
+) -> Result<PluginSetImportResult, String> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取插件清单失败: {}", e))?;
+    let manifest: PluginSetManifest =
+        serde_json::from_str(&content).map_err(|e| format!("插件清单格式无效: {}", e))?;
+    if manifest.format > PLUGIN_SET_FORMAT {
+        return Err("插件清单版本过新，请先更新客户端".to_string());
+    }
+    for entry in &manifest.plugins {
+        validate_entry(entry)?;
+    }
+
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let local = scan_local_plugins(&dir_path)?;
+    let thread_count = threads.unwrap_or(8) as u16;
+    let allow_local_sources = allow_local_sources.unwrap_or(false);
+    let mut result = PluginSetImportResult::default();
+
+    // 插件市场中有相同版本时使用插件市场的文件名和下载地址
+    let catalog = match load_catalog().await {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            println!("获取插件市场失败，只能使用清单中的下载地址: {}", e);
+            None
+        }
+    };
+    let temp_dir = std::env::temp_dir().join(format!(
+        "cloud-pe-import-{}",
+        chrono::Local::now().timestamp_millis()
+    ));
+
+    let mut installs = Vec::new();
+    for (index, entry) in manifest.plugins.iter().enumerate() {
+        let existing: Vec<&LocalPlugin> = local
+            .iter()
+            .filter(|p| {
+                p.manifest.id == entry.id && same_version(&p.manifest.version, &entry.version)
+            })
+            .collect();
+        let mut present = None;
+        for plugin in &existing {
+            if let Ok(hash) = hash_file(dir_path.join(&plugin.file)).await {
+                if hash.eq_ignore_ascii_case(&entry.sha256) {
+                    present = Some(*plugin);
+                    break;
+                }
+            }
+        }
+        if let Some(plugin) = present {
+            result.unchanged.push(plugin.file.clone());
+            continue;
+        }
+        // 同版本但内容不同的文件由下载的文件替换
+        let replaces = existing.first().map(|p| p.file.clone());
+
+        let listed = catalog.as_ref().and_then(|catalog| {
+            catalog
+                .plugins()
+                .find(|p| p.id() == entry.id && same_version(&p.version, &entry.version))
+        });
+        if let Some(plugin) = listed {
+            let mut install = plugin.planned_install(replaces);
+            install.sha256 = Some(entry.sha256.clone());
+            installs.push(install);
+            continue;
+        }
+
+        let link = match &entry.link {
+            Some(link) => link,
+            None => {
+                result.missing.push(entry.id.clone());
+                continue;
+            }
+        };
+        if !is_remote_link(link) && !allow_local_sources {
+            result.blocked.push(entry.id.clone());
+            continue;
+        }
+        fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+        match prefetch_entry(&temp_dir, index, entry, link, thread_count).await {
+            Ok(mut install) => {
+                install.replaces = replaces;
+                installs.push(install);
+            }
+            Err(e) => {
+                fs::remove_dir_all(&temp_dir).ok();
+                return Err(e);
+            }
+        }
+    }
+
+    let batch = install_batch(&drive_letter, &installs, thread_count).await;
+    fs::remove_dir_all(&temp_dir).ok();
+    result.installed = batch?.installed;
+
+    // 清单中的版本按记录的状态启用或禁用，同一插件的其他版本一律禁用
+    let exclusive = exclusive.unwrap_or(false);
+    let mut to_enable = Vec::new();
+    let mut to_disable = Vec::new();
+    for plugin in scan_local_plugins(&dir_path)? {
+        let entries: Vec<&PluginSetEntry> = manifest
+            .plugins
+            .iter()
+            .filter(|e| e.id == plugin.manifest.id)
+            .collect();
+        if entries.is_empty() && !exclusive {
+            continue;
+        }
+        let should_enable = entries
+            .iter()
+            .any(|e| e.enabled && same_version(&plugin.manifest.version, &e.version));
+        if should_enable && !plugin.enabled {
+            to_enable.push(plugin.file);
+        } else if !should_enable && plugin.enabled {
+            to_disable.push(plugin.file);
+        }
+    }
+
+    for file_name in to_disable {
+        disable_plugin(drive_letter.clone(), file_name.clone(), Some(true))?;
+        result.disabled.push(file_name);
+    }
+    for file_name in to_enable {
+        enable_plugin(drive_letter.clone(), file_name.clone())?;
+        result.enabled.push(file_name);
+    }
+
+    println!(
+        "已导入插件清单: 下载 {} 个，启用 {} 个，禁用 {} 个，缺少 {} 个，未允许 {} 个",
+        result.installed.len(),
+        result.enabled.len(),
+        result.disabled.len(),
+        result.missing.len(),
+        result.blocked.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_remote_link, validate_entry, PluginSetEntry};
+
+    fn entry(sha256: &str) -> PluginSetEntry {
+        PluginSetEntry {
+            id: "7-Zip|Cloud-PE".to_string(),
+            name: "7-Zip".to_string(),
+            version: "23.01".to_string(),
+            author: "Cloud-PE".to_string(),
+            file_name: "..\\..\\evil.ce".to_string(),
+            enabled: true,
+            size: 0,
+            sha256: sha256.to_string(),
+            link: None,
+        }
+    }
+
+    #[test]
+    fn validates_entry_hash() {
+        assert!(validate_entry(&entry(&"a".repeat(64))).is_ok());
+        assert!(validate_entry(&entry("abc")).is_err());
+        assert!(validate_entry(&entry(&"z".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn only_http_links_are_remote() {
+        assert!(is_remote_link("https://example.com/a.ce"));
+        assert!(is_remote_link("http://example.com/a.ce"));
+        assert!(!is_remote_link("file:///C:/plugins/a.ce"));
+        assert!(!is_remote_link("C:\\plugins\\a.ce"));
+        assert!(!is_remote_link("\\\\server\\share\\a.ce"));
+    }
+}
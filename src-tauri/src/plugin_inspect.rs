@@ -0,0 +1,386 @@
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use tauri::command;
+
+use crate::plugin_batch::validate_plugin_file_name;
+use crate::plugin_manifest::{
+    detect_container, read_embedded_manifest, ContainerFormat, PluginManifest,
+};
+use crate::plugins::ce_apps_path;
+
+// 插件包中的一个文件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageEntry {
+    pub path: String,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compressed_size: Option<u64>,
+    pub is_directory: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInspection {
+    pub file: String,
+    pub format: String,
+    pub file_size: u64,
+    pub unpacked_size: u64,
+    pub entries: Vec<PackageEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<PluginManifest>,
+    // 是否解压全部内容做过完整性测试
+    pub tested: bool,
+    // 压缩包截断、损坏或内容与清单不符，安装时会被拒绝
+    pub problems: Vec<String>,
+    // 无法检查但不影响安装的情况，如非 zip/7z 格式
+    pub warnings: Vec<String>,
+}
+
+impl PluginInspection {
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+// 启动盘上有问题的插件包
+#[derive(Debug, Clone, Serialize)]
+pub struct DamagedPlugin {
+    pub file: String,
+    pub problems: Vec<String>,
+}
+
+fn normalize_entry_path(path: &str) -> String {
+    path.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
+fn list_seven_zip(path: &Path) -> Result<Vec<PackageEntry>> {
+    let archive = sevenz_rust::Archive::open(path)?;
+    Ok(archive
+        .files
+        .iter()
+        .map(|f| PackageEntry {
+            path: normalize_entry_path(f.name()),
+            size: f.size(),
+            compressed_size: None,
+            is_directory: f.is_directory(),
+        })
+        .collect())
+}
+
+fn list_zip(path: &Path) -> Result<Vec<PackageEntry>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        entries.push(PackageEntry {
+            path: normalize_entry_path(entry.name()),
+            size: entry.size(),
+            compressed_size: Some(entry.compressed_size()),
+            is_directory: entry.is_dir(),
+        });
+    }
+    Ok(entries)
+}
+
+// 解压全部内容：压缩库会校验 CRC，同时计算每个文件的 sha256
+fn hash_seven_zip(path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())?;
+    let mut hashes = BTreeMap::new();
+    reader.for_each_entries(|entry, data| {
+        if entry.is_directory() {
+            return Ok(true);
+        }
+        let mut hasher = Sha256::new();
+        io::copy(data, &mut hasher)?;
+        hashes.insert(
+            normalize_entry_path(entry.name()),
+            format!("{:x}", hasher.finalize()),
+        );
+        Ok(true)
+    })?;
+    Ok(hashes)
+}
+
+fn hash_zip(path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let mut hashes = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let mut hasher = Sha256::new();
+        io::copy(&mut entry, &mut hasher)?;
+        hashes.insert(
+            normalize_entry_path(entry.name()),
+            format!("{:x}", hasher.finalize()),
+        );
+    }
+    Ok(hashes)
+}
+
+// 清单中列出的哈希与实际内容对比
+fn compare_hashes(manifest: &PluginManifest, actual: &BTreeMap<String, String>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (path, expected) in &manifest.hashes {
+        match actual.get(&normalize_entry_path(path)) {
+            None => problems.push(format!("缺少文件 {}", path)),
+            Some(hash) if !hash.eq_ignore_ascii_case(expected) => {
+                problems.push(format!("文件 {} 的哈希不匹配", path))
+            }
+            Some(_) => {}
+        }
+    }
+    problems
+}
+
+// 检查插件包：列出内容、读取内嵌清单，test 为 true 时解压全部内容做完整性测试
+pub fn inspect_package(path: &Path, test: bool) -> Result<PluginInspection> {
+    let file_size = fs::metadata(path)?.len();
+    let file = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let format = detect_container(path)?;
+
+    let mut inspection = PluginInspection {
+        file,
+        format: match format {
+            ContainerFormat::SevenZip => "7z",
+            ContainerFormat::Zip => "zip",
+            ContainerFormat::Unknown => "unknown",
+        }
+        .to_string(),
+        file_size,
+        unpacked_size: 0,
+        entries: Vec::new(),
+        manifest: None,
+        tested: false,
+        problems: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    if file_size == 0 {
+        inspection.problems.push("文件为空".to_string());
+        return Ok(inspection);
+    }
+
+    // 目录信息在文件末尾，文件被截断时无法打开
+    let listed = match format {
+        ContainerFormat::SevenZip => list_seven_zip(path),
+        ContainerFormat::Zip => list_zip(path),
+        ContainerFormat::Unknown => {
+            inspection
+                .warnings
+                .push("插件包不是 zip 或 7z 格式，无法检查完整性".to_string());
+            return Ok(inspection);
+        }
+    };
+    match listed {
+        Ok(entries) => inspection.entries = entries,
+        Err(e) => {
+            inspection
+                .problems
+                .push(format!("压缩包已损坏或不完整: {}", e));
+            return Ok(inspection);
+        }
+    }
+    inspection.unpacked_size = inspection.entries.iter().map(|e| e.size).sum();
+
+    match read_embedded_manifest(path) {
+        Ok(manifest) => inspection.manifest = manifest,
+        Err(e) => inspection.warnings.push(format!("内嵌清单无效: {}", e)),
+    }
+
+    if test {
+        let hashed = match format {
+            ContainerFormat::SevenZip => hash_seven_zip(path),
+            _ => hash_zip(path),
+        };
+        inspection.tested = true;
+        match hashed {
+            Ok(actual) => {
+                if let Some(manifest) = &inspection.manifest {
+                    let problems = compare_hashes(manifest, &actual);
+                    inspection.problems.extend(problems);
+                }
+            }
+            Err(e) => inspection.problems.push(format!("完整性测试失败: {}", e)),
+        }
+    }
+
+    Ok(inspection)
+}
+
+#[command]
+pub async fn inspect_plugin(
+    drive_letter: String,
+    file_name: String,
+    test: Option<bool>,
+) -> Result<PluginInspection, String> {
+    validate_plugin_file_name(&file_name)?;
+    let file_path = ce_apps_path(&drive_letter)?.join(&file_name);
+    if !file_path.is_file() {
+        return Err(format!("文件 {} 不存在", file_name));
+    }
+
+    // 完整性测试需要解压全部内容，放到后台线程
+    tokio::task::spawn_blocking(move || inspect_package(&file_path, test.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// 检查启动盘上的全部插件包，只返回有问题的
+#[command]
+pub async fn find_damaged_plugins(
+    drive_letter: String,
+    test: Option<bool>,
+) -> Result<Vec<DamagedPlugin>, String> {
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let test = test.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let mut damaged = Vec::new();
+        for entry in fs::read_dir(&dir_path).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let extension = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !path.is_file() || (extension != "ce" && extension != "cbk") {
+                continue;
+            }
+
+            let file = path.file_name().unwrap().to_string_lossy().to_string();
+            match inspect_package(&path, test) {
+                Ok(inspection) if inspection.is_healthy() => {}
+                Ok(inspection) => damaged.push(DamagedPlugin {
+                    file,
+                    problems: inspection.problems,
+                }),
+                Err(e) => damaged.push(DamagedPlugin {
+                    file,
+                    problems: vec![e.to_string()],
+                }),
+            }
+        }
+        if !damaged.is_empty() {
+            println!("发现 {} 个有问题的插件包", damaged.len());
+        }
+        Ok(damaged)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn temp_file(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("cloud-pe-inspect-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn zip_bytes() -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("bin/tool.exe", options).unwrap();
+        writer.write_all(&[7u8; 4096]).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    #[test]
+    fn intact_zip_has_no_problems() {
+        let path = temp_file("intact.ce", &zip_bytes());
+        let inspection = inspect_package(&path, true).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(inspection.format, "zip");
+        assert_eq!(inspection.unpacked_size, 4096);
+        assert!(inspection.is_healthy(), "{:?}", inspection.problems);
+    }
+
+    #[test]
+    fn truncated_zip_is_damaged() {
+        let bytes = zip_bytes();
+        let path = temp_file("truncated.ce", &bytes[..bytes.len() - 30]);
+        let inspection = inspect_package(&path, false).unwrap();
+        fs::remove_file(&path).ok();
+        assert!(!inspection.is_healthy());
+    }
+
+    #[test]
+    fn truncated_seven_zip_is_damaged() {
+        // 签名头完整，但其指向的文件头超出了文件末尾
+        let mut next_header = Vec::new();
+        next_header.extend_from_slice(&4096u64.to_le_bytes());
+        next_header.extend_from_slice(&64u64.to_le_bytes());
+        next_header.extend_from_slice(&0u32.to_le_bytes());
+        let mut bytes = vec![b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C, 0, 4];
+        bytes.extend_from_slice(&crc32(&next_header).to_le_bytes());
+        bytes.extend_from_slice(&next_header);
+        bytes.extend_from_slice(&[0u8; 100]);
+
+        let path = temp_file("truncated7z.ce", &bytes);
+        let inspection = inspect_package(&path, false).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(inspection.format, "7z");
+        assert!(!inspection.is_healthy());
+    }
+
+    #[test]
+    fn unknown_container_is_only_a_warning() {
+        let path = temp_file("unknown.ce", b"not an archive");
+        let inspection = inspect_package(&path, true).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(inspection.format, "unknown");
+        assert!(inspection.is_healthy());
+        assert_eq!(inspection.warnings.len(), 1);
+    }
+
+    #[test]
+    fn compare_hashes_reports_mismatch_and_missing_files() {
+        let manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "name": "Tool",
+            "version": "1.0",
+            "author": "me",
+            "hashes": { "bin\\tool.exe": "AA", "bin/gone.dll": "bb", "readme.txt": "cc" },
+        }))
+        .unwrap();
+        let actual = BTreeMap::from([
+            ("bin/tool.exe".to_string(), "ff".to_string()),
+            ("readme.txt".to_string(), "CC".to_string()),
+        ]);
+
+        let problems = compare_hashes(&manifest, &actual);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().any(|p| p.contains("bin/gone.dll")));
+        assert!(problems.iter().any(|p| p.contains("哈希不匹配")));
+    }
+}
@@ -0,0 +1,315 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::command;
+
+use crate::download::download_plugin_file;
+use crate::paths::app_data_dir;
+use crate::plugin_batch::{install_batch, validate_plugin_file_name, verify_staged};
+use crate::plugin_catalog::load_catalog;
+use crate::plugin_manifest::parse_plugin_version;
+use crate::plugins::{ce_apps_path, pick_winner, scan_local_plugins, LocalPlugin, PlannedInstall};
+
+// 本机插件库：下载过的插件保存在用户数据目录，同步到多个启动盘时不需要重复下载
+const LIBRARY_DIR: &str = "plugin-library";
+// 下载中的文件先放在子目录，校验通过后再移入插件库
+const DOWNLOAD_DIR: &str = ".download";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPlugin {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub describe: String,
+    pub file: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncKind {
+    Add,
+    Upgrade,
+}
+
+// 同步时需要复制的一个插件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    pub id: String,
+    pub name: String,
+    pub kind: SyncKind,
+    pub version: String,
+    pub current_version: Option<String>,
+    // 来源中的文件名
+    pub file: String,
+    // 复制到目标后的文件名，升级时保持原有的启用状态
+    pub target_file: String,
+    pub replaces: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSyncReport {
+    pub actions: Vec<SyncAction>,
+    pub total_bytes: u64,
+    // dry run 时为 false，只返回差异
+    pub applied: bool,
+}
+
+fn library_dir() -> Result<PathBuf> {
+    let dir = app_data_dir()?.join(LIBRARY_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// 每个插件只取最新的版本
+fn latest_by_id<'a>(
+    dir_path: &Path,
+    plugins: &'a [LocalPlugin],
+) -> BTreeMap<&'a str, &'a LocalPlugin> {
+    let mut groups: BTreeMap<&str, Vec<&LocalPlugin>> = BTreeMap::new();
+    for plugin in plugins {
+        groups.entry(&plugin.manifest.id).or_default().push(plugin);
+    }
+    groups
+        .into_iter()
+        .map(|(id, group)| (id, pick_winner(dir_path, &group)))
+        .collect()
+}
+
+// 对比来源和目标，找出目标缺少或版本较旧的插件
+fn diff_plugins(
+    source_dir: &Path,
+    source: &[LocalPlugin],
+    target_dir: &Path,
+    target: &[LocalPlugin],
+) -> Vec<SyncAction> {
+    let target_latest = latest_by_id(target_dir, target);
+    let mut actions = Vec::new();
+    for (id, plugin) in latest_by_id(source_dir, source) {
+        let current = target_latest.get(id).copied();
+        let kind = match current {
+            None => SyncKind::Add,
+            Some(existing) if plugin.manifest.semver() > existing.manifest.semver() => {
+                SyncKind::Upgrade
+            }
+            Some(_) => continue,
+        };
+        let enabled = current.map(|c| c.enabled).unwrap_or(true);
+        let target_file = Path::new(&plugin.file)
+            .with_extension(if enabled { "ce" } else { "CBK" })
+            .to_string_lossy()
+            .to_string();
+
+        actions.push(SyncAction {
+            id: id.to_string(),
+            name: plugin.manifest.name.clone(),
+            kind,
+            version: plugin.manifest.version.clone(),
+            current_version: current.map(|c| c.manifest.version.clone()),
+            file: plugin.file.clone(),
+            target_file,
+            replaces: current.map(|c| c.file.clone()),
+            size: fs::metadata(source_dir.join(&plugin.file))
+                .map(|m| m.len())
+                .unwrap_or(0),
+        });
+    }
+    actions
+}
+
+fn report(actions: Vec<SyncAction>, applied: bool) -> PluginSyncReport {
+    PluginSyncReport {
+        total_bytes: actions.iter().map(|a| a.size).sum(),
+        actions,
+        applied,
+    }
+}
+
+// 新版本放入插件库后删除同一插件的旧版本
+fn remove_older_versions(dir_path: &Path, id: &str, keep: &str) -> Result<(), String> {
+    for plugin in scan_local_plugins(dir_path)? {
+        if plugin.manifest.id == id && plugin.file != keep {
+            fs::remove_file(dir_path.join(&plugin.file)).ok();
+        }
+    }
+    Ok(())
+}
+
+#[command]
+pub fn get_plugin_library() -> Result<Vec<LibraryPlugin>, String> {
+    let dir_path = library_dir().map_err(|e| e.to_string())?;
+    let mut plugins: Vec<LibraryPlugin> = scan_local_plugins(&dir_path)?
+        .into_iter()
+        .map(|p| LibraryPlugin {
+            size: fs::metadata(dir_path.join(&p.file))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            id: p.manifest.id,
+            name: p.manifest.name,
+            version: p.manifest.version,
+            author: p.manifest.author,
+            describe: p.manifest.description,
+            file: p.file,
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+// 下载插件到插件库。items 为空时从插件市场更新插件库中已有的插件
+#[command]
+pub async fn download_to_plugin_library(
+    items: Option<Vec<PlannedInstall>>,
+    threads: Option<u32>,
+) -> Result<Vec<String>, String> {
+    let dir_path = library_dir().map_err(|e| e.to_string())?;
+    let items = match items {
+        Some(items) => items,
+        None => {
+            let catalog = load_catalog().await.map_err(|e| e.to_string())?;
+            let local = scan_local_plugins(&dir_path)?;
+            latest_by_id(&dir_path, &local)
+                .into_iter()
+                .filter_map(|(id, plugin)| {
+                    let latest = catalog.latest(id)?;
+                    let newer = match (
+                        parse_plugin_version(&latest.version),
+                        plugin.manifest.semver(),
+                    ) {
+                        (Some(available), Some(current)) => available > current,
+                        _ => false,
+                    };
+                    newer.then(|| latest.planned_install(Some(plugin.file.clone())))
+                })
+                .collect()
+        }
+    };
+    for item in &items {
+        validate_plugin_file_name(&item.file_name)?;
+    }
+
+    let download_dir = dir_path.join(DOWNLOAD_DIR);
+    fs::create_dir_all(&download_dir).map_err(|e| e.to_string())?;
+    let thread_count = threads.unwrap_or(8) as u16;
+
+    let mut downloaded = Vec::new();
+    for item in &items {
+        println!("下载插件到插件库: {} {}", item.name, item.version);
+        let temp_path = download_dir.join(&item.file_name);
+        let result = async {
+            download_plugin_file(item.url.clone(), temp_path.clone(), thread_count)
+                .await
+                .map_err(|e| format!("下载插件 {} 失败: {}", item.name, e))?;
+            verify_staged(&temp_path, item)
+                .map_err(|e| format!("插件 {} 校验失败: {}", item.name, e))?;
+            fs::rename(&temp_path, dir_path.join(&item.file_name)).map_err(|e| e.to_string())
+        }
+        .await;
+
+        if let Err(e) = result {
+            fs::remove_dir_all(&download_dir).ok();
+            return Err(e);
+        }
+        remove_older_versions(&dir_path, &item.id, &item.file_name)?;
+        downloaded.push(item.file_name.clone());
+    }
+
+    fs::remove_dir_all(&download_dir).ok();
+    Ok(downloaded)
+}
+
+// 把启动盘上插件库中没有或更新的插件复制到插件库
+#[command]
+pub fn add_drive_plugins_to_library(
+    drive_letter: String,
+    dry_run: Option<bool>,
+) -> Result<PluginSyncReport, String> {
+    let drive_dir = ce_apps_path(&drive_letter)?;
+    let dir_path = library_dir().map_err(|e| e.to_string())?;
+    let mut actions = diff_plugins(
+        &drive_dir,
+        &scan_local_plugins(&drive_dir)?,
+        &dir_path,
+        &scan_local_plugins(&dir_path)?,
+    );
+    // 插件库中统一保存为 .ce
+    for action in &mut actions {
+        action.target_file = Path::new(&action.file)
+            .with_extension("ce")
+            .to_string_lossy()
+            .to_string();
+    }
+
+    if dry_run.unwrap_or(false) {
+        return Ok(report(actions, false));
+    }
+
+    for action in &actions {
+        let temp_path = dir_path.join(format!("{}.tmp", action.target_file));
+        fs::copy(drive_dir.join(&action.file), &temp_path).map_err(|e| e.to_string())?;
+        fs::rename(&temp_path, dir_path.join(&action.target_file)).map_err(|e| e.to_string())?;
+        remove_older_versions(&dir_path, &action.id, &action.target_file)?;
+    }
+    println!("已从启动盘添加 {} 个插件到插件库", actions.len());
+    Ok(report(actions, true))
+}
+
+// 把插件库中启动盘缺少或更新的插件同步到启动盘，dry_run 为 true 时只返回差异
+#[command]
+pub async fn sync_plugin_library(
+    drive_letter: String,
+    dry_run: Option<bool>,
+) -> Result<PluginSyncReport, String> {
+    let drive_dir = ce_apps_path(&drive_letter)?;
+    let dir_path = library_dir().map_err(|e| e.to_string())?;
+    let actions = diff_plugins(
+        &dir_path,
+        &scan_local_plugins(&dir_path)?,
+        &drive_dir,
+        &scan_local_plugins(&drive_dir)?,
+    );
+
+    if dry_run.unwrap_or(false) {
+        return Ok(report(actions, false));
+    }
+
+    // 复用批量安装的暂存、校验和回滚，来源为本机文件
+    let mut items = Vec::new();
+    for action in &actions {
+        let source = dir_path.join(&action.file);
+        let url = url::Url::from_file_path(&source)
+            .map_err(|_| format!("无效的插件路径: {}", source.display()))?;
+        items.push(PlannedInstall {
+            id: action.id.clone(),
+            name: action.name.clone(),
+            version: action.version.clone(),
+            url: url.to_string(),
+            file_name: action.target_file.clone(),
+            replaces: action.replaces.clone(),
+            sha256: None,
+        });
+    }
+    install_batch(&drive_letter, &items, 1).await?;
+
+    println!("已从插件库同步 {} 个插件到 {}", actions.len(), drive_letter);
+    Ok(report(actions, true))
+}
+
+#[command]
+pub fn remove_library_plugin(file_name: String) -> Result<bool, String> {
+    validate_plugin_file_name(&file_name)?;
+    let dir_path = library_dir().map_err(|e| e.to_string())?;
+    let file_path = dir_path.join(&file_name);
+    if !file_path.is_file() {
+        return Err(format!("插件库中没有文件 {}", file_name));
+    }
+    fs::remove_file(&file_path).map_err(|e| e.to_string())?;
+    Ok(true)
+}
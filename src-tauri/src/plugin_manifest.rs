@@ -0,0 +1,420 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// 插件清单：优先读取 .ce 容器内的 plugin.json，其次读取同名的 .plugin.json 附属文件，
+// 两者都没有时才按旧的 名称_版本_作者_描述.ce 文件名解析
+pub const MANIFEST_ENTRY_NAME: &str = "plugin.json";
+pub const SIDECAR_SUFFIX: &str = ".plugin.json";
+const MAX_MANIFEST_SIZE: u64 = 1024 * 1024;
+
+const SEVEN_ZIP_MAGIC: &[u8] = &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
+const ZIP_MAGIC: &[u8] = &[0x50, 0x4B, 0x03, 0x04];
+
+lazy_static::lazy_static! {
+    static ref MANIFEST_CACHE: Mutex<HashMap<PathBuf, (FileStamp, PluginManifest, ManifestSource)>> =
+        Mutex::new(HashMap::new());
+}
+
+// 插件依赖，version 为 semver 版本约束（如 ">=4.8"），为空表示任意版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginDependency {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl PluginDependency {
+    // 判断指定版本是否满足约束，约束无效时视为不满足
+    pub fn matches(&self, version: &str) -> bool {
+        let version = match parse_plugin_version(version) {
+            Some(v) => v,
+            None => return false,
+        };
+        match &self.version {
+            Some(req) => semver::VersionReq::parse(req)
+                .map(|r| r.matches(&version))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.version {
+            Some(req) => format!("{} ({})", self.id, req),
+            None => self.id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    // 为空时使用 名称|作者 生成，与插件市场保持一致
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    #[serde(default, alias = "describe")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<PluginDependency>,
+    // 不能同时启用的插件，格式与依赖相同
+    #[serde(default)]
+    pub conflicts: Vec<PluginDependency>,
+    // 容器内文件路径 -> sha256
+    #[serde(default)]
+    pub hashes: BTreeMap<String, String>,
+}
+
+// 清单来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestSource {
+    Embedded,
+    Sidecar,
+    Filename,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerFormat {
+    SevenZip,
+    Zip,
+    Unknown,
+}
+
+pub fn generate_plugin_id(name: &str, author: &str) -> String {
+    format!("{}|{}", name, author)
+}
+
+// 插件版本号转为 semver：去掉前缀 v，不足三段补 0，第四段及以后放入构建元数据。
+// 数字后面的其余部分（如 1.0-beta、2.1 Beta）作为预发布标识，与旧版本按文件名解析时一样宽松
+pub fn parse_plugin_version(version: &str) -> Option<semver::Version> {
+    let trimmed = version.trim().trim_start_matches(['v', 'V']);
+    if let Ok(parsed) = semver::Version::parse(trimmed) {
+        return Some(parsed);
+    }
+    if let Some(parsed) = parse_numeric_version(trimmed) {
+        return Some(parsed);
+    }
+
+    let numeric_end = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let mut parsed = parse_numeric_version(trimmed[..numeric_end].trim_end_matches('.'))?;
+    let suffix: String = trimmed[numeric_end..]
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let suffix = suffix.trim_matches('-');
+    if !suffix.is_empty() {
+        if let Ok(pre) = semver::Prerelease::new(suffix) {
+            parsed.pre = pre;
+        }
+    }
+    Some(parsed)
+}
+
+fn parse_numeric_version(version: &str) -> Option<semver::Version> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if parts.is_empty() {
+        return None;
+    }
+
+    let mut parsed = semver::Version::new(
+        parts[0],
+        parts.get(1).copied().unwrap_or(0),
+        parts.get(2).copied().unwrap_or(0),
+    );
+    if parts.len() > 3 {
+        let rest: Vec<String> = parts[3..].iter().map(|p| p.to_string()).collect();
+        parsed.build = semver::BuildMetadata::new(&rest.join(".")).ok()?;
+    }
+    Some(parsed)
+}
+
+pub fn detect_container(path: &Path) -> Result<ContainerFormat> {
+    let mut header = [0u8; 6];
+    let mut file = File::open(path)?;
+    let read = file.read(&mut header)?;
+    let header = &header[..read];
+
+    Ok(if header.starts_with(SEVEN_ZIP_MAGIC) {
+        ContainerFormat::SevenZip
+    } else if header.starts_with(ZIP_MAGIC) {
+        ContainerFormat::Zip
+    } else {
+        ContainerFormat::Unknown
+    })
+}
+
+impl PluginManifest {
+    // 补全默认字段并检查必填项
+    fn normalize(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        self.author = self.author.trim().to_string();
+        self.version = self.version.trim().to_string();
+
+        if self.name.is_empty() {
+            anyhow::bail!("清单缺少插件名称");
+        }
+        if self.author.is_empty() {
+            anyhow::bail!("清单缺少作者");
+        }
+        // 无法转为 semver 的版本号仍然保留，只是不参与版本比较
+        if self.version.is_empty() {
+            anyhow::bail!("清单缺少版本号");
+        }
+        for dependency in self.dependencies.iter().chain(&self.conflicts) {
+            if let Some(req) = &dependency.version {
+                semver::VersionReq::parse(req)
+                    .map_err(|e| anyhow::anyhow!("依赖 {} 的版本约束无效: {}", dependency.id, e))?;
+            }
+        }
+        if self.id.trim().is_empty() {
+            self.id = generate_plugin_id(&self.name, &self.author);
+        }
+        Ok(self)
+    }
+
+    pub fn semver(&self) -> Option<semver::Version> {
+        parse_plugin_version(&self.version)
+    }
+}
+
+fn parse_manifest(data: &[u8]) -> Result<PluginManifest> {
+    let manifest: PluginManifest = serde_json::from_slice(data)?;
+    manifest.normalize()
+}
+
+fn is_manifest_entry(name: &str) -> bool {
+    name.replace('\\', "/")
+        .trim_start_matches('/')
+        .eq_ignore_ascii_case(MANIFEST_ENTRY_NAME)
+}
+
+fn read_zip_manifest(path: &Path) -> Result<Option<Vec<u8>>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        if !is_manifest_entry(entry.name()) {
+            continue;
+        }
+        let mut data = Vec::new();
+        entry.take(MAX_MANIFEST_SIZE).read_to_end(&mut data)?;
+        return Ok(Some(data));
+    }
+    Ok(None)
+}
+
+fn read_seven_zip_manifest(path: &Path) -> Result<Option<Vec<u8>>> {
+    // 先只读取文件头，没有清单时不需要解压
+    let archive = sevenz_rust::Archive::open(path)?;
+    if !archive.files.iter().any(|f| is_manifest_entry(f.name())) {
+        return Ok(None);
+    }
+
+    let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())?;
+    let mut manifest = None;
+    reader.for_each_entries(|entry, data| {
+        if !is_manifest_entry(entry.name()) {
+            // 固实压缩时必须按顺序解压，跳过的内容直接丢弃
+            std::io::copy(data, &mut std::io::sink())?;
+            return Ok(true);
+        }
+        let mut buf = Vec::new();
+        data.take(MAX_MANIFEST_SIZE).read_to_end(&mut buf)?;
+        manifest = Some(buf);
+        Ok(false)
+    })?;
+    Ok(manifest)
+}
+
+// 读取 .ce 容器内嵌的清单，容器格式无法识别时返回 None
+pub fn read_embedded_manifest(path: &Path) -> Result<Option<PluginManifest>> {
+    let data = match detect_container(path)? {
+        ContainerFormat::SevenZip => read_seven_zip_manifest(path)?,
+        ContainerFormat::Zip => read_zip_manifest(path)?,
+        ContainerFormat::Unknown => None,
+    };
+    data.map(|d| parse_manifest(&d)).transpose()
+}
+
+// 附属清单与插件文件同名，启用和禁用（.ce / .CBK）共用同一个
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}{}", stem, SIDECAR_SUFFIX))
+}
+
+pub fn read_sidecar_manifest(path: &Path) -> Result<Option<PluginManifest>> {
+    let sidecar = sidecar_path(path);
+    if !sidecar.is_file() {
+        return Ok(None);
+    }
+    let data = std::fs::read(&sidecar)?;
+    parse_manifest(&data)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("附属清单 {} 无效: {}", sidecar.display(), e))
+}
+
+// 带点或纯数字的字段才当作版本号，避免名称中的 3D 之类被误认
+fn looks_like_version(part: &str) -> bool {
+    let trimmed = part.trim_start_matches(['v', 'V']);
+    trimmed.starts_with(|c: char| c.is_ascii_digit())
+        && (trimmed.contains('.') || trimmed.chars().all(|c| c.is_ascii_digit()))
+        && parse_plugin_version(trimmed).is_some()
+}
+
+// 旧格式：名称_版本_作者_描述.ce。
+// 以形如版本号的字段为界，名称中的下划线保留，描述可以为空
+pub fn parse_legacy_file_name(file_name: &str) -> Result<PluginManifest> {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parts: Vec<&str> = stem.split('_').collect();
+
+    // 优先取带点的字段，避免名称中的年份（如 Office_2021）被当成版本号
+    let candidates: Vec<usize> = (1..parts.len())
+        .filter(|&i| looks_like_version(parts[i]))
+        .collect();
+    let version_index = candidates
+        .iter()
+        .copied()
+        .find(|&i| parts[i].contains('.'))
+        .or_else(|| candidates.first().copied())
+        .ok_or_else(|| anyhow::anyhow!("文件名中找不到版本号"))?;
+
+    let author = parts
+        .get(version_index + 1)
+        .filter(|a| !a.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("文件名中缺少作者"))?;
+
+    let manifest = PluginManifest {
+        id: String::new(),
+        name: parts[..version_index].join("_"),
+        version: parts[version_index].to_string(),
+        author: author.to_string(),
+        description: parts
+            .get(version_index + 2..)
+            .map(|rest| rest.join("_"))
+            .unwrap_or_default(),
+        category: None,
+        dependencies: Vec::new(),
+        conflicts: Vec::new(),
+        hashes: BTreeMap::new(),
+    };
+    manifest.normalize()
+}
+
+// 插件文件及附属清单的大小和修改时间，任一变化时缓存失效
+#[derive(Clone, PartialEq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    sidecar_modified: Option<SystemTime>,
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(FileStamp {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+        sidecar_modified: std::fs::metadata(sidecar_path(path))
+            .and_then(|m| m.modified())
+            .ok(),
+    })
+}
+
+// 按 内嵌清单 -> 附属清单 -> 文件名 的顺序读取插件信息。
+// 清单存在但无效时返回错误，不再回退到文件名，避免错误被掩盖。
+// 结果按文件缓存，刷新插件列表时不必重新打开每个压缩包（固实 7z 需要解压才能读到清单）
+pub fn load_plugin_manifest(path: &Path) -> Result<(PluginManifest, ManifestSource)> {
+    let stamp = file_stamp(path);
+    if let Some(stamp) = &stamp {
+        if let Some((cached_stamp, manifest, source)) = MANIFEST_CACHE.lock().unwrap().get(path) {
+            if cached_stamp == stamp {
+                return Ok((manifest.clone(), *source));
+            }
+        }
+    }
+
+    let (manifest, source) = read_plugin_manifest(path)?;
+    if let Some(stamp) = stamp {
+        MANIFEST_CACHE
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (stamp, manifest.clone(), source));
+    }
+    Ok((manifest, source))
+}
+
+fn read_plugin_manifest(path: &Path) -> Result<(PluginManifest, ManifestSource)> {
+    let embedded =
+        read_embedded_manifest(path).map_err(|e| anyhow::anyhow!("读取内嵌清单失败: {}", e))?;
+    if let Some(manifest) = embedded {
+        return Ok((manifest, ManifestSource::Embedded));
+    }
+
+    if let Some(manifest) = read_sidecar_manifest(path)? {
+        return Ok((manifest, ManifestSource::Sidecar));
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let manifest = parse_legacy_file_name(&file_name)
+        .map_err(|e| anyhow::anyhow!("没有插件清单，且无法解析文件名: {}", e))?;
+    Ok((manifest, ManifestSource::Filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coerces_loose_versions() {
+        let v = |s: &str| parse_plugin_version(s).map(|v| v.to_string());
+        assert_eq!(v("23.01").as_deref(), Some("23.1.0"));
+        assert_eq!(v("v2.1").as_deref(), Some("2.1.0"));
+        assert_eq!(v("1.0-beta").as_deref(), Some("1.0.0-beta"));
+        assert_eq!(v("2.1 Beta2").as_deref(), Some("2.1.0-Beta2"));
+        assert_eq!(v("1.2.3.4").as_deref(), Some("1.2.3+4"));
+        assert_eq!(v("beta"), None);
+        assert!(parse_plugin_version("1.0-beta") < parse_plugin_version("1.0"));
+    }
+
+    #[test]
+    fn parses_legacy_file_names() {
+        let m = parse_legacy_file_name("Office_2021_1.0-beta_Cloud-PE_办公_套件.ce").unwrap();
+        assert_eq!(m.name, "Office_2021");
+        assert_eq!(m.version, "1.0-beta");
+        assert_eq!(m.author, "Cloud-PE");
+        assert_eq!(m.description, "办公_套件");
+
+        let m = parse_legacy_file_name("Paint_3D_2024_Microsoft_.CBK").unwrap();
+        assert_eq!(m.name, "Paint_3D");
+        assert_eq!(m.version, "2024");
+    }
+
+    #[test]
+    fn accepts_manifest_with_non_semver_version() {
+        let m = parse_manifest(br#"{"name":"Tool","version":"beta","author":"A"}"#).unwrap();
+        assert_eq!(m.id, "Tool|A");
+        assert!(m.semver().is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+
+use crate::plugin_batch::install_batch;
+use crate::plugin_catalog::load_catalog;
+use crate::plugins::{
+    apply_renames, ce_apps_path, pick_winner, scan_local_plugins, undo_renames, LocalPlugin,
+};
+
+// 插件配置保存在启动盘的 cloud-pe\profiles 目录，每个配置一个 JSON 文件
+const PROFILES_DIR: &str = "cloud-pe\\profiles";
+const INVALID_NAME_CHARS: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+// Windows 保留的设备名，不能用作文件名（带扩展名也不行）
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// 按用途保存的一组插件，如数据恢复、系统安装、硬件检测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // 需要启用的插件 ID，应用时其余插件都会被禁用
+    pub plugins: Vec<String>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileApplyResult {
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+    pub installed: Vec<String>,
+    // 启动盘和插件市场中都找不到的插件 ID
+    pub missing: Vec<String>,
+}
+
+fn profiles_dir(drive_letter: &str) -> PathBuf {
+    PathBuf::from(format!("{}\\{}", drive_letter, PROFILES_DIR))
+}
+
+fn profile_path(drive_letter: &str, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    let device = name.split('.').next().unwrap_or_default().trim_end();
+    if name.is_empty()
+        || name.contains(INVALID_NAME_CHARS)
+        || name.chars().any(|c| c.is_control())
+        || name.ends_with('.')
+        || RESERVED_NAMES
+            .iter()
+            .any(|r| r.eq_ignore_ascii_case(device))
+    {
+        return Err(format!("配置名称无效: {}", name));
+    }
+    Ok(profiles_dir(drive_letter).join(format!("{}.json", name)))
+}
+
+fn read_profile(drive_letter: &str, name: &str) -> Result<PluginProfile, String> {
+    let path = profile_path(drive_letter, name)?;
+    let content = fs::read_to_string(&path).map_err(|_| format!("插件配置 {} 不存在", name))?;
+    serde_json::from_str(&content).map_err(|e| format!("插件配置 {} 无效: {}", name, e))
+}
+
+// 配置中的插件加上它们依赖的插件，避免依赖被禁用
+fn with_dependencies(local: &[LocalPlugin], ids: &BTreeSet<String>) -> BTreeSet<String> {
+    let mut wanted = ids.clone();
+    let mut queue: Vec<String> = ids.iter().cloned().collect();
+    while let Some(id) = queue.pop() {
+        for plugin in local.iter().filter(|p| p.manifest.id == id) {
+            for dependency in &plugin.manifest.dependencies {
+                if wanted.insert(dependency.id.clone()) {
+                    queue.push(dependency.id.clone());
+                }
+            }
+        }
+    }
+    wanted
+}
+
+#[command]
+pub fn list_plugin_profiles(drive_letter: String) -> Result<Vec<PluginProfile>, String> {
+    let entries = match fs::read_dir(profiles_dir(&drive_letter)) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut profiles = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map(|e| e != "json").unwrap_or(true) {
+            continue;
+        }
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| serde_json::from_str::<PluginProfile>(&c).map_err(|e| e.to_string()))
+        {
+            Ok(profile) => profiles.push(profile),
+            Err(e) => println!("读取插件配置 {} 失败: {}", path.display(), e),
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+// plugins 为空时保存启动盘上当前已启用的插件
+#[command]
+pub fn save_plugin_profile(
+    drive_letter: String,
+    name: String,
+    description: Option<String>,
+    plugins: Option<Vec<String>>,
+) -> Result<PluginProfile, String> {
+    let path = profile_path(&drive_letter, &name)?;
+    let plugins = match plugins {
+        Some(plugins) => plugins,
+        None => scan_local_plugins(&ce_apps_path(&drive_letter)?)?
+            .into_iter()
+            .filter(|p| p.enabled)
+            .map(|p| p.manifest.id)
+            .collect(),
+    };
+
+    let profile = PluginProfile {
+        name: name.trim().to_string(),
+        description: description.unwrap_or_default(),
+        plugins: plugins
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        updated_at: chrono::Local::now().timestamp(),
+    };
+
+    fs::create_dir_all(profiles_dir(&drive_letter)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    println!(
+        "已保存插件配置 {}，共 {} 个插件",
+        profile.name,
+        profile.plugins.len()
+    );
+    Ok(profile)
+}
+
+#[command]
+pub fn delete_plugin_profile(drive_letter: String, name: String) -> Result<bool, String> {
+    let path = profile_path(&drive_letter, &name)?;
+    if !path.exists() {
+        return Err(format!("插件配置 {} 不存在", name));
+    }
+    fs::remove_file(&path).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// 应用插件配置：先下载启动盘上缺少的插件，再启用配置中的插件并禁用其余插件。
+// 同一插件有多个版本时只启用最新的一个
+#[command]
+pub async fn apply_plugin_profile(
+    drive_letter: String,
+    name: String,
+    threads: Option<u32>,
+) -> Result<ProfileApplyResult, String> {
+    let profile = read_profile(&drive_letter, &name)?;
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let mut result = ProfileApplyResult::default();
+
+    let mut local = scan_local_plugins(&dir_path)?;
+    let mut wanted = with_dependencies(&local, &profile.plugins.iter().cloned().collect());
+    let mut catalog = None;
+    let mut attempted = BTreeSet::new();
+
+    // 新下载的插件可能带来新的依赖，重复下载直到没有新的缺少的插件
+    loop {
+        let absent: Vec<String> = wanted
+            .iter()
+            .filter(|id| !local.iter().any(|p| &p.manifest.id == *id) && !attempted.contains(*id))
+            .cloned()
+            .collect();
+        if absent.is_empty() {
+            break;
+        }
+        if catalog.is_none() {
+            match load_catalog().await {
+                Ok(loaded) => catalog = Some(loaded),
+                Err(e) => {
+                    // 无法获取插件目录时不下载，缺少的插件记入结果，其余插件照常启用和禁用
+                    println!("加载插件目录失败，跳过下载缺少的插件: {}", e);
+                    break;
+                }
+            }
+        }
+        let catalog = catalog.as_ref().unwrap();
+
+        let mut installs = Vec::new();
+        for id in absent {
+            if let Some(plugin) = catalog.latest(&id) {
+                installs.push(plugin.planned_install(None));
+            }
+            attempted.insert(id);
+        }
+        if installs.is_empty() {
+            break;
+        }
+        let batch = install_batch(&drive_letter, &installs, threads.unwrap_or(8) as u16).await?;
+        result.installed.extend(batch.installed);
+
+        local = scan_local_plugins(&dir_path)?;
+        wanted = with_dependencies(&local, &wanted);
+    }
+    for id in &wanted {
+        if !local.iter().any(|p| &p.manifest.id == id) {
+            result.missing.push(id.clone());
+        }
+    }
+
+    let mut groups: BTreeMap<&str, Vec<&LocalPlugin>> = BTreeMap::new();
+    for plugin in &local {
+        groups.entry(&plugin.manifest.id).or_default().push(plugin);
+    }
+
+    let mut to_enable = Vec::new();
+    let mut to_disable = Vec::new();
+    for (id, group) in &groups {
+        let winner = wanted.contains(*id).then(|| pick_winner(&dir_path, group));
+        for plugin in group {
+            let keep = winner.is_some_and(|w| std::ptr::eq(w, *plugin));
+            if keep && !plugin.enabled {
+                to_enable.push(plugin.file.clone());
+            } else if !keep && plugin.enabled {
+                to_disable.push(plugin.file.clone());
+            }
+        }
+    }
+
+    // 中途失败时撤销已完成的启用和禁用，不留下只应用了一半的配置
+    let mut renamed = Vec::new();
+    if let Err(e) = apply_renames(&drive_letter, &to_disable, &to_enable, &mut renamed) {
+        println!("应用插件配置失败，正在撤销已启用和禁用的插件: {}", e);
+        undo_renames(&dir_path, &renamed);
+        return Err(e);
+    }
+    result.disabled = to_disable;
+    result.enabled = to_enable;
+
+    println!(
+        "已应用插件配置 {}: 启用 {} 个，禁用 {} 个，下载 {} 个",
+        profile.name,
+        result.enabled.len(),
+        result.disabled.len(),
+        result.installed.len()
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::profile_path;
+
+    #[test]
+    fn accepts_ordinary_names() {
+        assert!(profile_path("E:", "数据恢复").is_ok());
+        assert!(profile_path("E:", "console tools").is_ok());
+        assert!(profile_path("E:", "v1.2").is_ok());
+    }
+
+    #[test]
+    fn rejects_reserved_and_invalid_names() {
+        for name in [
+            "", "  ", "CON", "con", "Nul.txt", "COM1", "lpt9", "a/b", "..\\x", "x.",
+        ] {
+            assert!(profile_path("E:", name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::command;
+
+use crate::plugin_batch::validate_plugin_file_name;
+use crate::plugins::ce_apps_path;
+
+// 卸载的插件先移到启动盘的 cloud-pe\recycle 目录，保留期内可以撤销
+const RECYCLE_DIR: &str = "cloud-pe\\recycle";
+const INDEX_FILE_NAME: &str = "index.json";
+const RECYCLE_RETENTION_SECS: i64 = 7 * 24 * 60 * 60;
+
+// 回收区中的一个插件文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecycledPlugin {
+    pub token: String,
+    #[serde(default)]
+    pub plugin_id: String,
+    // 原来在 ce-apps 中的文件名
+    pub file: String,
+    // 回收区中的文件名
+    pub recycled_file: String,
+    // 一起移走的附属清单
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<String>,
+    pub size: u64,
+    pub deleted_at: i64,
+    pub expires_at: i64,
+}
+
+fn recycle_dir(drive_letter: &str) -> PathBuf {
+    PathBuf::from(format!("{}\\{}", drive_letter, RECYCLE_DIR))
+}
+
+// 只能是回收区内的单个文件名
+fn is_single_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['\\', '/', ':'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        )
+}
+
+// 索引文件在可移动磁盘上，可能被篡改：文件名必须是 recycle_file 生成的格式
+fn is_valid_entry(entry: &RecycledPlugin) -> bool {
+    let prefix = format!("{}_", entry.token);
+    validate_plugin_file_name(&entry.file).is_ok()
+        && entry.recycled_file == format!("{}{}", prefix, entry.file)
+        && is_single_file_name(&entry.recycled_file)
+        && entry
+            .sidecar
+            .as_deref()
+            .map(|s| s.starts_with(&prefix) && is_single_file_name(s))
+            .unwrap_or(true)
+}
+
+fn read_index(dir: &Path) -> Vec<RecycledPlugin> {
+    let entries: Vec<RecycledPlugin> = fs::read_to_string(dir.join(INDEX_FILE_NAME))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            let valid = is_valid_entry(entry);
+            if !valid {
+                println!("忽略插件回收区中无效的记录: {}", entry.recycled_file);
+            }
+            valid
+        })
+        .collect()
+}
+
+fn write_index(dir: &Path, entries: &[RecycledPlugin]) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join(INDEX_FILE_NAME),
+        serde_json::to_string_pretty(entries)?,
+    )?;
+    Ok(())
+}
+
+// 彻底删除回收区中的文件，返回释放的字节数
+fn delete_entry(dir: &Path, entry: &RecycledPlugin) -> u64 {
+    let mut freed = 0;
+    if fs::remove_file(dir.join(&entry.recycled_file)).is_ok() {
+        freed += entry.size;
+    }
+    if let Some(sidecar) = &entry.sidecar {
+        fs::remove_file(dir.join(sidecar)).ok();
+    }
+    freed
+}
+
+// 清理超过保留期的文件，返回释放的字节数
+pub fn purge_expired(drive_letter: &str) -> u64 {
+    let dir = recycle_dir(drive_letter);
+    let now = chrono::Local::now().timestamp();
+    let (expired, kept): (Vec<_>, Vec<_>) = read_index(&dir)
+        .into_iter()
+        .partition(|e| e.expires_at <= now);
+    if expired.is_empty() {
+        return 0;
+    }
+
+    let freed = expired.iter().map(|e| delete_entry(&dir, e)).sum();
+    if let Err(e) = write_index(&dir, &kept) {
+        println!("更新插件回收区索引失败: {}", e);
+    }
+    println!("已清理 {} 个过期的已卸载插件", expired.len());
+    freed
+}
+
+// 把插件文件（及附属清单）移到回收区
+pub fn recycle_file(
+    drive_letter: &str,
+    plugin_id: &str,
+    file: &str,
+    sidecar: Option<&Path>,
+) -> Result<RecycledPlugin> {
+    validate_plugin_file_name(file).map_err(anyhow::Error::msg)?;
+    let source = ce_apps_path(drive_letter)
+        .map_err(anyhow::Error::msg)?
+        .join(file);
+    let dir = recycle_dir(drive_letter);
+    fs::create_dir_all(&dir)?;
+
+    let mut entries = read_index(&dir);
+    let size = fs::metadata(&source)?.len();
+    let now = chrono::Local::now();
+    // 批量卸载时同一毫秒内可能有多个文件，加上序号区分
+    let token = format!("{}-{}", now.timestamp_millis(), entries.len());
+    let recycled_file = format!("{}_{}", token, file);
+
+    fs::rename(&source, dir.join(&recycled_file))?;
+
+    let sidecar = sidecar.filter(|p| p.is_file()).and_then(|p| {
+        let name = format!("{}_{}", token, p.file_name()?.to_string_lossy());
+        fs::rename(p, dir.join(&name)).ok().map(|_| name)
+    });
+
+    let entry = RecycledPlugin {
+        token,
+        plugin_id: plugin_id.to_string(),
+        file: file.to_string(),
+        recycled_file,
+        sidecar,
+        size,
+        deleted_at: now.timestamp(),
+        expires_at: now.timestamp() + RECYCLE_RETENTION_SECS,
+    };
+
+    entries.push(entry.clone());
+    write_index(&dir, &entries)?;
+    Ok(entry)
+}
+
+#[command]
+pub fn list_recycled_plugins(drive_letter: String) -> Result<Vec<RecycledPlugin>, String> {
+    purge_expired(&drive_letter);
+    Ok(read_index(&recycle_dir(&drive_letter)))
+}
+
+// 撤销卸载，把文件移回 ce-apps
+#[command]
+pub fn restore_plugin(drive_letter: String, token: String) -> Result<String, String> {
+    let dir = recycle_dir(&drive_letter);
+    let mut entries = read_index(&dir);
+    let index = entries
+        .iter()
+        .position(|e| e.token == token)
+        .ok_or_else(|| "回收区中找不到该插件，可能已超过保留期".to_string())?;
+    let entry = entries[index].clone();
+
+    let ce_apps = ce_apps_path(&drive_letter)?;
+    let target = ce_apps.join(&entry.file);
+    if target.exists() {
+        return Err(format!("文件 {} 已存在，无法恢复", entry.file));
+    }
+
+    fs::rename(dir.join(&entry.recycled_file), &target).map_err(|e| e.to_string())?;
+    if let Some(sidecar) = &entry.sidecar {
+        let original = sidecar
+            .strip_prefix(&format!("{}_", entry.token))
+            .unwrap_or(sidecar);
+        fs::rename(dir.join(sidecar), ce_apps.join(original)).ok();
+    }
+
+    entries.remove(index);
+    write_index(&dir, &entries).map_err(|e| e.to_string())?;
+    println!("已恢复插件: {}", entry.file);
+    Ok(entry.file)
+}
+
+// 清空回收区，返回释放的字节数
+#[command]
+pub fn empty_plugin_recycle(drive_letter: String) -> Result<u64, String> {
+    let dir = recycle_dir(&drive_letter);
+    let entries = read_index(&dir);
+    let freed = entries.iter().map(|e| delete_entry(&dir, e)).sum();
+    write_index(&dir, &[]).map_err(|e| e.to_string())?;
+    Ok(freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试用的“启动盘”，路径拼接方式与真实盘符一致
+    fn test_drive(name: &str) -> String {
+        let drive =
+            std::env::temp_dir().join(format!("cloud-pe-recycle-{}-{}", name, std::process::id()));
+        let drive = drive.to_string_lossy().to_string();
+        fs::create_dir_all(format!("{}\\ce-apps", drive)).unwrap();
+        drive
+    }
+
+    fn cleanup(drive: &str) {
+        fs::remove_dir_all(format!("{}\\ce-apps", drive)).ok();
+        fs::remove_dir_all(recycle_dir(drive)).ok();
+        fs::remove_dir_all(drive).ok();
+    }
+
+    #[test]
+    fn recycles_and_restores_with_sidecar() {
+        let drive = test_drive("restore");
+        let ce_apps = ce_apps_path(&drive).unwrap();
+        fs::write(ce_apps.join("A_1.0_me_.ce"), b"plugin").unwrap();
+        let sidecar = ce_apps.join("A_1.0_me_.ce.json");
+        fs::write(&sidecar, b"{}").unwrap();
+
+        let entry = recycle_file(&drive, "a", "A_1.0_me_.ce", Some(&sidecar)).unwrap();
+        assert!(!ce_apps.join("A_1.0_me_.ce").exists());
+        assert!(!sidecar.exists());
+        assert_eq!(entry.size, 6);
+        assert_eq!(list_recycled_plugins(drive.clone()).unwrap().len(), 1);
+
+        let restored = restore_plugin(drive.clone(), entry.token).unwrap();
+        assert_eq!(restored, "A_1.0_me_.ce");
+        assert_eq!(fs::read(ce_apps.join("A_1.0_me_.ce")).unwrap(), b"plugin");
+        assert!(sidecar.exists());
+        assert!(list_recycled_plugins(drive.clone()).unwrap().is_empty());
+        cleanup(&drive);
+    }
+
+    #[test]
+    fn purges_only_expired_entries() {
+        let drive = test_drive("purge");
+        let ce_apps = ce_apps_path(&drive).unwrap();
+        fs::write(ce_apps.join("Old_1.0_me_.ce"), b"old").unwrap();
+        fs::write(ce_apps.join("New_1.0_me_.ce"), b"new!").unwrap();
+        let old = recycle_file(&drive, "old", "Old_1.0_me_.ce", None).unwrap();
+        recycle_file(&drive, "new", "New_1.0_me_.ce", None).unwrap();
+
+        let dir = recycle_dir(&drive);
+        let mut entries = read_index(&dir);
+        entries[0].expires_at = 0;
+        write_index(&dir, &entries).unwrap();
+
+        assert_eq!(purge_expired(&drive), 3);
+        assert!(!dir.join(&old.recycled_file).exists());
+        let left = read_index(&dir);
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].file, "New_1.0_me_.ce");
+        assert_eq!(empty_plugin_recycle(drive.clone()).unwrap(), 4);
+        cleanup(&drive);
+    }
+
+    #[test]
+    fn ignores_crafted_index_entries() {
+        let drive = test_drive("crafted");
+        let outside = std::env::temp_dir().join(format!(
+            "cloud-pe-recycle-outside-{}.ce",
+            std::process::id()
+        ));
+        fs::write(&outside, b"keep me").unwrap();
+
+        let dir = recycle_dir(&drive);
+        let crafted = |file: &str, recycled_file: &str, sidecar: Option<&str>| RecycledPlugin {
+            token: "1-0".to_string(),
+            plugin_id: String::new(),
+            file: file.to_string(),
+            recycled_file: recycled_file.to_string(),
+            sidecar: sidecar.map(str::to_string),
+            size: 1,
+            deleted_at: 0,
+            expires_at: 0,
+        };
+        let outside_name = outside.to_string_lossy().to_string();
+        write_index(
+            &dir,
+            &[
+                crafted("a.ce", &outside_name, None),
+                crafted("a.ce", "1-0_a.ce", Some(&outside_name)),
+                crafted("../../a.ce", "1-0_../../a.ce", None),
+                crafted(&outside_name, "1-0_a.ce", None),
+            ],
+        )
+        .unwrap();
+
+        assert!(list_recycled_plugins(drive.clone()).unwrap().is_empty());
+        assert_eq!(empty_plugin_recycle(drive.clone()).unwrap(), 0);
+        assert!(outside.exists());
+        fs::remove_file(&outside).ok();
+        cleanup(&drive);
+    }
+}
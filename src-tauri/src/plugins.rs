@@ -1,15 +1,68 @@
 use anyhow::Result;
 use std::fs;
-use std::path::Path;
-use url::Url;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use tauri::command;
-use crate::download::{download_plugin_file, get_file_info};
+use crate::download::{download_plugin_file, get_source_filename};
+use crate::plugin_manifest::{
+    generate_plugin_id, load_plugin_manifest, parse_plugin_version, sidecar_path, ManifestSource,
+    PluginDependency, PluginManifest,
+};
+use crate::plugin_batch;
+use crate::plugin_inspect::inspect_package;
+use crate::plugin_recycle;
 use reqwest::Client;
 
 const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36 Edg/138.0.0.0";
 
+lazy_static::lazy_static! {
+    // 正在下载（或将被下载替换）的插件文件，卸载时需要避开
+    static ref ACTIVE_PLUGIN_FILES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+fn file_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+// 下载期间占用插件文件，离开作用域后自动释放
+pub(crate) struct PluginFileGuard {
+    keys: Vec<String>,
+}
+
+impl PluginFileGuard {
+    pub(crate) fn acquire(paths: &[&Path]) -> Result<Self, String> {
+        let mut active = ACTIVE_PLUGIN_FILES.lock().unwrap();
+        let keys: Vec<String> = paths.iter().map(|p| file_key(p)).collect();
+        if let Some(busy) = keys.iter().find(|k| active.contains(*k)) {
+            return Err(format!("文件 {} 正在下载中", busy));
+        }
+        for key in &keys {
+            active.insert(key.clone());
+        }
+        Ok(Self { keys })
+    }
+}
+
+impl Drop for PluginFileGuard {
+    fn drop(&mut self) {
+        let mut active = ACTIVE_PLUGIN_FILES.lock().unwrap();
+        for key in &self.keys {
+            active.remove(key);
+        }
+    }
+}
+
+// 下载完成后检查插件包是否完整，避免把损坏的文件放进 ce-apps
+fn check_package(path: &Path) -> Result<(), String> {
+    let inspection = inspect_package(path, false).map_err(|e| e.to_string())?;
+    match inspection.problems.first() {
+        Some(problem) => Err(format!("插件包无效: {}", problem)),
+        None => Ok(()),
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PluginInfo {
     name: String,
@@ -20,10 +73,9 @@ pub struct PluginInfo {
     file: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     id: Option<String>,
-}
-
-fn generate_plugin_id(name: &str, author: &str) -> String {
-    format!("{}|{}", name, author)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    source: ManifestSource,
 }
 
 #[command]
@@ -34,7 +86,6 @@ pub async fn download_plugin(
     threads: Option<u32>,
 ) -> Result<String, String> {
     let thread_count = threads.unwrap_or(8) as u16;
-    let url_parsed = Url::parse(&url).map_err(|e| e.to_string())?;
 
     let download_dir = Path::new(&path);
     if !download_dir.exists() {
@@ -47,18 +98,39 @@ pub async fn download_plugin(
         .build()
         .map_err(|e| e.to_string())?;
 
-    let (_, filename, _, _) = get_file_info(&client, &url_parsed)
+    let filename = get_source_filename(&client, &url)
         .await
         .map_err(|e| e.to_string())?;
 
     let final_filename = file_name.unwrap_or(filename);
     let file_path = download_dir.join(&final_filename);
+    let _guard = PluginFileGuard::acquire(&[&file_path])?;
 
-    download_plugin_file(url, file_path, thread_count)
+    let saved_path = download_plugin_file(url, file_path.clone(), thread_count)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = check_package(&file_path) {
+        fs::remove_file(&file_path).ok();
+        return Err(e);
+    }
+    Ok(saved_path)
 }
 
+// 前端传入的是 <盘符>\ce-apps，批量安装以盘符为准
+fn drive_from_ce_apps(path: &str) -> Result<String, String> {
+    const SUFFIX: &str = "\\ce-apps";
+    let trimmed = path.trim_end_matches('\\');
+    match trimmed.len().checked_sub(SUFFIX.len()) {
+        Some(split)
+            if trimmed.is_char_boundary(split) && trimmed[split..].eq_ignore_ascii_case(SUFFIX) =>
+        {
+            Ok(trimmed[..split].to_string())
+        }
+        _ => Err(format!("无效的插件目录: {}", path)),
+    }
+}
+
+// 更新单个插件，与批量安装走同一流程：新版本下载并校验通过后才替换旧文件，替换失败时恢复旧版本
 #[command]
 pub async fn update_plugin(
     url: String,
@@ -68,43 +140,62 @@ pub async fn update_plugin(
     threads: Option<u32>,
 ) -> Result<String, String> {
     let thread_count = threads.unwrap_or(8) as u16;
-    let url_parsed = Url::parse(&url).map_err(|e| e.to_string())?;
+    let drive_letter = drive_from_ce_apps(&path)?;
 
     let download_dir = Path::new(&path);
     if !download_dir.exists() {
         fs::create_dir_all(download_dir).map_err(|e| e.to_string())?;
     }
 
-    let client = Client::builder()
-        .user_agent(USER_AGENT)
-        .connect_timeout(std::time::Duration::from_secs(60))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let item = PlannedInstall {
+        id: String::new(),
+        name: new_file_name.clone(),
+        version: String::new(),
+        url,
+        file_name: new_file_name.clone(),
+        replaces: Some(old_file_name),
+        sha256: None,
+    };
+    plugin_batch::install_batch(&drive_letter, &[item], thread_count).await?;
 
-    let (_, _, _, _) = get_file_info(&client, &url_parsed)
-        .await
-        .map_err(|e| e.to_string())?;
+    Ok(download_dir.join(&new_file_name).to_string_lossy().to_string())
+}
 
-    let temp_filename = format!("{}.tmp", new_file_name);
-    let temp_file_path = download_dir.join(&temp_filename);
-    let final_file_path = download_dir.join(&new_file_name);
-    let old_file_path = download_dir.join(&old_file_name);
+// 读取失败的插件文件，返回给前端提示而不是直接隐藏
+#[derive(Clone, Serialize)]
+pub struct PluginLoadError {
+    file: String,
+    error: String,
+}
 
-    download_plugin_file(url, temp_file_path.clone(), thread_count)
-        .await
-        .map_err(|e| e.to_string())?;
+#[derive(Clone, Serialize)]
+pub struct PluginFiles {
+    enabled: Vec<PluginInfo>,
+    disabled: Vec<PluginInfo>,
+    errors: Vec<PluginLoadError>,
+}
 
-    if old_file_path.exists() {
-        fs::remove_file(&old_file_path).map_err(|e| e.to_string())?;
-    }
+fn read_plugin_info(path: &Path, file_name: String, enabled: bool) -> Result<PluginInfo> {
+    let (manifest, source) = load_plugin_manifest(path)?;
 
-    fs::rename(&temp_file_path, &final_file_path).map_err(|e| e.to_string())?;
+    let metadata = fs::metadata(path)?;
+    let size = format!("{:.2} MB", metadata.len() as f64 / 1024.0 / 1024.0);
 
-    Ok(final_file_path.to_string_lossy().to_string())
+    Ok(PluginInfo {
+        name: manifest.name,
+        size,
+        version: manifest.version,
+        author: manifest.author,
+        describe: manifest.description,
+        file: file_name,
+        id: if enabled { Some(manifest.id) } else { None },
+        category: manifest.category,
+        source,
+    })
 }
 
 #[command]
-pub fn get_plugin_files(drive_letter: String) -> Result<HashMap<String, Vec<PluginInfo>>, String> {
+pub fn get_plugin_files(drive_letter: String) -> Result<PluginFiles, String> {
     let ce_apps_dir = format!("{}\\ce-apps", drive_letter);
     let dir_path = Path::new(&ce_apps_dir);
 
@@ -114,6 +205,7 @@ pub fn get_plugin_files(drive_letter: String) -> Result<HashMap<String, Vec<Plug
 
     let mut enabled_plugins = Vec::new();
     let mut disabled_plugins = Vec::new();
+    let mut errors = Vec::new();
 
     let entries = fs::read_dir(dir_path).map_err(|e| e.to_string())?;
 
@@ -127,43 +219,17 @@ pub fn get_plugin_files(drive_letter: String) -> Result<HashMap<String, Vec<Plug
 
                 if extension_str == "ce" || extension_str == "cbk" {
                     let file_name = path.file_name().unwrap().to_string_lossy().to_string();
-                    let parts: Vec<&str> = file_name.split('_').collect();
-
-                    if parts.len() >= 4 {
-                        let name = parts[0].to_string();
-                        let version = parts[1].to_string();
-                        let author = parts[2].to_string();
-
-                        let describe_with_ext = parts[3..].join("_");
-                        let describe = describe_with_ext
-                            .strip_suffix(".ce")
-                            .or_else(|| describe_with_ext.strip_suffix(".CBK"))
-                            .unwrap_or(&describe_with_ext)
-                            .to_string();
-
-                        let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
-                        let size = format!("{:.2} MB", metadata.len() as f64 / 1024.0 / 1024.0);
-
-                        let id = if extension_str == "ce" {
-                            Some(generate_plugin_id(&name, &author))
-                        } else {
-                            None
-                        };
-
-                        let plugin_info = PluginInfo {
-                            name,
-                            size,
-                            version,
-                            author,
-                            describe,
-                            file: file_name,
-                            id,
-                        };
-
-                        if extension_str == "ce" {
-                            enabled_plugins.push(plugin_info);
-                        } else {
-                            disabled_plugins.push(plugin_info);
+                    let enabled = extension_str == "ce";
+
+                    match read_plugin_info(&path, file_name.clone(), enabled) {
+                        Ok(plugin_info) if enabled => enabled_plugins.push(plugin_info),
+                        Ok(plugin_info) => disabled_plugins.push(plugin_info),
+                        Err(e) => {
+                            println!("读取插件 {} 失败: {}", file_name, e);
+                            errors.push(PluginLoadError {
+                                file: file_name,
+                                error: e.to_string(),
+                            });
                         }
                     }
                 }
@@ -171,11 +237,11 @@ pub fn get_plugin_files(drive_letter: String) -> Result<HashMap<String, Vec<Plug
         }
     }
 
-    let mut result = HashMap::new();
-    result.insert("enabled".to_string(), enabled_plugins);
-    result.insert("disabled".to_string(), disabled_plugins);
-
-    Ok(result)
+    Ok(PluginFiles {
+        enabled: enabled_plugins,
+        disabled: disabled_plugins,
+        errors,
+    })
 }
 
 #[command]
@@ -207,7 +273,11 @@ pub fn enable_plugin(drive_letter: String, file_name: String) -> Result<bool, St
 }
 
 #[command]
-pub fn disable_plugin(drive_letter: String, file_name: String) -> Result<bool, String> {
+pub fn disable_plugin(
+    drive_letter: String,
+    file_name: String,
+    force: Option<bool>,
+) -> Result<bool, String> {
     let ce_apps_dir = format!("{}\\ce-apps", drive_letter);
     let dir_path = Path::new(&ce_apps_dir);
 
@@ -226,6 +296,14 @@ pub fn disable_plugin(drive_letter: String, file_name: String) -> Result<bool, S
         }
     }
 
+    // 仍有已启用的插件依赖它时，需要用户强制确认
+    if !force.unwrap_or(false) {
+        let plan = plan_plugin_disable(drive_letter.clone(), file_name.clone())?;
+        if !plan.problems.is_empty() {
+            return Err(format!("无法禁用插件: {}", plan.problems.join("；")));
+        }
+    }
+
     let new_file_name = file_name.replace(".ce", ".CBK");
     let new_file_path = dir_path.join(&new_file_name);
 
@@ -233,3 +311,1015 @@ pub fn disable_plugin(drive_letter: String, file_name: String) -> Result<bool, S
 
     Ok(true)
 }
+
+// 插件市场等来源中可安装的插件，依赖和冲突信息可选
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PluginCandidate {
+    name: String,
+    version: String,
+    author: String,
+    #[serde(default)]
+    describe: String,
+    link: String,
+    #[serde(default)]
+    dependencies: Vec<PluginDependency>,
+    #[serde(default)]
+    conflicts: Vec<PluginDependency>,
+}
+
+impl PluginCandidate {
+    fn id(&self) -> String {
+        generate_plugin_id(&self.name, &self.author)
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}_{}_{}_{}.ce",
+            self.name, self.version, self.author, self.describe
+        )
+    }
+}
+
+// 计划下载的插件，replaces 为下载完成后需要删除的旧版本文件
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedInstall {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) url: String,
+    pub(crate) file_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) replaces: Option<String>,
+    // 已知时用于校验下载的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sha256: Option<String>,
+}
+
+// 安装、启用或禁用插件前计算出的完整操作，problems 不为空时需要用户确认
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PluginPlan {
+    install: Vec<PlannedInstall>,
+    enable: Vec<String>,
+    disable: Vec<String>,
+    problems: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request: Option<PlanRequest>,
+}
+
+// 启动盘上已有的插件
+pub(crate) struct LocalPlugin {
+    pub(crate) manifest: PluginManifest,
+    pub(crate) file: String,
+    pub(crate) enabled: bool,
+}
+
+pub(crate) fn ce_apps_path(drive_letter: &str) -> Result<PathBuf, String> {
+    let ce_apps_dir = format!("{}\\ce-apps", drive_letter);
+    let dir_path = PathBuf::from(&ce_apps_dir);
+    if !dir_path.exists() {
+        return Err(format!("目录 {} 不存在", ce_apps_dir));
+    }
+    Ok(dir_path)
+}
+
+// 读取所有能识别的插件，无法识别的由 get_plugin_files 单独报告
+pub(crate) fn scan_local_plugins(dir_path: &Path) -> Result<Vec<LocalPlugin>, String> {
+    let mut plugins = Vec::new();
+    for entry in fs::read_dir(dir_path).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if extension != "ce" && extension != "cbk" {
+            continue;
+        }
+        if let Ok((manifest, _)) = load_plugin_manifest(&path) {
+            plugins.push(LocalPlugin {
+                manifest,
+                file: path.file_name().unwrap().to_string_lossy().to_string(),
+                enabled: extension == "ce",
+            });
+        }
+    }
+    Ok(plugins)
+}
+
+// 解析过程中的插件：已在启动盘上，或需要从插件市场下载
+#[derive(Clone, Copy)]
+enum PlanNode<'a> {
+    Local(&'a LocalPlugin),
+    Candidate(&'a PluginCandidate),
+}
+
+impl<'a> PlanNode<'a> {
+    fn id(&self) -> String {
+        match *self {
+            PlanNode::Local(p) => p.manifest.id.clone(),
+            PlanNode::Candidate(c) => c.id(),
+        }
+    }
+
+    fn name(&self) -> &'a str {
+        match *self {
+            PlanNode::Local(p) => &p.manifest.name,
+            PlanNode::Candidate(c) => &c.name,
+        }
+    }
+
+    fn version(&self) -> &'a str {
+        match *self {
+            PlanNode::Local(p) => &p.manifest.version,
+            PlanNode::Candidate(c) => &c.version,
+        }
+    }
+
+    fn dependencies(&self) -> &'a [PluginDependency] {
+        match *self {
+            PlanNode::Local(p) => &p.manifest.dependencies,
+            PlanNode::Candidate(c) => &c.dependencies,
+        }
+    }
+
+    fn conflicts(&self) -> &'a [PluginDependency] {
+        match *self {
+            PlanNode::Local(p) => &p.manifest.conflicts,
+            PlanNode::Candidate(c) => &c.conflicts,
+        }
+    }
+}
+
+struct PluginResolver<'a> {
+    local: &'a [LocalPlugin],
+    available: &'a [PluginCandidate],
+    // 执行计划后会处于启用状态的新增或新启用插件
+    planned: Vec<PlanNode<'a>>,
+    visiting: HashSet<String>,
+    plan: PluginPlan,
+}
+
+impl<'a> PluginResolver<'a> {
+    fn new(local: &'a [LocalPlugin], available: &'a [PluginCandidate]) -> Self {
+        Self {
+            local,
+            available,
+            planned: Vec::new(),
+            visiting: HashSet::new(),
+            plan: PluginPlan::default(),
+        }
+    }
+
+    fn planned_version(&self, id: &str) -> Option<&'a str> {
+        self.planned
+            .iter()
+            .find(|n| n.id() == id)
+            .map(|n| n.version())
+    }
+
+    // 先处理依赖，再把插件本身加入计划，保证下载和启用顺序正确
+    fn require(&mut self, node: PlanNode<'a>) {
+        let id = node.id();
+        if self.planned_version(&id).is_some() || !self.visiting.insert(id.clone()) {
+            return;
+        }
+
+        for dependency in node.dependencies() {
+            self.resolve_dependency(dependency, node.name());
+        }
+
+        match node {
+            PlanNode::Local(p) if !p.enabled => self.plan.enable.push(p.file.clone()),
+            PlanNode::Local(_) => {}
+            PlanNode::Candidate(c) => {
+                let replaces = self
+                    .local
+                    .iter()
+                    .find(|p| p.manifest.id == id)
+                    .map(|p| p.file.clone());
+                self.plan.install.push(PlannedInstall {
+                    id: id.clone(),
+                    name: c.name.clone(),
+                    version: c.version.clone(),
+                    url: c.link.clone(),
+                    file_name: c.file_name(),
+                    replaces,
+                    sha256: None,
+                });
+            }
+        }
+        self.planned.push(node);
+    }
+
+    fn resolve_dependency(&mut self, dependency: &PluginDependency, required_by: &str) {
+        if let Some(version) = self.planned_version(&dependency.id) {
+            if !dependency.matches(version) {
+                self.plan.problems.push(format!(
+                    "{} 需要 {}，但计划使用的版本为 {}",
+                    required_by,
+                    dependency.describe(),
+                    version
+                ));
+            }
+            return;
+        }
+
+        // 优先使用已启用的版本，其次启用已禁用的版本，最后从插件市场下载
+        let local = self.local;
+        let available = self.available;
+        let same_id = |enabled: bool| {
+            local
+                .iter()
+                .filter(move |p| p.enabled == enabled && p.manifest.id == dependency.id)
+        };
+
+        if let Some(p) = best_match(same_id(true), dependency, |p| p.manifest.version.as_str()) {
+            self.require(PlanNode::Local(p));
+        } else if let Some(p) =
+            best_match(same_id(false), dependency, |p| p.manifest.version.as_str())
+        {
+            self.require(PlanNode::Local(p));
+        } else if let Some(c) = best_match(
+            available.iter().filter(|c| c.id() == dependency.id),
+            dependency,
+            |c| c.version.as_str(),
+        ) {
+            self.require(PlanNode::Candidate(c));
+        } else {
+            let installed: Vec<String> =
+                same_id(true).map(|p| p.manifest.version.clone()).collect();
+            self.plan.problems.push(if installed.is_empty() {
+                format!("{} 缺少依赖 {}", required_by, dependency.describe())
+            } else {
+                format!(
+                    "{} 需要 {}，已安装的版本 {} 不满足且插件市场中没有合适的版本",
+                    required_by,
+                    dependency.describe(),
+                    installed.join(", ")
+                )
+            });
+        }
+    }
+
+    // 禁用与计划中插件冲突的已启用插件，并检查计划中的插件之间是否冲突
+    fn resolve_conflicts(&mut self) {
+        let replaced: HashSet<String> = self
+            .plan
+            .install
+            .iter()
+            .filter_map(|i| i.replaces.clone())
+            .collect();
+        let planned_ids: HashSet<String> = self.planned.iter().map(|n| n.id()).collect();
+        let local = self.local;
+        let active: Vec<&LocalPlugin> = local
+            .iter()
+            .filter(|p| p.enabled && !replaced.contains(&p.file))
+            .filter(|p| !planned_ids.contains(&p.manifest.id))
+            .collect();
+
+        for node in self.planned.clone() {
+            for conflict in node.conflicts() {
+                for other in &self.planned {
+                    if other.id() == conflict.id && conflict.matches(other.version()) {
+                        self.plan.problems.push(format!(
+                            "{} 与 {} 冲突，无法同时启用",
+                            node.name(),
+                            other.name()
+                        ));
+                    }
+                }
+            }
+
+            for p in &active {
+                let conflicts_with_local = node
+                    .conflicts()
+                    .iter()
+                    .any(|c| c.id == p.manifest.id && c.matches(&p.manifest.version));
+                let local_conflicts = p
+                    .manifest
+                    .conflicts
+                    .iter()
+                    .any(|c| c.id == node.id() && c.matches(node.version()));
+                if (conflicts_with_local || local_conflicts) && !self.plan.disable.contains(&p.file)
+                {
+                    self.plan.disable.push(p.file.clone());
+                }
+            }
+        }
+
+        // 被禁用的插件如果仍被其他插件依赖，需要用户确认
+        let disabled: Vec<&LocalPlugin> = active
+            .iter()
+            .copied()
+            .filter(|p| self.plan.disable.contains(&p.file))
+            .collect();
+        for p in disabled {
+            for dependent in enabled_dependents(local, &p.manifest.id) {
+                if !self.plan.disable.contains(&dependent.file) {
+                    self.plan.problems.push(format!(
+                        "禁用冲突插件 {} 会影响依赖它的 {}",
+                        p.manifest.name, dependent.manifest.name
+                    ));
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> PluginPlan {
+        self.resolve_conflicts();
+        self.plan
+    }
+}
+
+// 在可选插件中挑选满足约束的最高版本
+fn best_match<'a, T>(
+    items: impl Iterator<Item = &'a T>,
+    dependency: &PluginDependency,
+    version_of: impl Fn(&T) -> &str,
+) -> Option<&'a T>
+where
+    T: 'a,
+{
+    items
+        .filter(|item| dependency.matches(version_of(item)))
+        .max_by(|a, b| {
+            parse_plugin_version(version_of(a)).cmp(&parse_plugin_version(version_of(b)))
+        })
+}
+
+// 直接或间接依赖指定插件的已启用插件
+fn enabled_dependents<'a>(local: &'a [LocalPlugin], id: &str) -> Vec<&'a LocalPlugin> {
+    let mut result: Vec<&LocalPlugin> = Vec::new();
+    let mut pending = vec![id.to_string()];
+    while let Some(current) = pending.pop() {
+        for p in local.iter().filter(|p| p.enabled && p.manifest.id != id) {
+            let depends = p.manifest.dependencies.iter().any(|d| d.id == current);
+            if depends && !result.iter().any(|r| r.file == p.file) {
+                pending.push(p.manifest.id.clone());
+                result.push(p);
+            }
+        }
+    }
+    result
+}
+
+// 生成计划时的请求，执行前在后端按启动盘的当前状态重新解析
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum PlanRequest {
+    Install {
+        plugin: PluginCandidate,
+        #[serde(default)]
+        available: Vec<PluginCandidate>,
+    },
+    Enable {
+        file_name: String,
+        #[serde(default)]
+        available: Vec<PluginCandidate>,
+    },
+    Disable {
+        file_name: String,
+    },
+}
+
+fn resolve_request(local: &[LocalPlugin], request: &PlanRequest) -> Result<PluginPlan, String> {
+    let mut plan = match request {
+        PlanRequest::Install { plugin, available } => {
+            let mut resolver = PluginResolver::new(local, available);
+            resolver.require(PlanNode::Candidate(plugin));
+            resolver.finish()
+        }
+        PlanRequest::Enable {
+            file_name,
+            available,
+        } => {
+            let target = local
+                .iter()
+                .find(|p| &p.file == file_name)
+                .ok_or_else(|| format!("无法读取插件 {}", file_name))?;
+            let mut resolver = PluginResolver::new(local, available);
+            resolver.require(PlanNode::Local(target));
+            resolver.finish()
+        }
+        PlanRequest::Disable { file_name } => {
+            let mut plan = PluginPlan {
+                disable: vec![file_name.clone()],
+                ..Default::default()
+            };
+            if let Some(target) = local.iter().find(|p| &p.file == file_name) {
+                for dependent in enabled_dependents(local, &target.manifest.id) {
+                    plan.problems.push(format!(
+                        "{} 依赖 {}，禁用后可能无法使用",
+                        dependent.manifest.name, target.manifest.name
+                    ));
+                }
+            }
+            plan
+        }
+    };
+    plan.request = Some(request.clone());
+    Ok(plan)
+}
+
+fn plan_for(drive_letter: &str, request: PlanRequest) -> Result<PluginPlan, String> {
+    let local = scan_local_plugins(&ce_apps_path(drive_letter)?)?;
+    resolve_request(&local, &request)
+}
+
+#[command]
+pub fn plan_plugin_install(
+    drive_letter: String,
+    plugin: PluginCandidate,
+    available: Option<Vec<PluginCandidate>>,
+) -> Result<PluginPlan, String> {
+    plan_for(
+        &drive_letter,
+        PlanRequest::Install {
+            plugin,
+            available: available.unwrap_or_default(),
+        },
+    )
+}
+
+#[command]
+pub fn plan_plugin_enable(
+    drive_letter: String,
+    file_name: String,
+    available: Option<Vec<PluginCandidate>>,
+) -> Result<PluginPlan, String> {
+    plan_for(
+        &drive_letter,
+        PlanRequest::Enable {
+            file_name,
+            available: available.unwrap_or_default(),
+        },
+    )
+}
+
+#[command]
+pub fn plan_plugin_disable(drive_letter: String, file_name: String) -> Result<PluginPlan, String> {
+    plan_for(&drive_letter, PlanRequest::Disable { file_name })
+}
+
+// 依次禁用、启用插件，记录完成的重命名（原文件名, 新文件名）以便失败时撤销
+pub fn apply_renames(
+    drive_letter: &str,
+    disable: &[String],
+    enable: &[String],
+    renamed: &mut Vec<(String, String)>,
+) -> Result<(), String> {
+    for file_name in disable {
+        disable_plugin(drive_letter.to_string(), file_name.clone(), Some(true))?;
+        let new_name = file_name.replace(".ce", ".CBK");
+        if &new_name != file_name {
+            renamed.push((file_name.clone(), new_name));
+        }
+    }
+    for file_name in enable {
+        enable_plugin(drive_letter.to_string(), file_name.clone())?;
+        let new_name = file_name.replace(".CBK", ".ce");
+        if &new_name != file_name {
+            renamed.push((file_name.clone(), new_name));
+        }
+    }
+    Ok(())
+}
+
+pub fn undo_renames(dir_path: &Path, renamed: &[(String, String)]) {
+    for (original, new_name) in renamed.iter().rev() {
+        if let Err(e) = fs::rename(dir_path.join(new_name), dir_path.join(original)) {
+            println!("恢复插件 {} 失败: {}", original, e);
+        }
+    }
+}
+
+// 按启动盘的当前状态重新解析计划后执行：先禁用冲突插件，再启用已有插件，最后批量安装新插件。
+// 任何一步失败都会撤销已完成的启用和禁用
+#[command]
+pub async fn apply_plugin_plan(
+    drive_letter: String,
+    plan: PluginPlan,
+    force: Option<bool>,
+    threads: Option<u32>,
+) -> Result<bool, String> {
+    let request = plan.request.ok_or("插件计划缺少请求信息，请重新生成计划")?;
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let plan = resolve_request(&scan_local_plugins(&dir_path)?, &request)?;
+    if !plan.problems.is_empty() && !force.unwrap_or(false) {
+        return Err(format!("插件计划存在问题: {}", plan.problems.join("；")));
+    }
+
+    let thread_count = threads.unwrap_or(8) as u16;
+    let mut renamed = Vec::new();
+    let mut outcome = apply_renames(&drive_letter, &plan.disable, &plan.enable, &mut renamed);
+    if outcome.is_ok() {
+        // 新插件整批下载、校验后再替换，失败时不会留下缺失的插件
+        outcome = plugin_batch::install_batch(&drive_letter, &plan.install, thread_count)
+            .await
+            .map(|_| ());
+    }
+    if let Err(e) = outcome {
+        println!("执行插件计划失败，正在撤销已启用和禁用的插件: {}", e);
+        undo_renames(&dir_path, &renamed);
+        return Err(e);
+    }
+
+    Ok(true)
+}
+
+// 同一插件的某个版本文件
+#[derive(Clone, Serialize)]
+pub struct DuplicateFile {
+    file: String,
+    version: String,
+    enabled: bool,
+}
+
+// 同一 ID 存在多个版本时的检测结果，winner 为保留的文件
+#[derive(Clone, Serialize)]
+pub struct DuplicateGroup {
+    id: String,
+    name: String,
+    winner: String,
+    files: Vec<DuplicateFile>,
+}
+
+#[derive(Clone, Default, Serialize)]
+pub struct DuplicateCleanup {
+    kept: Vec<String>,
+    disabled: Vec<String>,
+    removed: Vec<String>,
+    // 正在下载中、本次未处理的文件
+    skipped: Vec<String>,
+}
+
+fn modified_time(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// 按版本号挑选保留的文件，版本相同时优先已启用的，再比较修改时间
+pub(crate) fn pick_winner<'a>(dir_path: &Path, group: &[&'a LocalPlugin]) -> &'a LocalPlugin {
+    group
+        .iter()
+        .copied()
+        .max_by(|a, b| {
+            a.manifest
+                .semver()
+                .cmp(&b.manifest.semver())
+                .then(a.enabled.cmp(&b.enabled))
+                .then_with(|| {
+                    modified_time(&dir_path.join(&a.file))
+                        .cmp(&modified_time(&dir_path.join(&b.file)))
+                })
+        })
+        .unwrap()
+}
+
+fn duplicate_groups(dir_path: &Path, local: &[LocalPlugin]) -> Vec<DuplicateGroup> {
+    let mut by_id: Vec<(String, Vec<&LocalPlugin>)> = Vec::new();
+    for p in local {
+        match by_id.iter_mut().find(|(id, _)| *id == p.manifest.id) {
+            Some((_, group)) => group.push(p),
+            None => by_id.push((p.manifest.id.clone(), vec![p])),
+        }
+    }
+
+    by_id
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|(id, group)| {
+            let winner = pick_winner(dir_path, &group);
+            DuplicateGroup {
+                id,
+                name: winner.manifest.name.clone(),
+                winner: winner.file.clone(),
+                files: group
+                    .iter()
+                    .map(|p| DuplicateFile {
+                        file: p.file.clone(),
+                        version: p.manifest.version.clone(),
+                        enabled: p.enabled,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+// 检测启用和禁用目录中同一插件（名称|作者）的多个版本
+#[command]
+pub fn find_duplicate_plugins(drive_letter: String) -> Result<Vec<DuplicateGroup>, String> {
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let local = scan_local_plugins(&dir_path)?;
+    Ok(duplicate_groups(&dir_path, &local))
+}
+
+// 每组只保留最新版本，其余禁用（remove 为 true 时删除）。
+// 组内有任一版本处于启用状态时，保留的版本也会被启用
+#[command]
+pub fn cleanup_duplicate_plugins(
+    drive_letter: String,
+    remove: Option<bool>,
+) -> Result<DuplicateCleanup, String> {
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let local = scan_local_plugins(&dir_path)?;
+    let remove = remove.unwrap_or(false);
+    let mut result = DuplicateCleanup::default();
+    if remove {
+        plugin_recycle::purge_expired(&drive_letter);
+    }
+
+    for group in duplicate_groups(&dir_path, &local) {
+        let any_enabled = group.files.iter().any(|f| f.enabled);
+
+        for file in group.files.iter().filter(|f| f.file != group.winner) {
+            // 已禁用的旧版本不会被加载，只在删除模式下处理
+            if !remove && !file.enabled {
+                continue;
+            }
+
+            let path = dir_path.join(&file.file);
+            // 处理完成前一直占用，避免检查之后又开始下载同一个文件
+            let _guard = match PluginFileGuard::acquire(&[&path]) {
+                Ok(guard) => guard,
+                Err(_) => {
+                    println!("重复插件正在下载中，跳过: {}", file.file);
+                    result.skipped.push(file.file.clone());
+                    continue;
+                }
+            };
+            // 禁用后会与已有的 .CBK 文件重名时只能删除
+            let collides = file.enabled && dir_path.join(file.file.replace(".ce", ".CBK")).exists();
+
+            if remove || collides {
+                // 删除的文件连同附属清单放入回收区，保留期内可以撤销
+                let sidecar = sidecar_path(&path);
+                plugin_recycle::recycle_file(&drive_letter, &group.id, &file.file, Some(&sidecar))
+                    .map_err(|e| format!("删除 {} 失败: {}", file.file, e))?;
+                println!("重复插件已移到回收区: {}", file.file);
+                result.removed.push(file.file.clone());
+            } else {
+                disable_plugin(drive_letter.clone(), file.file.clone(), Some(true))?;
+                println!("禁用重复插件: {}", file.file);
+                result.disabled.push(file.file.clone());
+            }
+        }
+
+        let winner_enabled = group
+            .files
+            .iter()
+            .any(|f| f.file == group.winner && f.enabled);
+        if any_enabled && !winner_enabled {
+            enable_plugin(drive_letter.clone(), group.winner.clone())?;
+            result.kept.push(group.winner.replace(".CBK", ".ce"));
+        } else {
+            result.kept.push(group.winner);
+        }
+    }
+
+    Ok(result)
+}
+
+// 要卸载的插件，id 用于确认文件确实属于该插件
+#[derive(Clone, Deserialize)]
+pub struct UninstallTarget {
+    #[serde(default)]
+    id: String,
+    file: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UninstalledPlugin {
+    id: String,
+    file: String,
+    size: u64,
+    // 放入回收区时用于撤销
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UninstallResult {
+    removed: Vec<UninstalledPlugin>,
+    errors: Vec<PluginLoadError>,
+    // 已彻底删除、实际释放的空间
+    reclaimed_bytes: u64,
+    // 放入回收区、清空回收区后才会释放的空间
+    recycled_bytes: u64,
+}
+
+fn uninstall_one(
+    drive_letter: &str,
+    dir_path: &Path,
+    target: &UninstallTarget,
+    recycle: bool,
+) -> Result<UninstalledPlugin> {
+    let path = dir_path.join(&target.file);
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if target.file.contains(['/', '\\']) || (extension != "ce" && extension != "cbk") {
+        anyhow::bail!("不是插件文件");
+    }
+    if !path.is_file() {
+        anyhow::bail!("文件不存在");
+    }
+    // 占用到移走文件为止，避免检查之后又开始下载同一个文件
+    let _guard = PluginFileGuard::acquire(&[&path])
+        .map_err(|_| anyhow::anyhow!("插件正在下载中，请等待下载完成后再卸载"))?;
+
+    // 核对插件 ID，避免误删同目录下的其他插件；无法识别的文件允许不带 ID 删除
+    let id = match load_plugin_manifest(&path) {
+        Ok((manifest, _)) => {
+            let legacy_id = generate_plugin_id(&manifest.name, &manifest.author);
+            if target.id != manifest.id && target.id != legacy_id {
+                anyhow::bail!("文件与插件 {} 不匹配", target.id);
+            }
+            manifest.id
+        }
+        Err(_) if target.id.is_empty() => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    let size = fs::metadata(&path)?.len();
+    let sidecar = sidecar_path(&path);
+
+    if recycle {
+        let entry = plugin_recycle::recycle_file(drive_letter, &id, &target.file, Some(&sidecar))?;
+        println!("插件已移到回收区: {}", target.file);
+        return Ok(UninstalledPlugin {
+            id,
+            file: target.file.clone(),
+            size,
+            token: Some(entry.token),
+        });
+    }
+
+    fs::remove_file(&path)?;
+    fs::remove_file(&sidecar).ok();
+    println!("插件已删除: {}", target.file);
+    Ok(UninstalledPlugin {
+        id,
+        file: target.file.clone(),
+        size,
+        token: None,
+    })
+}
+
+// 批量卸载插件，单个失败不影响其他插件
+#[command]
+pub fn uninstall_plugins(
+    drive_letter: String,
+    targets: Vec<UninstallTarget>,
+    recycle: Option<bool>,
+) -> Result<UninstallResult, String> {
+    let dir_path = ce_apps_path(&drive_letter)?;
+    let recycle = recycle.unwrap_or(true);
+    let mut result = UninstallResult {
+        reclaimed_bytes: plugin_recycle::purge_expired(&drive_letter),
+        ..Default::default()
+    };
+
+    for target in &targets {
+        match uninstall_one(&drive_letter, &dir_path, target, recycle) {
+            Ok(removed) => {
+                if recycle {
+                    result.recycled_bytes += removed.size;
+                } else {
+                    result.reclaimed_bytes += removed.size;
+                }
+                result.removed.push(removed);
+            }
+            Err(e) => {
+                println!("卸载插件 {} 失败: {}", target.file, e);
+                result.errors.push(PluginLoadError {
+                    file: target.file.clone(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[command]
+pub fn uninstall_plugin(
+    drive_letter: String,
+    id: String,
+    file_name: String,
+    recycle: Option<bool>,
+) -> Result<UninstallResult, String> {
+    let result = uninstall_plugins(
+        drive_letter,
+        vec![UninstallTarget {
+            id,
+            file: file_name,
+        }],
+        recycle,
+    )?;
+    match result.errors.first() {
+        Some(error) => Err(error.error.clone()),
+        None => Ok(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, version: &str, deps: &[&str], conflicts: &[&str]) -> PluginManifest {
+        let list = |ids: &[&str]| -> Vec<serde_json::Value> {
+            ids.iter()
+                .map(|id| serde_json::json!({ "id": id }))
+                .collect()
+        };
+        serde_json::from_value(serde_json::json!({
+            "id": generate_plugin_id(name, "test"),
+            "name": name,
+            "version": version,
+            "author": "test",
+            "dependencies": list(deps),
+            "conflicts": list(conflicts),
+        }))
+        .unwrap()
+    }
+
+    fn local(name: &str, deps: &[&str], conflicts: &[&str], enabled: bool) -> LocalPlugin {
+        LocalPlugin {
+            manifest: manifest(name, "1.0", deps, conflicts),
+            file: format!("{}_1.0_test_.{}", name, if enabled { "ce" } else { "CBK" }),
+            enabled,
+        }
+    }
+
+    fn candidate(name: &str, deps: &[&str], conflicts: &[&str]) -> PluginCandidate {
+        let m = manifest(name, "1.0", deps, conflicts);
+        PluginCandidate {
+            name: m.name,
+            version: m.version,
+            author: m.author,
+            describe: String::new(),
+            link: format!("https://example.com/{}.ce", name),
+            dependencies: m.dependencies,
+            conflicts: m.conflicts,
+        }
+    }
+
+    fn install(plugin: PluginCandidate, available: Vec<PluginCandidate>) -> PlanRequest {
+        PlanRequest::Install { plugin, available }
+    }
+
+    #[test]
+    fn conflict_disables_enabled_plugin() {
+        let local = vec![local("B", &[], &[], true)];
+        let request = install(candidate("A", &[], &["B|test"]), Vec::new());
+        let plan = resolve_request(&local, &request).unwrap();
+        assert_eq!(plan.install.len(), 1);
+        assert_eq!(plan.disable, vec!["B_1.0_test_.ce".to_string()]);
+        assert!(plan.problems.is_empty());
+    }
+
+    #[test]
+    fn conflicting_candidates_are_reported() {
+        let request = install(
+            candidate("A", &["B|test"], &["B|test"]),
+            vec![candidate("B", &[], &[])],
+        );
+        let plan = resolve_request(&[], &request).unwrap();
+        assert!(plan.problems.iter().any(|p| p.contains("冲突")));
+    }
+
+    #[test]
+    fn missing_dependency_is_reported() {
+        let request = install(candidate("A", &["B|test"], &[]), Vec::new());
+        let plan = resolve_request(&[], &request).unwrap();
+        assert_eq!(plan.install.len(), 1);
+        assert!(plan.problems.iter().any(|p| p.contains("缺少依赖")));
+    }
+
+    #[test]
+    fn dependency_is_installed_before_dependent() {
+        let request = install(
+            candidate("A", &["B|test"], &[]),
+            vec![candidate("B", &[], &[])],
+        );
+        let plan = resolve_request(&[], &request).unwrap();
+        let ids: Vec<&str> = plan.install.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["B|test", "A|test"]);
+        assert!(plan.problems.is_empty());
+    }
+
+    #[test]
+    fn dependency_cycle_terminates() {
+        let request = install(
+            candidate("A", &["B|test"], &[]),
+            vec![candidate("B", &["A|test"], &[])],
+        );
+        let plan = resolve_request(&[], &request).unwrap();
+        let mut ids: Vec<&str> = plan.install.iter().map(|i| i.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["A|test", "B|test"]);
+    }
+
+    #[test]
+    fn disabled_dependency_is_enabled() {
+        let local = vec![local("B", &[], &[], false)];
+        let request = install(candidate("A", &["B|test"], &[]), Vec::new());
+        let plan = resolve_request(&local, &request).unwrap();
+        assert_eq!(plan.enable, vec!["B_1.0_test_.CBK".to_string()]);
+    }
+
+    #[test]
+    fn uninstall_skips_files_being_downloaded() {
+        let dir = std::env::temp_dir().join(format!("cloud-pe-uninstall-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("A_1.0_test_.ce");
+        fs::write(&path, b"plugin").unwrap();
+        let target = UninstallTarget {
+            id: generate_plugin_id("A", "test"),
+            file: "A_1.0_test_.ce".to_string(),
+        };
+
+        let guard = PluginFileGuard::acquire(&[&path]).unwrap();
+        assert!(uninstall_one("Z:", &dir, &target, false).is_err());
+        assert!(path.exists());
+        drop(guard);
+
+        assert!(uninstall_one("Z:", &dir, &target, false).is_ok());
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn drive_from_ce_apps_path() {
+        assert_eq!(drive_from_ce_apps("E:\\ce-apps").unwrap(), "E:");
+        assert_eq!(drive_from_ce_apps("E:\\CE-APPS\\").unwrap(), "E:");
+        assert!(drive_from_ce_apps("E:\\other").is_err());
+        assert!(drive_from_ce_apps("").is_err());
+    }
+
+    #[test]
+    fn failed_update_keeps_the_old_version() {
+        let drive = std::env::temp_dir()
+            .join(format!("cloud-pe-update-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let ce_apps = format!("{}\\ce-apps", drive);
+        fs::create_dir_all(&ce_apps).unwrap();
+        let old = Path::new(&ce_apps).join("A_1.0_test_.ce");
+        fs::write(&old, b"old version").unwrap();
+
+        let missing = std::env::temp_dir().join("cloud-pe-missing-plugin.ce");
+        let url = url::Url::from_file_path(&missing).unwrap().to_string();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(update_plugin(
+            url,
+            ce_apps.clone(),
+            "A_1.0_test_.ce".to_string(),
+            "A_2.0_test_.ce".to_string(),
+            None,
+        ));
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&old).unwrap(), b"old version");
+        assert!(!Path::new(&ce_apps).join("A_2.0_test_.ce").exists());
+        fs::remove_dir_all(&ce_apps).ok();
+        fs::remove_dir_all(format!("{}\\cloud-pe\\staging", drive)).ok();
+    }
+    #[test]
+    fn failed_renames_are_undone() {
+        let drive = std::env::temp_dir()
+            .join(format!("cloud-pe-renames-{}", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let ce_apps = format!("{}\\ce-apps", drive);
+        fs::create_dir_all(&ce_apps).unwrap();
+        let dir = Path::new(&ce_apps);
+        fs::write(dir.join("A_1.0_test_.ce"), b"a").unwrap();
+        fs::write(dir.join("B_1.0_test_.CBK"), b"b").unwrap();
+
+        let disable = vec!["A_1.0_test_.ce".to_string()];
+        let enable = vec!["B_1.0_test_.CBK".to_string(), "C_1.0_test_.CBK".to_string()];
+        let mut renamed = Vec::new();
+        assert!(apply_renames(&drive, &disable, &enable, &mut renamed).is_err());
+        assert_eq!(renamed.len(), 2);
+
+        undo_renames(dir, &renamed);
+        assert!(dir.join("A_1.0_test_.ce").exists());
+        assert!(dir.join("B_1.0_test_.CBK").exists());
+        assert!(!dir.join("A_1.0_test_.CBK").exists());
+        assert!(!dir.join("B_1.0_test_.ce").exists());
+        fs::remove_dir_all(&ce_apps).ok();
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use minisign_verify::{PublicKey, Signature};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use url::Url;
+
+use crate::download::fetch_bytes;
+use crate::update_service::compare_versions;
+
+// 更新包签名公钥（minisign 公钥的 base64 部分），发布构建时通过环境变量
+// CLOUD_PE_UPDATE_PUBLIC_KEY 传入，未设置时拒绝所有更新包
+const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("CLOUD_PE_UPDATE_PUBLIC_KEY");
+const MAX_SIGNATURE_SIZE: u64 = 64 * 1024;
+const SIGNATURE_EXTENSION: &str = ".minisig";
+// 签名时在可信注释中写入目标版本，如 minisign -S -t "version:1.7.0" -m <更新包>，
+// 可信注释受签名保护，用来拒绝把旧版本的包和签名当作新版本重放
+const VERSION_TAG: &str = "version:";
+
+// 更新包对应的分离签名地址：<更新包地址>.minisig
+pub fn signature_url(package_url: &str) -> String {
+    if let Ok(mut url) = Url::parse(package_url) {
+        if matches!(url.scheme(), "http" | "https") {
+            let path = format!("{}{}", url.path(), SIGNATURE_EXTENSION);
+            url.set_path(&path);
+            return url.to_string();
+        }
+    }
+    format!("{}{}", package_url, SIGNATURE_EXTENSION)
+}
+
+// 下载签名并校验更新包及其签名的版本，未通过时返回错误
+pub async fn verify_update_package_from(
+    package: &Path,
+    signature_source: &str,
+    expected_version: &str,
+) -> Result<()> {
+    let signature = fetch_bytes(signature_source, MAX_SIGNATURE_SIZE)
+        .await
+        .map_err(|e| anyhow::anyhow!("无法获取更新包签名: {}", e))?;
+    let signature = String::from_utf8(signature)
+        .map_err(|_| anyhow::anyhow!("更新包签名格式错误"))?;
+
+    verify_update_package(package, &signature, expected_version)
+}
+
+// 使用内置公钥校验更新包的 minisign 签名，签名中的版本必须与预期的目标版本一致
+pub fn verify_update_package(
+    package: &Path,
+    signature_text: &str,
+    expected_version: &str,
+) -> Result<()> {
+    let public_key = UPDATE_PUBLIC_KEY
+        .ok_or_else(|| anyhow::anyhow!("此版本构建时未配置更新签名公钥，无法验证更新包"))?;
+    let version = verify_signature(public_key, File::open(package)?, signature_text)?;
+    if compare_versions(&version, expected_version) != std::cmp::Ordering::Equal {
+        anyhow::bail!(
+            "更新包签名对应版本 {}，与目标版本 {} 不一致",
+            version,
+            expected_version
+        );
+    }
+    Ok(())
+}
+
+// 校验签名并返回可信注释中的版本
+fn verify_signature<R: Read>(
+    public_key: &str,
+    mut data: R,
+    signature_text: &str,
+) -> Result<String> {
+    let public_key = PublicKey::from_base64(public_key.trim())
+        .map_err(|e| anyhow::anyhow!("内置签名公钥无效: {}", e))?;
+    let signature = Signature::decode(signature_text)
+        .map_err(|e| anyhow::anyhow!("更新包签名格式错误: {}", e))?;
+
+    let mut verifier = public_key
+        .verify_stream(&signature)
+        .map_err(|e| anyhow::anyhow!("更新包签名不匹配: {}", e))?;
+
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = data.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        verifier.update(&buffer[..read]);
+    }
+
+    verifier
+        .finalize()
+        .map_err(|e| anyhow::anyhow!("更新包签名验证失败: {}", e))?;
+
+    println!("更新包签名验证通过: {}", signature.trusted_comment());
+    signed_version(signature.trusted_comment())
+        .map(|v| v.to_string())
+        .ok_or_else(|| anyhow::anyhow!("更新包签名未包含版本信息"))
+}
+
+// 可信注释由空白分隔的 key:value 组成，取其中的 version
+fn signed_version(trusted_comment: &str) -> Option<&str> {
+    trusted_comment
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix(VERSION_TAG))
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{signed_version, verify_signature};
+
+    const TEST_PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const TEST_DATA: &[u8] = b"cloud-pe update package";
+    const SIGNED_WITH_VERSION: &str = "untrusted comment: test
+RUQBAgMEBQYHCHhvOGF8BKKTp0VGmy+M0m6IPTQB8QvqY483hplFm9RJWfTUkD1lwBOVAEc3zzxR4Jw/3MEt6crUl9gPHTCNGAk=
+trusted comment: timestamp:0\tfile:Cloud-PE-One_v1.7.0.zip\tversion:1.7.0
+jxPzZuUF3PFA5nV6nZvDkuFw34WJrkm5CrosrX/MfulriHDEeUM6019gtGQsouup0/n8y3rr5n+8CqqPfQ4FAg==
+";
+    const SIGNED_WITHOUT_VERSION: &str = "untrusted comment: test
+RUQBAgMEBQYHCHhvOGF8BKKTp0VGmy+M0m6IPTQB8QvqY483hplFm9RJWfTUkD1lwBOVAEc3zzxR4Jw/3MEt6crUl9gPHTCNGAk=
+trusted comment: timestamp:0\tfile:Cloud-PE-One_v1.7.0.zip
+tSBpEngaCxOsvy/GgsXX/dKzRBD63aq7yo8sLgCbZTwF4qyAibltbjOfZuRy82VozG3ywt/jUPCj1neCU5g8Dw==
+";
+
+    #[test]
+    fn returns_signed_version() {
+        let version = verify_signature(TEST_PUBLIC_KEY, TEST_DATA, SIGNED_WITH_VERSION).unwrap();
+        assert_eq!(version, "1.7.0");
+    }
+
+    #[test]
+    fn rejects_signature_without_version() {
+        assert!(verify_signature(TEST_PUBLIC_KEY, TEST_DATA, SIGNED_WITHOUT_VERSION).is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_data_and_comment() {
+        assert!(verify_signature(TEST_PUBLIC_KEY, &b"tampered"[..], SIGNED_WITH_VERSION).is_err());
+        let replayed = SIGNED_WITH_VERSION.replace("version:1.7.0", "version:1.8.0");
+        assert!(verify_signature(TEST_PUBLIC_KEY, TEST_DATA, &replayed).is_err());
+    }
+
+    #[test]
+    fn parses_version_from_trusted_comment() {
+        assert_eq!(signed_version("timestamp:1\tversion:1.6.1"), Some("1.6.1"));
+        assert_eq!(signed_version("version:v2.0 file:a.zip"), Some("v2.0"));
+        assert_eq!(signed_version("timestamp:1 version:"), None);
+        assert_eq!(signed_version("timestamp:1 file:a.zip"), None);
+    }
+}
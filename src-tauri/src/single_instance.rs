@@ -0,0 +1,94 @@
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// 主程序运行期间一直持有的命名互斥量，进程真正退出后才会释放，
+// 更新助手据此判断旧实例是否已完全退出；修改启动盘的命令行命令执行期间也会持有
+#[cfg(target_os = "windows")]
+const INSTANCE_MUTEX_NAME: &str = "Local\\cn.cloud-pe.one.instance";
+
+// 重复启动时把参数转发给已运行的实例
+pub const FORWARDED_ARGS_EVENT: &str = "single-instance://args";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForwardedArgs {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+// 第二个实例启动时在已运行的实例中调用：激活主窗口并转发参数
+pub fn on_second_instance(app: &AppHandle, argv: Vec<String>, cwd: String) {
+    // 第一个参数是程序路径
+    let args: Vec<String> = argv.into_iter().skip(1).collect();
+    println!("检测到重复启动，转发参数: {:?}", args);
+
+    if let Some(window) = app.get_webview_window("main") {
+        window.unminimize().ok();
+        window.show().ok();
+        window.set_focus().ok();
+    }
+
+    if let Err(e) = app.emit(FORWARDED_ARGS_EVENT, ForwardedArgs { args, cwd }) {
+        println!("转发启动参数失败: {}", e);
+    }
+}
+
+// 创建并持有实例互斥量，句柄保留到进程退出。
+// 互斥量已被主程序或命令行持有时返回 false
+#[cfg(target_os = "windows")]
+pub fn hold_instance_lock() -> bool {
+    use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::synchapi::CreateMutexW;
+
+    let name: Vec<u16> = INSTANCE_MUTEX_NAME.encode_utf16().chain(Some(0)).collect();
+    let handle = unsafe { CreateMutexW(std::ptr::null_mut(), 1, name.as_ptr()) };
+    if handle.is_null() {
+        println!("创建实例互斥量失败");
+        return true;
+    }
+    if std::io::Error::last_os_error().raw_os_error() == Some(ERROR_ALREADY_EXISTS as i32) {
+        unsafe {
+            CloseHandle(handle);
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn hold_instance_lock() -> bool {
+    true
+}
+
+// 等待主程序释放实例互斥量，超时返回 false
+#[cfg(target_os = "windows")]
+pub fn wait_for_instance_release(timeout: Duration) -> bool {
+    use winapi::shared::winerror::WAIT_TIMEOUT;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::synchapi::{OpenMutexW, ReleaseMutex, WaitForSingleObject};
+    use winapi::um::winbase::WAIT_FAILED;
+    use winapi::um::winnt::{MUTEX_MODIFY_STATE, SYNCHRONIZE};
+
+    let name: Vec<u16> = INSTANCE_MUTEX_NAME.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        let handle = OpenMutexW(SYNCHRONIZE | MUTEX_MODIFY_STATE, 0, name.as_ptr());
+        if handle.is_null() {
+            // 互斥量不存在，说明没有实例在运行
+            return true;
+        }
+        let result = WaitForSingleObject(handle, timeout.as_millis() as u32);
+        let released = result != WAIT_TIMEOUT && result != WAIT_FAILED;
+        if released {
+            // 等待成功后由本进程持有，立即释放以免影响新实例启动
+            ReleaseMutex(handle);
+        }
+        CloseHandle(handle);
+        released
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn wait_for_instance_release(_timeout: Duration) -> bool {
+    true
+}
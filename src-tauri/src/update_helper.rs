@@ -0,0 +1,1030 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::update_service::{compare_versions, CURRENT_VERSION};
+
+// 更新助手：由主程序复制到临时目录后以 --update-helper 模式启动，
+// 负责在主程序退出后替换安装目录中的文件
+pub const HELPER_FLAG: &str = "--update-helper";
+pub const POST_UPDATE_FLAG: &str = "--post-update";
+pub const UPDATE_STATUS_FLAG: &str = "--update-status";
+pub const STAGING_DIR_NAME: &str = "tmpFile";
+pub const BACKUP_DIR_NAME: &str = "updateBackup";
+pub const HEALTH_FILE_NAME: &str = "updateHealth.ok";
+pub const PLAN_FILE_NAME: &str = "updatePlan.json";
+pub const RESULT_FILE_NAME: &str = "updateResult.json";
+pub const LOG_FILE_NAME: &str = "update.log";
+pub const INSTALL_MANIFEST_NAME: &str = "installManifest.json";
+const HELPER_FILE_PREFIX: &str = "cloud-pe-update-helper-";
+
+const PARENT_EXIT_TIMEOUT: Duration = Duration::from_secs(60);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+const LOCK_RETRY_COUNT: u32 = 20;
+#[cfg(not(test))]
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(500);
+#[cfg(test)]
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(1);
+
+// 始终保留的用户配置文件，即使新版本也带有同名文件也不会覆盖；
+// 更新包可以在清单中声明更多需要保留的文件
+const DEFAULT_KEEP_LIST: &[&str] = &["ventoy/Ventoy2Disk.ini", "ventoy/ventoy.json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HelperMode {
+    Update,
+    Rollback,
+}
+
+// 助手执行计划，由主程序写入 updatePlan.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdatePlan {
+    pub mode: HelperMode,
+    pub parent_pid: u32,
+    pub app_dir: PathBuf,
+    pub app_exe: String,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+// 更新结果，供新启动的程序读取并提示用户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateResult {
+    pub status: String,
+    pub message: String,
+    pub time: String,
+}
+
+// 安装清单，记录当前版本拥有的文件（相对安装目录，以 / 分隔）。
+// 更新包根目录下也可以带一份同名清单，声明新版本的保留列表和旧版本的文件列表
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstallManifest {
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    // 除默认列表外用户可能修改过的文件，已存在时不覆盖也不删除
+    #[serde(default)]
+    pub keep: Option<Vec<String>>,
+    // 没有留下安装清单的旧版本所拥有的文件，按版本号索引，只在更新包的清单中使用
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub legacy_files: HashMap<String, Vec<String>>,
+}
+
+// 与进程相关的操作，抽象出来以便在任意系统上对目录操作逻辑进行测试
+pub trait HelperHost {
+    fn wait_for_exit(&self, pid: u32, timeout: Duration) -> bool;
+    fn launch(&self, exe: &Path, args: &[&str]) -> io::Result<Child>;
+}
+
+pub struct SystemHost;
+
+impl HelperHost for SystemHost {
+    #[cfg(target_os = "windows")]
+    fn wait_for_exit(&self, pid: u32, timeout: Duration) -> bool {
+        use winapi::shared::winerror::WAIT_TIMEOUT;
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::processthreadsapi::OpenProcess;
+        use winapi::um::synchapi::WaitForSingleObject;
+        use winapi::um::winnt::SYNCHRONIZE;
+
+        use crate::single_instance::wait_for_instance_release;
+
+        let start = Instant::now();
+        let exited = unsafe {
+            let handle = OpenProcess(SYNCHRONIZE, 0, pid);
+            if handle.is_null() {
+                // 进程已不存在
+                true
+            } else {
+                let result = WaitForSingleObject(handle, timeout.as_millis() as u32);
+                CloseHandle(handle);
+                result != WAIT_TIMEOUT
+            }
+        };
+
+        // 进程句柄结束后再确认实例互斥量已释放，避免 PID 被复用或进程仍在收尾
+        exited && wait_for_instance_release(timeout.saturating_sub(start.elapsed()))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn wait_for_exit(&self, pid: u32, timeout: Duration) -> bool {
+        let start = Instant::now();
+        let proc_path = PathBuf::from(format!("/proc/{}", pid));
+        while proc_path.exists() {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        true
+    }
+
+    fn launch(&self, exe: &Path, args: &[&str]) -> io::Result<Child> {
+        let mut command = Command::new(exe);
+        command.args(args);
+        if let Some(dir) = exe.parent() {
+            command.current_dir(dir);
+        }
+        command.spawn()
+    }
+}
+
+// 逐步写入日志文件，同时输出到控制台
+pub struct UpdateLog {
+    file: Option<File>,
+}
+
+impl UpdateLog {
+    pub fn open(path: &Path) -> Self {
+        let file = OpenOptions::new().create(true).append(true).open(path).ok();
+        Self { file }
+    }
+
+    pub fn step(&mut self, message: &str) {
+        let line = format!(
+            "[{}] {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            message
+        );
+        println!("{}", line);
+        if let Some(file) = &mut self.file {
+            let _ = writeln!(file, "{}", line);
+            let _ = file.flush();
+        }
+    }
+}
+
+pub struct UpdateHelper<'a, H: HelperHost> {
+    host: &'a H,
+    plan: UpdatePlan,
+    log: UpdateLog,
+    keep: Vec<String>,
+}
+
+impl<'a, H: HelperHost> UpdateHelper<'a, H> {
+    pub fn new(host: &'a H, plan: UpdatePlan) -> Self {
+        let log = UpdateLog::open(&plan.app_dir.join(LOG_FILE_NAME));
+        // 回滚时沿用当前安装清单中的保留列表
+        let keep = keep_list(read_install_manifest(&plan.app_dir).and_then(|m| m.keep));
+        Self {
+            host,
+            plan,
+            log,
+            keep,
+        }
+    }
+
+    fn staging_dir(&self) -> PathBuf {
+        self.plan.app_dir.join(STAGING_DIR_NAME)
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        self.plan.app_dir.join(BACKUP_DIR_NAME)
+    }
+
+    fn app_exe_path(&self) -> PathBuf {
+        self.plan.app_dir.join(&self.plan.app_exe)
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        self.log
+            .step(&format!("更新助手启动，模式: {:?}", self.plan.mode));
+        self.log
+            .step(&format!("等待主程序退出 (PID {})...", self.plan.parent_pid));
+        if !self
+            .host
+            .wait_for_exit(self.plan.parent_pid, PARENT_EXIT_TIMEOUT)
+        {
+            let message = "等待主程序退出超时，已取消操作";
+            self.log.step(message);
+            self.write_result("failed", message);
+            anyhow::bail!(message);
+        }
+        self.log.step("主程序已退出");
+
+        match self.plan.mode {
+            HelperMode::Update => self.run_update(),
+            HelperMode::Rollback => self.run_rollback(),
+        }
+    }
+
+    fn run_update(&mut self) -> Result<()> {
+        let staging = self.staging_dir();
+        if !staging.is_dir() {
+            let message = format!("未找到新版本文件: {}", staging.display());
+            self.log.step(&message);
+            self.write_result("failed", &message);
+            self.relaunch(&[]);
+            anyhow::bail!(message);
+        }
+
+        let staged = match list_files(&staging) {
+            Ok(files) => files,
+            Err(e) => {
+                let message = format!("读取新版本文件失败: {}", e);
+                self.log.step(&message);
+                self.write_result("failed", &message);
+                self.relaunch(&[]);
+                anyhow::bail!(message);
+            }
+        };
+        self.log.step(&format!(
+            "新版本共 {} 个文件，其余文件保持不变",
+            staged.len()
+        ));
+
+        let package = read_install_manifest(&staging);
+        self.keep = keep_list(package.as_ref().and_then(|m| m.keep.clone()));
+
+        self.log.step("备份当前版本...");
+        if let Err(e) = self.backup_current(&staged, package.as_ref()) {
+            self.log.step(&format!("备份失败: {}", e));
+            self.restore_backup(false);
+            self.write_result("failed", &format!("备份当前版本失败: {}", e));
+            self.relaunch(&[]);
+            return Err(e);
+        }
+
+        self.log.step("安装新版本文件...");
+        if let Err(e) = self.install_staged(&staged) {
+            self.log.step(&format!("安装失败: {}", e));
+            self.restore_backup(true);
+            self.write_result(
+                "rolled-back",
+                &format!("安装新版本失败，已恢复旧版本: {}", e),
+            );
+            self.relaunch(&["rolled-back"]);
+            return Err(e);
+        }
+
+        self.log.step("启动新版本并等待健康检查...");
+        match self.launch_and_check() {
+            Ok(true) => {
+                self.log.step("新版本运行正常");
+                remove_path(&staging).ok();
+                self.write_result("success", "更新完成");
+                self.log.step("更新完成，旧版本保留在备份目录中");
+                Ok(())
+            }
+            Ok(false) | Err(_) => {
+                self.log.step("新版本未能正常启动，开始回滚");
+                self.restore_backup(true);
+                remove_path(&staging).ok();
+                self.write_result("rolled-back", "新版本未能正常启动，已恢复旧版本");
+                self.relaunch(&["rolled-back"]);
+                anyhow::bail!("新版本健康检查失败")
+            }
+        }
+    }
+
+    fn run_rollback(&mut self) -> Result<()> {
+        if !self.backup_dir().is_dir() {
+            let message = "没有可回滚的旧版本";
+            self.log.step(message);
+            self.write_result("failed", message);
+            self.relaunch(&[]);
+            anyhow::bail!(message);
+        }
+
+        self.log.step("恢复旧版本...");
+        self.restore_backup(true);
+        self.write_result("rolled-back", "已回滚到上一版本");
+        self.relaunch(&["rolled-back"]);
+        Ok(())
+    }
+
+    // 本次更新需要替换的旧文件：旧版本清单登记的文件，加上新版本将要覆盖的文件；
+    // 用户在保留列表中的文件不会被移动
+    fn replaced_files(&self, staged: &[String], package: Option<&InstallManifest>) -> Vec<String> {
+        let mut files: Vec<String> = match read_install_manifest(&self.plan.app_dir) {
+            Some(manifest) => manifest.files,
+            // 首次通过更新助手升级时旧版本没有安装清单，使用更新包提供的旧版本文件列表
+            None => legacy_files(package, CURRENT_VERSION),
+        };
+        for rel in staged {
+            if self.plan.app_dir.join(rel).is_file() && !files.iter().any(|f| same_path(f, rel)) {
+                files.push(rel.clone());
+            }
+        }
+        files.retain(|rel| !is_kept_user_file(&self.keep, rel));
+        files
+    }
+
+    fn backup_current(
+        &mut self,
+        staged: &[String],
+        package: Option<&InstallManifest>,
+    ) -> Result<()> {
+        let backup = self.backup_dir();
+        if backup.exists() {
+            self.log.step("删除上一次的备份");
+            retry_locked(|| remove_path(&backup))?;
+        }
+        fs::create_dir_all(&backup)?;
+
+        // 先确定要替换的文件，再把旧版本的清单随备份一起保存，回滚时原样恢复
+        let replaced = self.replaced_files(staged, package);
+        let manifest = self.plan.app_dir.join(INSTALL_MANIFEST_NAME);
+        if manifest.is_file() {
+            retry_locked(|| move_path(&manifest, &backup.join(INSTALL_MANIFEST_NAME)))?;
+        }
+
+        for rel in replaced {
+            let path = self.plan.app_dir.join(&rel);
+            if !path.is_file() {
+                continue;
+            }
+            self.log.step(&format!("  备份: {}", rel));
+            retry_locked(|| move_path(&path, &backup.join(&rel)))?;
+            prune_empty_dirs(&self.plan.app_dir, &path);
+        }
+        Ok(())
+    }
+
+    fn install_staged(&mut self, staged: &[String]) -> Result<()> {
+        let staging = self.staging_dir();
+
+        // 先写入新版本清单，即使安装中途失败也能据此清理
+        write_install_manifest(
+            &self.plan.app_dir,
+            &InstallManifest {
+                version: self.plan.version.clone(),
+                files: staged.to_vec(),
+                keep: Some(self.keep.clone()),
+                legacy_files: HashMap::new(),
+            },
+        )?;
+
+        for rel in staged {
+            let target = self.plan.app_dir.join(rel);
+            if is_kept_user_file(&self.keep, rel) && target.exists() {
+                self.log.step(&format!("  保留用户文件: {}", rel));
+                continue;
+            }
+            self.log.step(&format!("  安装: {}", rel));
+            retry_locked(|| move_path(&staging.join(rel), &target))?;
+        }
+        Ok(())
+    }
+
+    // 删除新版本清单中的文件（备份阶段失败时还没有新文件，不删除）并把备份移回；
+    // 尽力而为，单个文件失败不会中断
+    fn restore_backup(&mut self, remove_installed: bool) {
+        let backup = self.backup_dir();
+        if !backup.is_dir() {
+            self.log.step("没有备份可以恢复");
+            return;
+        }
+
+        let installed = if remove_installed {
+            read_install_manifest(&self.plan.app_dir)
+        } else {
+            None
+        };
+        if let Some(manifest) = installed {
+            let keep = self.keep.clone();
+            for rel in manifest
+                .files
+                .iter()
+                .filter(|rel| !is_kept_user_file(&keep, rel))
+            {
+                let path = self.plan.app_dir.join(rel);
+                if let Err(e) = retry_locked(|| remove_path(&path)) {
+                    self.log.step(&format!("  删除失败: {} ({})", rel, e));
+                }
+                prune_empty_dirs(&self.plan.app_dir, &path);
+            }
+        }
+        if remove_installed {
+            remove_path(&self.plan.app_dir.join(INSTALL_MANIFEST_NAME)).ok();
+        }
+
+        match list_files(&backup) {
+            Ok(files) => {
+                for rel in files {
+                    let target = self.plan.app_dir.join(&rel);
+                    self.log.step(&format!("  恢复: {}", rel));
+                    if let Err(e) = retry_locked(|| move_path(&backup.join(&rel), &target)) {
+                        self.log.step(&format!("  恢复失败: {} ({})", rel, e));
+                    }
+                }
+            }
+            Err(e) => self.log.step(&format!("读取备份目录失败: {}", e)),
+        }
+        // 备份中的旧清单位于顶层，list_files 会跳过，单独移回
+        let old_manifest = backup.join(INSTALL_MANIFEST_NAME);
+        if old_manifest.is_file() {
+            move_path(
+                &old_manifest,
+                &self.plan.app_dir.join(INSTALL_MANIFEST_NAME),
+            )
+            .ok();
+        }
+
+        if let Err(e) = remove_path(&backup) {
+            self.log.step(&format!("删除备份目录失败: {}", e));
+        }
+        self.log.step("旧版本已恢复");
+    }
+
+    fn launch_and_check(&mut self) -> Result<bool> {
+        let health_file = self.plan.app_dir.join(HEALTH_FILE_NAME);
+        remove_path(&health_file).ok();
+
+        let mut child = self
+            .host
+            .launch(&self.app_exe_path(), &[POST_UPDATE_FLAG])?;
+        let start = Instant::now();
+
+        loop {
+            if health_file.exists() {
+                remove_path(&health_file).ok();
+                return Ok(true);
+            }
+
+            // 新版本在报告健康前退出，视为启动失败
+            if let Some(status) = child.try_wait()? {
+                self.log.step(&format!("新版本进程已退出: {}", status));
+                return Ok(false);
+            }
+
+            if start.elapsed() >= HEALTH_CHECK_TIMEOUT {
+                self.log.step("健康检查超时，结束新版本进程");
+                child.kill().ok();
+                child.wait().ok();
+                return Ok(false);
+            }
+
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    fn relaunch(&mut self, status: &[&str]) {
+        let exe = self.app_exe_path();
+        let mut args = Vec::new();
+        let flag;
+        if let Some(status) = status.first() {
+            flag = format!("{}={}", UPDATE_STATUS_FLAG, status);
+            args.push(flag.as_str());
+        }
+
+        match self.host.launch(&exe, &args) {
+            Ok(_) => self.log.step(&format!("已启动: {}", exe.display())),
+            Err(e) => self
+                .log
+                .step(&format!("启动失败: {} ({})", exe.display(), e)),
+        }
+    }
+
+    fn write_result(&mut self, status: &str, message: &str) {
+        let result = UpdateResult {
+            status: status.to_string(),
+            message: message.to_string(),
+            time: chrono::Local::now().to_rfc3339(),
+        };
+        let path = self.plan.app_dir.join(RESULT_FILE_NAME);
+        match serde_json::to_string_pretty(&result) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&path, content) {
+                    self.log.step(&format!("写入更新结果失败: {}", e));
+                }
+            }
+            Err(e) => self.log.step(&format!("序列化更新结果失败: {}", e)),
+        }
+    }
+}
+
+// 助手运行期间使用的文件，不属于任何版本
+fn is_helper_entry(name: &str) -> bool {
+    [
+        STAGING_DIR_NAME,
+        BACKUP_DIR_NAME,
+        PLAN_FILE_NAME,
+        RESULT_FILE_NAME,
+        LOG_FILE_NAME,
+        HEALTH_FILE_NAME,
+        INSTALL_MANIFEST_NAME,
+    ]
+    .iter()
+    .any(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn same_path(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+fn keep_list(declared: Option<Vec<String>>) -> Vec<String> {
+    let mut keep: Vec<String> = DEFAULT_KEEP_LIST.iter().map(|f| f.to_string()).collect();
+    for rel in declared.into_iter().flatten() {
+        if !keep.iter().any(|k| same_path(k, &rel)) {
+            keep.push(rel);
+        }
+    }
+    keep
+}
+
+fn is_kept_user_file(keep: &[String], rel: &str) -> bool {
+    keep.iter().any(|k| same_path(k, rel))
+}
+
+fn legacy_files(package: Option<&InstallManifest>, version: &str) -> Vec<String> {
+    package
+        .and_then(|m| {
+            m.legacy_files
+                .iter()
+                .find(|(v, _)| compare_versions(v, version) == Ordering::Equal)
+        })
+        .map(|(_, files)| files.clone())
+        .unwrap_or_default()
+}
+
+pub fn read_install_manifest(app_dir: &Path) -> Option<InstallManifest> {
+    let content = fs::read_to_string(app_dir.join(INSTALL_MANIFEST_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_install_manifest(app_dir: &Path, manifest: &InstallManifest) -> Result<()> {
+    let content = serde_json::to_string_pretty(manifest)?;
+    fs::write(app_dir.join(INSTALL_MANIFEST_NAME), content)?;
+    Ok(())
+}
+
+// 递归列出目录下的所有文件，返回以 / 分隔的相对路径；顶层的助手文件不计入
+pub fn list_files(root: &Path) -> io::Result<Vec<String>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else if let Ok(rel) = path.strip_prefix(root) {
+                let rel = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push(rel);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(root, root, &mut files)?;
+    files.retain(|rel| rel.contains('/') || !is_helper_entry(rel));
+    files.sort();
+    Ok(files)
+}
+
+// 删除文件后向上清理变空的目录，直到安装目录为止
+fn prune_empty_dirs(root: &Path, removed: &Path) {
+    let mut dir = removed.parent();
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) {
+            break;
+        }
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+// 文件被占用（如杀毒软件扫描）时重试
+fn retry_locked<F: FnMut() -> io::Result<()>>(mut op: F) -> io::Result<()> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                attempt += 1;
+                if attempt >= LOCK_RETRY_COUNT {
+                    return Err(e);
+                }
+                thread::sleep(LOCK_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+// 同一卷上直接重命名（原子操作），跨卷时退化为复制后删除
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if to.exists() {
+        remove_path(to)?;
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(_) => {
+            copy_recursive(from, to)?;
+            remove_path(from)
+        }
+    }
+}
+
+pub fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+// 主程序入口调用：带 --update-helper 参数时执行更新助手并返回退出码
+pub fn run_from_args(args: &[String]) -> Option<i32> {
+    if args.first().map(|a| a.as_str()) != Some(HELPER_FLAG) {
+        return None;
+    }
+
+    let plan_path = match args.get(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("缺少更新计划文件参数");
+            return Some(2);
+        }
+    };
+
+    let plan: UpdatePlan = match fs::read_to_string(&plan_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| serde_json::from_str(&content).map_err(anyhow::Error::from))
+    {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("读取更新计划失败: {}", e);
+            return Some(2);
+        }
+    };
+    fs::remove_file(&plan_path).ok();
+
+    let host = SystemHost;
+    let result = UpdateHelper::new(&host, plan).run();
+    Some(if result.is_ok() { 0 } else { 1 })
+}
+
+// 把当前程序复制到临时目录作为更新助手启动，避免替换文件时占用自身
+pub fn spawn_helper(plan: &UpdatePlan) -> Result<()> {
+    let exe_path = std::env::current_exe()?;
+    let helper_path =
+        std::env::temp_dir().join(format!("{}{}.exe", HELPER_FILE_PREFIX, std::process::id()));
+    fs::copy(&exe_path, &helper_path)?;
+
+    let plan_path = plan.app_dir.join(PLAN_FILE_NAME);
+    fs::write(&plan_path, serde_json::to_string_pretty(plan)?)?;
+
+    let mut command = Command::new(&helper_path);
+    command
+        .arg(HELPER_FLAG)
+        .arg(&plan_path)
+        .current_dir(std::env::temp_dir());
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    command.spawn()?;
+    Ok(())
+}
+
+// 程序启动时清理以前留下的助手副本（运行中的文件删除会失败，直接忽略）
+pub fn cleanup_stale_helpers() {
+    let current = std::env::current_exe().ok();
+    if let Ok(entries) = fs::read_dir(std::env::temp_dir()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(HELPER_FILE_PREFIX) && Some(entry.path()) != current {
+                fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+}
+
+// 读取并清除上一次更新的结果
+pub fn take_update_result(app_dir: &Path) -> Option<UpdateResult> {
+    let path = app_dir.join(RESULT_FILE_NAME);
+    let content = fs::read_to_string(&path).ok()?;
+    fs::remove_file(&path).ok();
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::process::Stdio;
+
+    // 在临时目录中模拟主程序：不等待进程，启动时按 healthy 决定是否写入健康标记
+    struct TestHost {
+        healthy: bool,
+        launches: RefCell<Vec<Vec<String>>>,
+    }
+
+    impl TestHost {
+        fn new(healthy: bool) -> Self {
+            Self {
+                healthy,
+                launches: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl HelperHost for TestHost {
+        fn wait_for_exit(&self, _pid: u32, _timeout: Duration) -> bool {
+            true
+        }
+
+        fn launch(&self, exe: &Path, args: &[&str]) -> io::Result<Child> {
+            self.launches
+                .borrow_mut()
+                .push(args.iter().map(|a| a.to_string()).collect());
+            if self.healthy && args.first() == Some(&POST_UPDATE_FLAG) {
+                fs::write(exe.parent().unwrap().join(HEALTH_FILE_NAME), "ok")?;
+            }
+            // 启动一个立即退出的进程代替主程序
+            Command::new(std::env::current_exe()?)
+                .arg("--list")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cloud-pe-helper-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        remove_path(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(root: &Path, rel: &str, content: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn read(root: &Path, rel: &str) -> Option<String> {
+        fs::read_to_string(root.join(rel)).ok()
+    }
+
+    fn plan(app_dir: &Path, mode: HelperMode) -> UpdatePlan {
+        UpdatePlan {
+            mode,
+            parent_pid: 0,
+            app_dir: app_dir.to_path_buf(),
+            app_exe: "app.exe".to_string(),
+            version: Some("2.0.0".to_string()),
+        }
+    }
+
+    fn result_status(app_dir: &Path) -> String {
+        take_update_result(app_dir).unwrap().status
+    }
+
+    // 旧版本 1.0.0 与暂存的新版本
+    fn prepare(app_dir: &Path) {
+        write(app_dir, "app.exe", "old exe");
+        write(app_dir, "a.txt", "old a");
+        write(app_dir, "old.txt", "old only");
+        write(app_dir, "ventoy/ventoy.json", "user config");
+        write_install_manifest(
+            app_dir,
+            &InstallManifest {
+                version: Some("1.0.0".to_string()),
+                files: vec![
+                    "app.exe".to_string(),
+                    "a.txt".to_string(),
+                    "old.txt".to_string(),
+                    "ventoy/ventoy.json".to_string(),
+                ],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let staging = app_dir.join(STAGING_DIR_NAME);
+        write(&staging, "app.exe", "new exe");
+        write(&staging, "a.txt", "new a");
+        write(&staging, "new/c.txt", "new c");
+        write(&staging, "ventoy/ventoy.json", "default config");
+    }
+
+    #[test]
+    fn swaps_in_new_version() {
+        let app_dir = test_dir("swap");
+        prepare(&app_dir);
+
+        let host = TestHost::new(true);
+        UpdateHelper::new(&host, plan(&app_dir, HelperMode::Update))
+            .run()
+            .unwrap();
+
+        assert_eq!(read(&app_dir, "app.exe").as_deref(), Some("new exe"));
+        assert_eq!(read(&app_dir, "a.txt").as_deref(), Some("new a"));
+        assert_eq!(read(&app_dir, "new/c.txt").as_deref(), Some("new c"));
+        assert_eq!(read(&app_dir, "old.txt"), None);
+        assert_eq!(
+            read(&app_dir, "ventoy/ventoy.json").as_deref(),
+            Some("user config")
+        );
+        assert!(!app_dir.join(STAGING_DIR_NAME).exists());
+
+        let backup = app_dir.join(BACKUP_DIR_NAME);
+        assert_eq!(read(&backup, "a.txt").as_deref(), Some("old a"));
+        assert_eq!(read(&backup, "old.txt").as_deref(), Some("old only"));
+
+        let manifest = read_install_manifest(&app_dir).unwrap();
+        assert_eq!(manifest.version.as_deref(), Some("2.0.0"));
+        assert!(manifest.files.contains(&"new/c.txt".to_string()));
+        assert_eq!(result_status(&app_dir), "success");
+        assert_eq!(
+            host.launches.borrow().as_slice(),
+            &[vec![POST_UPDATE_FLAG.to_string()]]
+        );
+
+        remove_path(&app_dir).ok();
+    }
+
+    #[test]
+    fn rolls_back_when_install_fails_midway() {
+        let app_dir = test_dir("install-failure");
+        prepare(&app_dir);
+        // 新版本需要 sub 目录，但安装目录中已有同名文件，安装到一半时失败
+        write(&app_dir, "sub", "blocking file");
+        write(&app_dir.join(STAGING_DIR_NAME), "sub/b.txt", "new b");
+
+        let host = TestHost::new(true);
+        assert!(UpdateHelper::new(&host, plan(&app_dir, HelperMode::Update))
+            .run()
+            .is_err());
+
+        assert_eq!(read(&app_dir, "app.exe").as_deref(), Some("old exe"));
+        assert_eq!(read(&app_dir, "a.txt").as_deref(), Some("old a"));
+        assert_eq!(read(&app_dir, "old.txt").as_deref(), Some("old only"));
+        assert_eq!(read(&app_dir, "new/c.txt"), None);
+        assert_eq!(read(&app_dir, "sub").as_deref(), Some("blocking file"));
+        assert_eq!(
+            read(&app_dir, "ventoy/ventoy.json").as_deref(),
+            Some("user config")
+        );
+        assert_eq!(
+            read_install_manifest(&app_dir).unwrap().version.as_deref(),
+            Some("1.0.0")
+        );
+        assert!(!app_dir.join(BACKUP_DIR_NAME).exists());
+        assert_eq!(result_status(&app_dir), "rolled-back");
+        assert_eq!(
+            host.launches.borrow().as_slice(),
+            &[vec![format!("{}=rolled-back", UPDATE_STATUS_FLAG)]]
+        );
+
+        remove_path(&app_dir).ok();
+    }
+
+    #[test]
+    fn rolls_back_when_new_version_is_unhealthy() {
+        let app_dir = test_dir("unhealthy");
+        prepare(&app_dir);
+
+        let host = TestHost::new(false);
+        assert!(UpdateHelper::new(&host, plan(&app_dir, HelperMode::Update))
+            .run()
+            .is_err());
+
+        assert_eq!(read(&app_dir, "app.exe").as_deref(), Some("old exe"));
+        assert_eq!(read(&app_dir, "old.txt").as_deref(), Some("old only"));
+        assert_eq!(read(&app_dir, "new/c.txt"), None);
+        assert!(!app_dir.join(STAGING_DIR_NAME).exists());
+        assert_eq!(result_status(&app_dir), "rolled-back");
+
+        remove_path(&app_dir).ok();
+    }
+
+    #[test]
+    fn follows_package_keep_list_and_legacy_files() {
+        let app_dir = test_dir("package-manifest");
+        // 旧版本由安装程序安装，没有安装清单
+        write(&app_dir, "app.exe", "old exe");
+        write(&app_dir, "old.txt", "old only");
+        write(&app_dir, "settings.ini", "user settings");
+        write(&app_dir, "ventoy/ventoy.json", "user config");
+        write(&app_dir, "isos/cached.iso", "user data");
+
+        let staging = app_dir.join(STAGING_DIR_NAME);
+        write(&staging, "app.exe", "new exe");
+        write(&staging, "settings.ini", "default settings");
+        write(&staging, "ventoy/ventoy.json", "default config");
+        write_install_manifest(
+            &staging,
+            &InstallManifest {
+                version: Some("2.0.0".to_string()),
+                keep: Some(vec!["settings.ini".to_string()]),
+                legacy_files: HashMap::from([(
+                    CURRENT_VERSION.to_string(),
+                    vec!["app.exe".to_string(), "old.txt".to_string()],
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let host = TestHost::new(true);
+        UpdateHelper::new(&host, plan(&app_dir, HelperMode::Update))
+            .run()
+            .unwrap();
+
+        assert_eq!(read(&app_dir, "app.exe").as_deref(), Some("new exe"));
+        assert_eq!(read(&app_dir, "old.txt"), None);
+        assert_eq!(
+            read(&app_dir, "settings.ini").as_deref(),
+            Some("user settings")
+        );
+        assert_eq!(
+            read(&app_dir, "ventoy/ventoy.json").as_deref(),
+            Some("user config")
+        );
+        assert_eq!(
+            read(&app_dir, "isos/cached.iso").as_deref(),
+            Some("user data")
+        );
+
+        let manifest = read_install_manifest(&app_dir).unwrap();
+        assert!(manifest.keep.unwrap().contains(&"settings.ini".to_string()));
+        assert!(manifest.legacy_files.is_empty());
+
+        remove_path(&app_dir).ok();
+    }
+
+    #[test]
+    fn restores_backup_on_rollback() {
+        let app_dir = test_dir("rollback");
+        prepare(&app_dir);
+
+        let host = TestHost::new(true);
+        UpdateHelper::new(&host, plan(&app_dir, HelperMode::Update))
+            .run()
+            .unwrap();
+        take_update_result(&app_dir);
+
+        UpdateHelper::new(&host, plan(&app_dir, HelperMode::Rollback))
+            .run()
+            .unwrap();
+
+        assert_eq!(read(&app_dir, "app.exe").as_deref(), Some("old exe"));
+        assert_eq!(read(&app_dir, "a.txt").as_deref(), Some("old a"));
+        assert_eq!(read(&app_dir, "old.txt").as_deref(), Some("old only"));
+        assert_eq!(read(&app_dir, "new/c.txt"), None);
+        assert!(!app_dir.join("new").exists());
+        assert_eq!(
+            read(&app_dir, "ventoy/ventoy.json").as_deref(),
+            Some("user config")
+        );
+        assert_eq!(
+            read_install_manifest(&app_dir).unwrap().version.as_deref(),
+            Some("1.0.0")
+        );
+        assert!(!app_dir.join(BACKUP_DIR_NAME).exists());
+        assert_eq!(result_status(&app_dir), "rolled-back");
+
+        // 没有备份时拒绝回滚
+        assert!(
+            UpdateHelper::new(&host, plan(&app_dir, HelperMode::Rollback))
+                .run()
+                .is_err()
+        );
+
+        remove_path(&app_dir).ok();
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::delta_update::prepare_delta_update;
+use crate::download::{
+    download_update_package, get_prefetch_download_status, mark_update_download_complete,
+    DownloadEventType, UPDATE_DOWNLOAD_STATUS,
+};
+#[cfg(not(test))]
+use crate::paths::app_data_dir;
+use crate::update_service::{compare_versions, evaluate_update, CURRENT_VERSION};
+
+// 更新包暂存区：%APPDATA%\Cloud-PE One\update_cache\<地址哈希>\
+const CACHE_DIR_NAME: &str = "update_cache";
+const META_FILE_NAME: &str = "prefetch.json";
+const DELTA_STAGING_NAME: &str = "staging";
+const PREFETCH_THREADS: u16 = 8;
+
+// 暂存的更新包信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PrefetchMeta {
+    url: String,
+    #[serde(default)]
+    version: Option<String>,
+    // 下载完成后才会记录，签名在安装前校验
+    #[serde(default)]
+    package: Option<PathBuf>,
+    // 通过增量包预先准备好的新版本文件目录
+    #[serde(default)]
+    staged: Option<PathBuf>,
+}
+
+lazy_static::lazy_static! {
+    // 后台预下载与用户点击更新共用，保证同一时间只有一个下载写入暂存区
+    static ref FETCH_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[cfg(not(test))]
+fn cache_root() -> Result<PathBuf> {
+    Ok(app_data_dir()?.join(CACHE_DIR_NAME))
+}
+
+#[cfg(test)]
+fn cache_root() -> Result<PathBuf> {
+    Ok(std::env::temp_dir().join(format!(
+        "cloud-pe-{}-{}",
+        CACHE_DIR_NAME,
+        std::process::id()
+    )))
+}
+
+// 同一地址始终使用同一目录，重启后可以从状态文件续传
+fn entry_dir(url: &str) -> Result<PathBuf> {
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    Ok(cache_root()?.join(&hash[..16]))
+}
+
+fn read_meta(dir: &Path) -> Option<PrefetchMeta> {
+    let content = fs::read_to_string(dir.join(META_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn write_meta(dir: &Path, meta: &PrefetchMeta) -> Result<()> {
+    fs::write(
+        dir.join(META_FILE_NAME),
+        serde_json::to_string_pretty(meta)?,
+    )?;
+    Ok(())
+}
+
+fn open_entry(url: &str, version: Option<&str>) -> Result<(PathBuf, PrefetchMeta)> {
+    let dir = entry_dir(url)?;
+    fs::create_dir_all(&dir)?;
+
+    let mut meta = read_meta(&dir).unwrap_or(PrefetchMeta {
+        url: url.to_string(),
+        version: None,
+        package: None,
+        staged: None,
+    });
+    if let Some(version) = version {
+        meta.version = Some(version.to_string());
+    }
+    Ok((dir, meta))
+}
+
+fn cached_meta(url: &str) -> Option<PrefetchMeta> {
+    let meta = read_meta(&entry_dir(url).ok()?)?;
+    (meta.url == url).then_some(meta)
+}
+
+// 已下载完成的更新包
+pub fn cached_package(url: &str) -> Option<PathBuf> {
+    cached_meta(url)?.package.filter(|p| p.is_file())
+}
+
+// 已通过增量包准备好的新版本文件
+fn cached_staging(url: &str) -> Option<PathBuf> {
+    cached_meta(url)?.staged.filter(|p| p.is_dir())
+}
+
+// 等待后台预下载结束，期间把预下载进度同步到界面显示的更新进度
+pub async fn lock_fetch() -> MutexGuard<'static, ()> {
+    let lock = FETCH_LOCK.lock();
+    tokio::pin!(lock);
+    let mut ticker = tokio::time::interval(Duration::from_millis(500));
+    loop {
+        tokio::select! {
+            // 先检查锁，锁已空闲时不再用预下载的进度覆盖界面进度
+            biased;
+            guard = &mut lock => return guard,
+            _ = ticker.tick() => {
+                if let Some(status) = get_prefetch_download_status() {
+                    *UPDATE_DOWNLOAD_STATUS.lock().unwrap() = Some(status);
+                }
+            }
+        }
+    }
+}
+
+// 把后台准备好的增量更新文件移动到 target，没有时返回 false
+pub fn take_staged_delta(url: &str, target: &Path) -> Result<bool> {
+    let staged = match cached_staging(url) {
+        Some(staged) => staged,
+        None => return Ok(false),
+    };
+
+    if target.exists() {
+        fs::remove_dir_all(target)?;
+    }
+    // 暂存区与安装目录可能不在同一个分区，无法重命名时复制
+    if fs::rename(&staged, target).is_err() {
+        copy_dir(&staged, target)?;
+    }
+    discard(url);
+    Ok(true)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+// 下载（或续传）更新包到暂存区，返回更新包路径。调用方需持有 lock_fetch 返回的锁
+pub async fn fetch_package(
+    url: &str,
+    version: Option<&str>,
+    event_type: DownloadEventType,
+) -> Result<PathBuf> {
+    if let Some(package) = cached_package(url) {
+        println!("使用已预下载的更新包: {}", package.display());
+        mark_update_download_complete(&event_type);
+        return Ok(package);
+    }
+
+    let (dir, mut meta) = open_entry(url, version)?;
+    meta.package = None;
+    write_meta(&dir, &meta)?;
+
+    let package = PathBuf::from(
+        download_update_package(url.to_string(), dir.clone(), PREFETCH_THREADS, event_type).await?,
+    );
+
+    meta.package = Some(package.clone());
+    write_meta(&dir, &meta)?;
+    Ok(package)
+}
+
+// 安装完成后删除暂存的更新包
+pub fn discard(url: &str) {
+    if let Ok(dir) = entry_dir(url) {
+        fs::remove_dir_all(dir).ok();
+    }
+}
+
+// 删除已安装版本或已被新版本取代的暂存包
+fn cleanup(keep_url: Option<&str>) {
+    let root = match cache_root() {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+    let entries = match fs::read_dir(&root) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let keep = match read_meta(&dir) {
+            Some(meta) => {
+                let outdated = meta
+                    .version
+                    .as_deref()
+                    .map(|v| compare_versions(v, CURRENT_VERSION) != Ordering::Greater)
+                    .unwrap_or(false);
+                !outdated && keep_url.map(|u| u == meta.url).unwrap_or(true)
+            }
+            None => false,
+        };
+        if !keep {
+            println!("清理暂存的更新包: {}", dir.display());
+            fs::remove_dir_all(&dir).ok();
+        }
+    }
+}
+
+async fn prefetch_latest() -> Result<()> {
+    let decision = evaluate_update().await?;
+    if !decision.available || decision.download_link.is_empty() {
+        cleanup(None);
+        return Ok(());
+    }
+
+    let url = decision.download_link.as_str();
+    cleanup(Some(url));
+    let _guard = FETCH_LOCK.lock().await;
+    if cached_staging(url).is_some() || cached_package(url).is_some() {
+        return Ok(());
+    }
+    println!("发现新版本 {}，开始后台预下载", decision.version);
+
+    // 优先准备增量更新，没有适用的增量包时才下载完整更新包
+    let app_dir = std::env::current_exe()?
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("无法获取exe父目录"))?
+        .to_path_buf();
+    let (dir, mut meta) = open_entry(url, Some(&decision.version))?;
+    let staging = dir.join(DELTA_STAGING_NAME);
+    let prepared = prepare_delta_update(
+        url,
+        &decision.version,
+        &app_dir,
+        &staging,
+        DownloadEventType::UpdatePrefetch,
+    )
+    .await;
+    match prepared {
+        Ok(true) => {
+            meta.staged = Some(staging);
+            write_meta(&dir, &meta)?;
+            println!("新版本的增量更新已预先准备完成");
+            return Ok(());
+        }
+        Ok(false) => {}
+        Err(e) => println!("预先准备增量更新失败，改为预下载完整更新包: {}", e),
+    }
+
+    let package = fetch_package(
+        url,
+        Some(&decision.version),
+        DownloadEventType::UpdatePrefetch,
+    )
+    .await?;
+    println!("新版本已预下载完成: {}", package.display());
+    Ok(())
+}
+
+// 启动时在后台检查并预下载更新包
+pub fn start_prefetch() {
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = prefetch_latest().await {
+            println!("后台预下载更新失败: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::get_update_download_status;
+
+    #[test]
+    fn cached_package_completes_the_visible_progress() {
+        let url = "https://example.com/Cloud-PE-One_v9.9.9.zip";
+        let (dir, mut meta) = open_entry(url, Some("9.9.9")).unwrap();
+        let package = dir.join("Cloud-PE-One_v9.9.9.zip");
+        fs::write(&package, b"package").unwrap();
+        meta.package = Some(package.clone());
+        write_meta(&dir, &meta).unwrap();
+        *UPDATE_DOWNLOAD_STATUS.lock().unwrap() = None;
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let fetched = runtime
+            .block_on(async {
+                let _guard = lock_fetch().await;
+                fetch_package(url, Some("9.9.9"), DownloadEventType::UpdateDownload).await
+            })
+            .unwrap();
+
+        assert_eq!(fetched, package);
+        assert_eq!(get_update_download_status().map(|s| s.progress), Some(100));
+        discard(url);
+    }
+
+    #[test]
+    fn staged_delta_moves_into_place() {
+        let url = "https://example.com/Cloud-PE-One_v9.9.8.zip";
+        let (dir, mut meta) = open_entry(url, Some("9.9.8")).unwrap();
+        let staged = dir.join(DELTA_STAGING_NAME);
+        fs::create_dir_all(staged.join("sub")).unwrap();
+        fs::write(staged.join("sub").join("a.txt"), b"new").unwrap();
+        meta.staged = Some(staged);
+        write_meta(&dir, &meta).unwrap();
+
+        let target = cache_root().unwrap().join("target");
+        assert!(take_staged_delta(url, &target).unwrap());
+        assert_eq!(fs::read(target.join("sub").join("a.txt")).unwrap(), b"new");
+        assert!(cached_meta(url).is_none());
+        assert!(!take_staged_delta(url, &target).unwrap());
+        fs::remove_dir_all(target).ok();
+    }
+}
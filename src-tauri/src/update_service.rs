@@ -0,0 +1,539 @@
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::command;
+
+use crate::paths::{app_data_dir, app_install_dir};
+
+const UPDATE_API_URL: &str = "https://api.cloud-pe.cn/GetInfo/";
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/138.0.0.0 Safari/537.36 Edg/138.0.0.0";
+const SETTINGS_FILE: &str = "update_settings.json";
+const POLICY_FILE: &str = "update_policy.json";
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// 服务器返回的单个版本的更新日志
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionLog {
+    #[serde(default)]
+    pub can_skip: String,
+    #[serde(default)]
+    pub log: String,
+    #[serde(default)]
+    pub md5: String,
+}
+
+// 客户端更新清单（hub_new / hub_beta）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HubManifest {
+    pub hub_ver: String,
+    #[serde(default)]
+    pub hub_tip: String,
+    #[serde(default)]
+    pub hub_tip_type: String,
+    pub hub_updata_link: String,
+    pub app_name_exe: String,
+    #[serde(default)]
+    pub log: HashMap<String, VersionLog>,
+}
+
+impl HubManifest {
+    fn version_log(&self, version: &str) -> Option<&VersionLog> {
+        self.log.get(version).or_else(|| {
+            self.log
+                .iter()
+                .find(|(v, _)| compare_versions(v, version) == Ordering::Equal)
+                .map(|(_, log)| log)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateApiResponse {
+    code: i64,
+    #[serde(default)]
+    message: String,
+    hub_new: HubManifest,
+    // 测试版清单，服务器未提供时测试通道回退到正式版
+    #[serde(default)]
+    hub_beta: Option<HubManifest>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+    Pinned,
+}
+
+// 用户自己的更新设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    #[serde(default)]
+    pub pinned_version: Option<String>,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            pinned_version: None,
+        }
+    }
+}
+
+// 管理员策略，存在时覆盖用户设置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePolicy {
+    #[serde(default)]
+    pub frozen: bool,
+    #[serde(default)]
+    pub channel: Option<UpdateChannel>,
+    #[serde(default)]
+    pub pinned_version: Option<String>,
+    // 固定版本的下载地址（如内网镜像），服务器已不再发布该版本时使用
+    #[serde(default)]
+    pub pinned_url: Option<String>,
+    #[serde(default)]
+    pub pinned_app_name: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+// 返回给前端的更新决定
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDecision {
+    pub available: bool,
+    pub current_version: String,
+    pub version: String,
+    pub update_log: String,
+    pub download_link: String,
+    pub app_executable_name: String,
+    pub can_skip: bool,
+    pub channel: UpdateChannel,
+    pub frozen: bool,
+    pub managed: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSettingsView {
+    pub settings: UpdateSettings,
+    pub policy: Option<UpdatePolicy>,
+}
+
+// 比较版本号，忽略前缀 v，缺少的段视为 0
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim()
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+
+    let (a, b) = (parse(a), parse(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn settings_path() -> Result<PathBuf> {
+    Ok(app_data_dir()?.join(SETTINGS_FILE))
+}
+
+fn load_settings() -> UpdateSettings {
+    settings_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(settings: &UpdateSettings) -> Result<()> {
+    let path = settings_path()?;
+    fs::write(path, serde_json::to_string_pretty(settings)?)?;
+    Ok(())
+}
+
+// 策略文件位置：%ProgramData%\Cloud-PE One 优先，其次为程序目录
+fn policy_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Ok(dir) = std::env::var("ProgramData") {
+        paths.push(PathBuf::from(dir).join("Cloud-PE One").join(POLICY_FILE));
+    }
+    if let Ok(dir) = app_install_dir() {
+        paths.push(dir.join(POLICY_FILE));
+    }
+    paths
+}
+
+pub fn load_policy() -> Option<UpdatePolicy> {
+    for path in policy_paths() {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        match serde_json::from_str(&content) {
+            Ok(policy) => return Some(policy),
+            Err(e) => {
+                // 策略文件损坏时按冻结处理，避免机器偏离管理员指定的版本
+                eprintln!("解析更新策略失败 {}: {}", path.display(), e);
+                return Some(UpdatePolicy {
+                    frozen: true,
+                    message: Some(format!("更新策略文件无效: {}", e)),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    None
+}
+
+async fn fetch_manifest() -> Result<UpdateApiResponse> {
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .build()?;
+
+    let response: UpdateApiResponse = client
+        .get(UPDATE_API_URL)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if response.code != 200 {
+        anyhow::bail!("API返回错误: {}", response.message);
+    }
+    Ok(response)
+}
+
+// 可以跳过的更新：can_skip 为 "0"（或 "false"）时为强制更新
+fn is_skippable(log: Option<&VersionLog>) -> bool {
+    match log {
+        Some(log) => !matches!(log.can_skip.trim(), "0" | "false"),
+        None => true,
+    }
+}
+
+fn no_update(
+    channel: UpdateChannel,
+    policy: &Option<UpdatePolicy>,
+    message: Option<String>,
+) -> UpdateDecision {
+    UpdateDecision {
+        available: false,
+        current_version: CURRENT_VERSION.to_string(),
+        version: CURRENT_VERSION.to_string(),
+        update_log: String::new(),
+        download_link: String::new(),
+        app_executable_name: String::new(),
+        can_skip: true,
+        channel,
+        frozen: policy.as_ref().map(|p| p.frozen).unwrap_or(false),
+        managed: policy.is_some(),
+        message,
+    }
+}
+
+fn offer(
+    manifest: &HubManifest,
+    channel: UpdateChannel,
+    policy: &Option<UpdatePolicy>,
+    force: bool,
+) -> UpdateDecision {
+    let log = manifest.version_log(&manifest.hub_ver);
+    UpdateDecision {
+        available: true,
+        current_version: CURRENT_VERSION.to_string(),
+        version: manifest.hub_ver.clone(),
+        update_log: log.map(|l| l.log.clone()).unwrap_or_default(),
+        download_link: manifest.hub_updata_link.clone(),
+        app_executable_name: manifest.app_name_exe.clone(),
+        can_skip: !force && is_skippable(log),
+        channel,
+        frozen: false,
+        managed: policy.is_some(),
+        message: policy.as_ref().and_then(|p| p.message.clone()),
+    }
+}
+
+// 实际生效的通道：管理员固定了版本时强制使用固定版本通道，其次是策略指定的通道
+fn effective_channel(settings: &UpdateSettings, policy: &Option<UpdatePolicy>) -> UpdateChannel {
+    match policy {
+        Some(policy) if policy.pinned_version.is_some() => UpdateChannel::Pinned,
+        Some(policy) => policy.channel.unwrap_or(settings.channel),
+        None => settings.channel,
+    }
+}
+
+// 根据通道、固定版本和管理员策略决定是否提供更新
+fn decide(
+    response: &UpdateApiResponse,
+    settings: &UpdateSettings,
+    policy: &Option<UpdatePolicy>,
+) -> UpdateDecision {
+    let channel = effective_channel(settings, policy);
+
+    if policy.as_ref().map(|p| p.frozen).unwrap_or(false) {
+        return decide_frozen(settings, policy);
+    }
+
+    let stable = &response.hub_new;
+    let beta = response.hub_beta.as_ref();
+
+    match channel {
+        UpdateChannel::Stable => {
+            if compare_versions(&stable.hub_ver, CURRENT_VERSION) == Ordering::Greater {
+                offer(stable, channel, policy, false)
+            } else {
+                no_update(channel, policy, None)
+            }
+        }
+        UpdateChannel::Beta => {
+            // 测试通道取正式版与测试版中较新的一个
+            let newest = match beta {
+                Some(beta)
+                    if compare_versions(&beta.hub_ver, &stable.hub_ver) == Ordering::Greater =>
+                {
+                    beta
+                }
+                _ => stable,
+            };
+            if compare_versions(&newest.hub_ver, CURRENT_VERSION) == Ordering::Greater {
+                offer(newest, channel, policy, false)
+            } else {
+                no_update(channel, policy, None)
+            }
+        }
+        UpdateChannel::Pinned => {
+            let managed_pin = policy.as_ref().and_then(|p| p.pinned_version.clone());
+            let pinned = match managed_pin
+                .clone()
+                .or_else(|| settings.pinned_version.clone())
+            {
+                Some(version) => version,
+                None => return no_update(channel, policy, Some("未指定固定版本".to_string())),
+            };
+
+            if compare_versions(&pinned, CURRENT_VERSION) == Ordering::Equal {
+                return no_update(channel, policy, None);
+            }
+
+            // 管理员固定的版本必须安装，用户自己固定的版本遵循服务器的 can_skip
+            let force = managed_pin.is_some();
+            let published = std::iter::once(stable)
+                .chain(beta)
+                .find(|m| compare_versions(&m.hub_ver, &pinned) == Ordering::Equal);
+
+            if let Some(manifest) = published {
+                return offer(manifest, channel, policy, force);
+            }
+
+            match policy.as_ref().and_then(|p| p.pinned_url.clone()) {
+                Some(url) => {
+                    let mut decision = offer(stable, channel, policy, force);
+                    decision.version = pinned.clone();
+                    decision.download_link = url;
+                    decision.update_log = stable
+                        .version_log(&pinned)
+                        .map(|l| l.log.clone())
+                        .unwrap_or_default();
+                    if let Some(name) = policy.as_ref().and_then(|p| p.pinned_app_name.clone()) {
+                        decision.app_executable_name = name;
+                    }
+                    decision
+                }
+                None => no_update(
+                    channel,
+                    policy,
+                    Some(format!("服务器未提供固定版本 {}", pinned)),
+                ),
+            }
+        }
+    }
+}
+
+// 下载更新前调用，冻结时拒绝
+pub fn ensure_update_allowed() -> Result<(), String> {
+    match load_policy() {
+        Some(policy) if policy.frozen => Err(policy
+            .message
+            .unwrap_or_else(|| "管理员已冻结更新".to_string())),
+        _ => Ok(()),
+    }
+}
+
+#[command]
+pub async fn check_app_update() -> Result<UpdateDecision, String> {
+    evaluate_update()
+        .await
+        .map_err(|e| format!("获取更新信息失败: {}", e))
+}
+
+// 按当前设置和策略检查更新（界面和后台预下载共用）
+pub async fn evaluate_update() -> Result<UpdateDecision> {
+    let settings = load_settings();
+    let policy = load_policy();
+
+    // 冻结时不访问服务器
+    if policy.as_ref().map(|p| p.frozen).unwrap_or(false) {
+        return Ok(decide_frozen(&settings, &policy));
+    }
+
+    let response = fetch_manifest().await?;
+
+    let decision = decide(&response, &settings, &policy);
+    println!(
+        "更新检查: 当前 {}，通道 {:?}，目标 {}，可用 {}",
+        CURRENT_VERSION, decision.channel, decision.version, decision.available
+    );
+    Ok(decision)
+}
+
+fn decide_frozen(settings: &UpdateSettings, policy: &Option<UpdatePolicy>) -> UpdateDecision {
+    let channel = effective_channel(settings, policy);
+    let message = policy
+        .as_ref()
+        .and_then(|p| p.message.clone())
+        .unwrap_or_else(|| "管理员已冻结更新".to_string());
+    no_update(channel, policy, Some(message))
+}
+
+#[command]
+pub fn get_update_settings() -> UpdateSettingsView {
+    UpdateSettingsView {
+        settings: load_settings(),
+        policy: load_policy(),
+    }
+}
+
+#[command]
+pub fn set_update_settings(
+    channel: UpdateChannel,
+    pinned_version: Option<String>,
+) -> Result<UpdateSettingsView, String> {
+    if let Some(policy) = load_policy() {
+        if policy.frozen || policy.channel.is_some() || policy.pinned_version.is_some() {
+            return Err("更新设置由管理员策略管理，无法修改".to_string());
+        }
+    }
+
+    let pinned_version = pinned_version
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    if channel == UpdateChannel::Pinned && pinned_version.is_none() {
+        return Err("固定版本通道需要指定版本号".to_string());
+    }
+
+    let settings = UpdateSettings {
+        channel,
+        pinned_version,
+    };
+    save_settings(&settings).map_err(|e| e.to_string())?;
+
+    Ok(UpdateSettingsView {
+        settings,
+        policy: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(version: &str) -> HubManifest {
+        HubManifest {
+            hub_ver: version.to_string(),
+            hub_tip: String::new(),
+            hub_tip_type: String::new(),
+            hub_updata_link: format!("https://example.com/Cloud-PE-One_v{}.zip", version),
+            app_name_exe: "Cloud-PE One.exe".to_string(),
+            log: HashMap::new(),
+        }
+    }
+
+    fn response() -> UpdateApiResponse {
+        UpdateApiResponse {
+            code: 200,
+            message: String::new(),
+            hub_new: manifest("99.0.0"),
+            hub_beta: Some(manifest("99.1.0")),
+        }
+    }
+
+    fn pinned_policy(version: &str, channel: Option<UpdateChannel>) -> Option<UpdatePolicy> {
+        Some(UpdatePolicy {
+            channel,
+            pinned_version: Some(version.to_string()),
+            pinned_url: Some("https://mirror.example.com/pinned.zip".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn policy_pin_forces_pinned_channel() {
+        let settings = UpdateSettings::default();
+        for channel in [None, Some(UpdateChannel::Stable), Some(UpdateChannel::Beta)] {
+            let decision = decide(&response(), &settings, &pinned_policy("99.0.0", channel));
+            assert_eq!(decision.channel, UpdateChannel::Pinned);
+            assert_eq!(decision.version, "99.0.0");
+            assert!(!decision.can_skip);
+        }
+    }
+
+    #[test]
+    fn policy_pin_overrides_user_pin() {
+        let settings = UpdateSettings {
+            channel: UpdateChannel::Pinned,
+            pinned_version: Some("99.1.0".to_string()),
+        };
+        let decision = decide(&response(), &settings, &pinned_policy("50.0.0", None));
+        assert_eq!(decision.version, "50.0.0");
+        assert_eq!(
+            decision.download_link,
+            "https://mirror.example.com/pinned.zip"
+        );
+    }
+
+    #[test]
+    fn policy_channel_applies_without_pin() {
+        let policy = Some(UpdatePolicy {
+            channel: Some(UpdateChannel::Beta),
+            ..Default::default()
+        });
+        let decision = decide(&response(), &UpdateSettings::default(), &policy);
+        assert_eq!(decision.channel, UpdateChannel::Beta);
+        assert_eq!(decision.version, "99.1.0");
+    }
+
+    #[test]
+    fn frozen_policy_reports_pinned_channel() {
+        let mut policy = pinned_policy("99.0.0", Some(UpdateChannel::Stable));
+        policy.as_mut().unwrap().frozen = true;
+        let decision = decide(&response(), &UpdateSettings::default(), &policy);
+        assert!(!decision.available);
+        assert_eq!(decision.channel, UpdateChannel::Pinned);
+    }
+}
@@ -1,61 +1,181 @@
 use anyhow::Result;
-use encoding_rs::GBK;
 use std::fs::{self, File};
-use std::io::{self, Write};
-use std::path::Path;
-use std::process::Command;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use encoding_rs::GBK;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
 use zip::ZipArchive;
-use tauri::{command, AppHandle};
-use crate::download::{download_update_package, get_update_download_status, DownloadStatus};
+use tauri::{command, AppHandle, Emitter, Manager};
+use crate::download::{
+    get_update_download_status, mark_update_download_complete, DownloadEventType, DownloadStatus,
+};
+use crate::delta_update::prepare_delta_update;
+use crate::paths::available_space;
+use crate::signature::{signature_url, verify_update_package_from};
+use crate::update_prefetch::{cached_package, discard, fetch_package, lock_fetch, take_staged_delta};
+use crate::update_service::{
+    compare_versions, ensure_update_allowed, evaluate_update, load_policy, CURRENT_VERSION,
+};
+use crate::update_helper::{
+    self, HelperMode, UpdatePlan, UpdateResult, BACKUP_DIR_NAME, HEALTH_FILE_NAME,
+    PLAN_FILE_NAME, POST_UPDATE_FLAG, STAGING_DIR_NAME,
+};
+
+// 解压限制，防止压缩炸弹
+const MAX_EXTRACT_TOTAL_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+const MAX_EXTRACT_ENTRIES: usize = 50_000;
+// 预留的磁盘空间
+const EXTRACT_FREE_SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+pub const EXTRACT_PROGRESS_EVENT: &str = "update://extract-progress";
+// 小文件很多时逐个发送进度会拖慢界面，按时间间隔合并
+const EXTRACT_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const PACKAGE_INFO_NAME: &str = "version.json";
+// 本地更新包先复制到程序目录下的这个目录，再校验和解压
+const LOCAL_PACKAGE_DIR: &str = "updatePackage";
+
+// 解压进度事件
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractProgress {
+    pub processed_entries: usize,
+    pub total_entries: usize,
+    pub extracted_bytes: u64,
+    pub total_bytes: u64,
+    pub current_file: String,
+    pub percent: f64,
+}
+
+fn emit_extract_progress(app_handle: Option<&AppHandle>, progress: &ExtractProgress) {
+    if let Some(app) = app_handle {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.emit(EXTRACT_PROGRESS_EVENT, progress);
+        }
+    }
+}
 
+// 未设置 UTF-8 标志的旧压缩包通常使用 CP936（GBK）编码文件名
+fn decode_entry_name(raw: &[u8], fallback: &str) -> String {
+    if let Ok(name) = std::str::from_utf8(raw) {
+        return name.to_string();
+    }
+    match GBK.decode_without_bom_handling_and_without_replacement(raw) {
+        Some(name) => name.into_owned(),
+        None => fallback.to_string(),
+    }
+}
 
-fn extract_archive(
+// 只接受相对路径，拒绝绝对路径和 ..
+fn sanitize_entry_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.replace('\\', "/").split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            _ if part.contains(':') => return None,
+            _ => path.push(part),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+pub(crate) fn extract_archive(
     archive_path: &str,
     extract_dir: &str,
+    app_handle: Option<&AppHandle>,
 ) -> Result<()> {
     println!("开始解压文件...");
     println!("压缩包路径: {}", archive_path);
     println!("解压目标目录: {}", extract_dir);
 
     let extract_path = Path::new(extract_dir);
-    if !extract_path.exists() {
-        fs::create_dir_all(extract_path)?;
-        println!("已创建解压目标目录");
-    }
-
     let file = File::open(archive_path)?;
     let mut archive = ZipArchive::new(file)?;
 
     let total_files = archive.len();
     println!("压缩包中共有 {} 个文件", total_files);
+    if total_files > MAX_EXTRACT_ENTRIES {
+        anyhow::bail!("压缩包文件数过多 ({} > {})", total_files, MAX_EXTRACT_ENTRIES);
+    }
+
+    // 声明的大小仅用于预检，实际写入时仍按真实字节数限制
+    let mut declared_total = 0u64;
+    for i in 0..total_files {
+        declared_total = declared_total.saturating_add(archive.by_index_raw(i)?.size());
+    }
+    if declared_total > MAX_EXTRACT_TOTAL_SIZE {
+        anyhow::bail!("压缩包解压后过大 ({} 字节)", declared_total);
+    }
+    if let Some(available) = available_space(extract_path) {
+        if available < declared_total.saturating_add(EXTRACT_FREE_SPACE_MARGIN) {
+            anyhow::bail!(
+                "磁盘空间不足，需要 {} MB，可用 {} MB",
+                declared_total / 1024 / 1024 + 1,
+                available / 1024 / 1024
+            );
+        }
+    }
 
+    if !extract_path.exists() {
+        fs::create_dir_all(extract_path)?;
+        println!("已创建解压目标目录");
+    }
+
+    let mut extracted_bytes = 0u64;
+    let mut last_emit: Option<Instant> = None;
     for i in 0..total_files {
         let mut file = archive.by_index(i)?;
-        let outpath = match file.enclosed_name() {
-            Some(path) => extract_path.join(path),
-            None => continue,
+        let name = decode_entry_name(file.name_raw(), file.name());
+        let relative = match sanitize_entry_path(&name) {
+            Some(path) => path,
+            None => {
+                println!("跳过不安全的路径: {}", name);
+                continue;
+            }
         };
+        let outpath = extract_path.join(&relative);
 
-        if file.name().ends_with('/') {
+        if file.is_dir() || name.ends_with('/') || name.ends_with('\\') {
             fs::create_dir_all(&outpath)?;
-            continue;
-        }
+        } else {
+            if let Some(p) = outpath.parent() {
+                if !p.exists() {
+                    fs::create_dir_all(p)?;
+                }
+            }
 
-        if let Some(p) = outpath.parent() {
-            if !p.exists() {
-                fs::create_dir_all(&p)?;
+            let remaining = MAX_EXTRACT_TOTAL_SIZE - extracted_bytes;
+            let mut outfile = File::create(&outpath)?;
+            let written = io::copy(&mut (&mut file).take(remaining + 1), &mut outfile)?;
+            extracted_bytes += written;
+            if extracted_bytes > MAX_EXTRACT_TOTAL_SIZE {
+                anyhow::bail!("解压数据超过限制，压缩包可能已损坏或被篡改");
             }
         }
 
-        let mut outfile = File::create(&outpath)?;
-        io::copy(&mut file, &mut outfile)?;
-
-        if (i + 1) % 10 == 0 || i + 1 == total_files {
-            println!(
-                "解压进度: {}/{} ({:.1}%)",
-                i + 1,
-                total_files,
-                ((i + 1) as f64 / total_files as f64) * 100.0
+        // 第一个和最后一个文件总是发送，其余按间隔发送
+        let is_last = i + 1 == total_files;
+        let due = match last_emit {
+            Some(t) => t.elapsed() >= EXTRACT_PROGRESS_INTERVAL,
+            None => true,
+        };
+        if is_last || due {
+            last_emit = Some(Instant::now());
+            emit_extract_progress(
+                app_handle,
+                &ExtractProgress {
+                    processed_entries: i + 1,
+                    total_entries: total_files,
+                    extracted_bytes,
+                    total_bytes: declared_total,
+                    current_file: name,
+                    percent: ((i + 1) as f64 / total_files as f64) * 100.0,
+                },
             );
         }
     }
@@ -63,147 +183,344 @@ fn extract_archive(
     println!("文件解压完成！");
     println!("解压后的文件位于: {}", extract_dir);
 
-    println!("\n解压后的主要内容:");
-    if let Ok(entries) = fs::read_dir(extract_dir) {
-        for entry in entries.flatten().take(10) {
-            if let Ok(file_name) = entry.file_name().into_string() {
-                let file_type = if entry.path().is_dir() {
-                    "[目录]"
-                } else {
-                    "[文件]"
-                };
-                println!("  {} {}", file_type, file_name);
+    Ok(())
+}
+
+// 新版本启动成功后调用，通知更新助手无需回滚
+pub fn report_update_health() {
+    if !std::env::args().any(|arg| arg == POST_UPDATE_FLAG) {
+        return;
+    }
+
+    match std::env::current_exe() {
+        Ok(exe_path) => {
+            if let Some(app_dir) = exe_path.parent() {
+                let health_file = app_dir.join(HEALTH_FILE_NAME);
+                if let Err(e) = fs::write(&health_file, env!("CARGO_PKG_VERSION")) {
+                    println!("写入更新健康标记失败: {}", e);
+                }
             }
         }
+        Err(e) => println!("获取exe路径失败: {}", e),
     }
+}
 
-    Ok(())
+
+fn current_app_dir() -> Result<std::path::PathBuf, String> {
+    let exe_path = std::env::current_exe().map_err(|e| format!("获取exe路径失败: {}", e))?;
+    Ok(exe_path.parent().ok_or("无法获取exe父目录")?.to_path_buf())
 }
 
-fn create_update_script(
-    app_dir: &str,
-    tmp_dir: &str,
-    app_name: &str,
-) -> Result<String> {
-    println!("开始创建更新脚本...");
-    let script_path = Path::new(app_dir).join("tmpAppUpdata.bat");
-
-    let mut script_content = String::new();
-
-    #[cfg(target_os = "windows")]
-    {
-        script_content.push_str("@echo off\r\n");
-        script_content.push_str("echo ==========================================\r\n");
-        script_content.push_str("echo 开始执行应用程序更新...\r\n");
-        script_content.push_str("echo ==========================================\r\n");
-        script_content.push_str("echo.\r\n");
-        script_content.push_str("echo [步骤1/5] 等待应用程序关闭...\r\n");
-        script_content.push_str("timeout /t 2 /nobreak > nul\r\n");
-        script_content.push_str("echo [完成] 应用程序已关闭\r\n");
-        script_content.push_str("echo.\r\n");
-
-        script_content.push_str("echo [步骤2/5] 清理旧版本文件...\r\n");
-        script_content.push_str("for /d %%i in (*) do if not \"%%i\"==\"tmpFile\" (\r\n");
-        script_content.push_str("    echo   删除目录: %%i\r\n");
-        script_content.push_str("    rd /s /q \"%%i\"\r\n");
-        script_content.push_str(")\r\n");
-
-        script_content.push_str("for %%i in (*) do if not \"%%i\"==\"tmpAppUpdata.bat\" (\r\n");
-        script_content.push_str("    echo   删除文件: %%i\r\n");
-        script_content.push_str("    del \"%%i\"\r\n");
-        script_content.push_str(")\r\n");
-        script_content.push_str("echo [完成] 旧版本文件已清理\r\n");
-        script_content.push_str("echo.\r\n");
-
-        script_content.push_str("echo [步骤3/5] 安装新版本文件...\r\n");
-        script_content.push_str(&format!("echo   源目录: {}\r\n", tmp_dir));
-        script_content.push_str(&format!("echo   目标目录: {}\r\n", app_dir));
-        script_content.push_str(&format!(
-            "xcopy /e /y \"{}\\*\" \"{}\\\" > nul\r\n",
-            tmp_dir, app_dir
-        ));
-        script_content.push_str("echo [完成] 新版本文件已安装\r\n");
-        script_content.push_str("echo.\r\n");
-
-        script_content.push_str("echo [步骤4/5] 清理临时文件...\r\n");
-        script_content.push_str(&format!("rd /s /q \"{}\"\r\n", tmp_dir));
-        script_content.push_str("echo [完成] 临时文件已清理\r\n");
-        script_content.push_str("echo.\r\n");
-
-        script_content.push_str("echo [步骤5/5] 启动新版本应用程序...\r\n");
-        script_content.push_str(&format!("echo   应用程序: {}\r\n", app_name));
-        script_content.push_str(&format!("start \"\" \"{}\"\r\n", app_name));
-        script_content.push_str("echo [完成] 新版本应用程序已启动\r\n");
-        script_content.push_str("echo.\r\n");
-
-        script_content.push_str("echo ==========================================\r\n");
-        script_content.push_str("echo 更新完成！\r\n");
-        script_content.push_str("echo ==========================================\r\n");
-        script_content.push_str("echo.\r\n");
-        script_content.push_str("echo 正在清理更新脚本...\r\n");
-
-        script_content.push_str("del \"%~f0\"\r\n");
-    }
-
-    let (encoded, _, _) = GBK.encode(&script_content);
-
-    let mut file = File::create(&script_path)?;
-    file.write_all(&encoded)?;
-
-    println!("更新脚本创建完成: {}", script_path.display());
-    Ok(script_path.to_string_lossy().to_string())
+// 下载、验证并解压完整更新包到临时目录
+async fn download_full_package(
+    app_handle: &AppHandle,
+    url: String,
+    version: &str,
+    tmp_dir_path: &Path,
+) -> Result<(), String> {
+    println!("\n[步骤 1/5] 下载更新包...");
+    // 下载到暂存区，可续传
+    let package = fetch_package(&url, Some(version), DownloadEventType::UpdateDownload)
+        .await
+        .map_err(|e| e.to_string())?;
+    let download_result = package.to_string_lossy().to_string();
+
+    println!("已完成下载更新包");
+    println!("更新包位置: {}", download_result);
+
+    println!("\n[步骤 2/5] 验证更新包签名...");
+    if let Err(e) = verify_update_package_from(&package, &signature_url(&url), version).await {
+        discard(&url);
+        return Err(format!("更新包签名验证失败，已拒绝安装: {}", e));
+    }
+    println!("已完成验证更新包签名");
+
+    println!("\n[步骤 3/5] 解压更新包...");
+    extract_archive(&download_result, &tmp_dir_path.to_string_lossy(), Some(app_handle))
+        .map_err(|e| format!("解压失败: {}", e))?;
+    println!("已完成解压更新包");
+
+    println!("\n[步骤 4/5] 清理下载的压缩包...");
+    discard(&url);
+    println!("已完成清理压缩包");
+
+    Ok(())
 }
 
 #[command]
-pub async fn download_update(url: String, app_name: String) -> Result<String, String> {
+pub async fn download_update(
+    app_handle: AppHandle,
+    url: String,
+    app_name: String,
+) -> Result<String, String> {
     println!("\n========================================");
     println!("开始应用程序更新流程");
     println!("========================================\n");
 
-    let exe_path = std::env::current_exe().map_err(|e| format!("获取exe路径失败: {}", e))?;
-    let app_dir = exe_path.parent().ok_or("无法获取exe父目录")?.to_path_buf();
+    // 管理员冻结更新时拒绝下载
+    ensure_update_allowed()?;
+
+    // 以后端的更新决定为准，签名中的版本必须与之一致，防止旧版本的包和签名被重放
+    let decision = evaluate_update()
+        .await
+        .map_err(|e| format!("获取更新信息失败: {}", e))?;
+    if !decision.available || decision.download_link != url {
+        return Err("更新地址与当前可用的更新不一致，已拒绝下载".to_string());
+    }
+    let version = decision.version;
+    println!("目标版本: {}", version);
+    if let Some(pinned) = load_policy().and_then(|p| p.pinned_version) {
+        if compare_versions(&pinned, &version) != Ordering::Equal {
+            return Err(format!("管理员已将版本固定为 {}", pinned));
+        }
+    }
+
+    let app_dir = current_app_dir()?;
 
     println!("应用程序安装目录: {}", app_dir.display());
 
-    let app_dir_str = app_dir.to_string_lossy().to_string();
-    let tmp_dir_path = app_dir.join("tmpFile");
+    let tmp_dir_path = app_dir.join(STAGING_DIR_NAME);
+
+    // 后台预下载进行中时等待其完成，避免同时写入暂存区
+    let _fetch_guard = lock_fetch().await;
+
+    // 优先尝试增量更新，任何不一致都回退到完整更新包
+    println!("\n检查增量更新...");
+    let staged = take_staged_delta(&url, &tmp_dir_path).unwrap_or_else(|e| {
+        println!("无法使用预先准备的增量更新: {}", e);
+        false
+    });
+    let delta_ready = if staged {
+        println!("使用后台预先准备的增量更新");
+        true
+    } else if cached_package(&url).is_some() {
+        println!("更新包已在后台预下载，跳过增量更新");
+        false
+    } else {
+        match prepare_delta_update(
+            &url,
+            &version,
+            &app_dir,
+            &tmp_dir_path,
+            DownloadEventType::UpdateDownload,
+        )
+        .await
+        {
+            Ok(ready) => ready,
+            Err(e) => {
+                println!("增量更新失败，改用完整更新包: {}", e);
+                false
+            }
+        }
+    };
 
-    println!("\n[步骤 1/4] 下载更新包...");
-    let download_result = download_update_package(
-        url,
-        app_dir.clone(),
-        8,
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    if delta_ready {
+        println!("已通过增量包准备新版本文件，跳过步骤 1-4");
+    } else {
+        download_full_package(&app_handle, url, &version, &tmp_dir_path).await?;
+    }
+    // 界面在进度达到 100 后开始安装，使用预先准备的文件时也需要标记完成
+    mark_update_download_complete(&DownloadEventType::UpdateDownload);
 
-    println!("已完成下载更新包");
-    println!("更新包位置: {}", download_result);
+    println!("\n[步骤 5/5] 生成更新计划...");
+    let plan_path = write_update_plan(&app_dir, app_name, Some(version))?;
+    println!("已完成生成更新计划");
 
-    println!("\n[步骤 2/4] 解压更新包...");
-    extract_archive(&download_result, &tmp_dir_path.to_string_lossy())
-        .map_err(|e| format!("解压失败: {}", e))?;
-    println!("已完成解压更新包");
+    println!("\n========================================");
+    println!("更新准备完成！");
+    println!("========================================\n");
 
-    println!("\n[步骤 3/4] 清理下载的压缩包...");
-    if let Err(e) = fs::remove_file(&download_result) {
-        println!("警告: 无法删除压缩包: {}", e);
-    } else {
-        println!("已完成清理压缩包");
+    Ok(plan_path)
+}
+
+// 写入更新计划，返回计划文件路径，由 install_update 交给更新助手执行
+fn write_update_plan(app_dir: &Path, app_name: String, version: Option<String>) -> Result<String, String> {
+    let plan = UpdatePlan {
+        mode: HelperMode::Update,
+        parent_pid: std::process::id(),
+        app_dir: app_dir.to_path_buf(),
+        app_exe: app_name,
+        version,
+    };
+    let plan_path = app_dir.join(PLAN_FILE_NAME);
+    let content = serde_json::to_string_pretty(&plan).map_err(|e| e.to_string())?;
+    fs::write(&plan_path, content).map_err(|e| format!("写入更新计划失败: {}", e))?;
+    Ok(plan_path.to_string_lossy().to_string())
+}
+
+// 更新包内可选的版本信息文件
+#[derive(Deserialize)]
+struct PackageInfo {
+    version: String,
+    #[serde(default)]
+    app_name_exe: Option<String>,
+}
+
+// 从文件名中提取版本号，如 Cloud-PE-One_v1.7.0.zip
+fn version_from_file_name(name: &str) -> Option<String> {
+    name.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map(|part| part.trim_matches('.'))
+        .find(|part| part.contains('.') && part.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|part| part.to_string())
+}
+
+// 读取更新包版本：优先使用包内的 version.json，其次从文件名解析
+fn read_package_info(package: &Path) -> Result<PackageInfo> {
+    let mut archive = ZipArchive::new(File::open(package)?)?;
+    if let Ok(mut entry) = archive.by_name(PACKAGE_INFO_NAME) {
+        let mut content = String::new();
+        (&mut entry).take(64 * 1024).read_to_string(&mut content)?;
+        return Ok(serde_json::from_str(&content)?);
     }
 
-    println!("\n[步骤 4/4] 创建更新脚本...");
-    let script_path =
-        create_update_script(&app_dir_str, &tmp_dir_path.to_string_lossy(), &app_name)
-            .map_err(|e| e.to_string())?;
+    let file_name = package
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match version_from_file_name(&file_name) {
+        Some(version) => Ok(PackageInfo {
+            version,
+            app_name_exe: None,
+        }),
+        None => anyhow::bail!("无法确定更新包版本，包内缺少 {}", PACKAGE_INFO_NAME),
+    }
+}
+
+// 本地更新包摘要，供界面在安装前确认
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalPackageSummary {
+    pub version: String,
+    pub current_version: String,
+}
+
+// 检查本地更新包：必须带有效签名 (.minisig)，签名中的版本需与包内声明一致，且版本可安装
+async fn check_local_package(package: &Path, allow_downgrade: bool) -> Result<PackageInfo, String> {
+    if !package.is_file() {
+        return Err(format!("更新包不存在: {}", package.display()));
+    }
 
-    println!("已完成创建更新脚本");
+    let info = read_package_info(package).map_err(|e| format!("读取更新包版本失败: {}", e))?;
+    println!("更新包版本: {}，当前版本: {}", info.version, CURRENT_VERSION);
+
+    let signature_path = signature_url(&package.to_string_lossy());
+    if !Path::new(&signature_path).is_file() {
+        return Err("未找到更新包签名文件 (.minisig)，已拒绝安装".to_string());
+    }
+    verify_update_package_from(package, &signature_path, &info.version)
+        .await
+        .map_err(|e| format!("更新包签名验证失败，已拒绝安装: {}", e))?;
+
+    match compare_versions(&info.version, CURRENT_VERSION) {
+        Ordering::Greater => {}
+        Ordering::Equal => return Err("更新包版本与当前版本相同".to_string()),
+        Ordering::Less if allow_downgrade => println!("允许降级安装"),
+        Ordering::Less => {
+            return Err(format!(
+                "更新包版本 {} 低于当前版本 {}",
+                info.version, CURRENT_VERSION
+            ))
+        }
+    }
+    if let Some(pinned) = load_policy().and_then(|p| p.pinned_version) {
+        if compare_versions(&pinned, &info.version) != Ordering::Equal {
+            return Err(format!("管理员已将版本固定为 {}", pinned));
+        }
+    }
+
+    Ok(info)
+}
+
+// 把本地更新包及其签名复制到程序目录，之后只使用副本，避免校验后 U 盘上的文件被替换
+fn copy_local_package(package: &Path, dest_dir: &Path) -> Result<PathBuf, String> {
+    if !package.is_file() {
+        return Err(format!("更新包不存在: {}", package.display()));
+    }
+    let file_name = package.file_name().ok_or("更新包路径无效")?;
+
+    if dest_dir.exists() {
+        fs::remove_dir_all(dest_dir).map_err(|e| format!("清理更新包目录失败: {}", e))?;
+    }
+    fs::create_dir_all(dest_dir).map_err(|e| format!("创建更新包目录失败: {}", e))?;
+
+    let copy = dest_dir.join(file_name);
+    fs::copy(package, &copy).map_err(|e| format!("复制更新包失败: {}", e))?;
+    // 没有签名时不复制，由校验步骤拒绝
+    let signature = signature_url(&package.to_string_lossy());
+    if Path::new(&signature).is_file() {
+        fs::copy(&signature, signature_url(&copy.to_string_lossy()))
+            .map_err(|e| format!("复制更新包签名失败: {}", e))?;
+    }
+    Ok(copy)
+}
+
+// 安装前检查本地更新包，返回包版本供用户确认
+#[command]
+pub async fn inspect_update_package(
+    file_path: String,
+    allow_downgrade: Option<bool>,
+) -> Result<LocalPackageSummary, String> {
+    ensure_update_allowed()?;
+    let info = check_local_package(Path::new(&file_path), allow_downgrade.unwrap_or(false)).await?;
+    Ok(LocalPackageSummary {
+        version: info.version,
+        current_version: CURRENT_VERSION.to_string(),
+    })
+}
+
+// 从本地更新包安装（离线环境），返回更新计划路径
+#[command]
+pub async fn install_update_from_file(
+    app_handle: AppHandle,
+    file_path: String,
+    allow_downgrade: Option<bool>,
+) -> Result<String, String> {
+    println!("\n========================================");
+    println!("开始从本地更新包更新");
+    println!("========================================\n");
+
+    ensure_update_allowed()?;
+
+    let app_dir = current_app_dir()?;
+    let tmp_dir_path = app_dir.join(STAGING_DIR_NAME);
+    let package_dir = app_dir.join(LOCAL_PACKAGE_DIR);
+
+    println!("\n[步骤 1/4] 复制更新包...");
+    let package = copy_local_package(Path::new(&file_path), &package_dir)?;
+
+    let prepared = async {
+        println!("\n[步骤 2/4] 验证更新包...");
+        let info = check_local_package(&package, allow_downgrade.unwrap_or(false)).await?;
+
+        println!("\n[步骤 3/4] 解压更新包...");
+        if tmp_dir_path.exists() {
+            fs::remove_dir_all(&tmp_dir_path).map_err(|e| format!("清理临时目录失败: {}", e))?;
+        }
+        extract_archive(&package.to_string_lossy(), &tmp_dir_path.to_string_lossy(), Some(&app_handle))
+            .map_err(|e| format!("解压失败: {}", e))?;
+        fs::remove_file(tmp_dir_path.join(PACKAGE_INFO_NAME)).ok();
+        Ok::<_, String>(info)
+    }
+    .await;
+    // 副本只在校验和解压时使用，无论成功与否都删除
+    fs::remove_dir_all(&package_dir).ok();
+    let info = prepared?;
+
+    println!("\n[步骤 4/4] 生成更新计划...");
+    let app_name = match info.app_name_exe {
+        Some(name) => name,
+        None => std::env::current_exe()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .ok_or("无法获取程序文件名")?,
+    };
+    let plan_path = write_update_plan(&app_dir, app_name, Some(info.version))?;
 
     println!("\n========================================");
     println!("更新准备完成！");
     println!("========================================\n");
 
-    Ok(script_path)
+    Ok(plan_path)
 }
 
 #[command]
@@ -212,24 +529,130 @@ pub fn get_app_download_status() -> Option<DownloadStatus> {
 }
 
 #[command]
-pub fn install_update(app_handle: AppHandle, script_path: String) -> Result<(), String> {
+pub fn install_update(app_handle: AppHandle, plan_path: String) -> Result<(), String> {
     println!("\n========================================");
     println!("开始安装更新...");
     println!("========================================\n");
 
-    println!("执行更新脚本: {}", script_path);
+    let content = fs::read_to_string(&plan_path).map_err(|e| format!("读取更新计划失败: {}", e))?;
+    let mut plan: UpdatePlan = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    plan.parent_pid = std::process::id();
+
+    println!("启动更新助手: {}", plan_path);
+    update_helper::spawn_helper(&plan).map_err(|e| format!("启动更新助手失败: {}", e))?;
+
+    println!("更新助手已启动，应用程序即将退出...");
+
+    app_handle.exit(0);
+
+    Ok(())
+}
+
+#[command]
+pub fn rollback_update(app_handle: AppHandle) -> Result<(), String> {
+    let exe_path = std::env::current_exe().map_err(|e| format!("获取exe路径失败: {}", e))?;
+    let app_dir = current_app_dir()?;
 
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("cmd")
-            .args(&["/C", "start", "", &script_path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
+    if !app_dir.join(BACKUP_DIR_NAME).is_dir() {
+        return Err("没有可回滚的旧版本".to_string());
     }
 
-    println!("更新脚本已启动，应用程序即将退出...");
+    let app_name = exe_path
+        .file_name()
+        .ok_or("无法获取程序文件名")?
+        .to_string_lossy()
+        .to_string();
+
+    let plan = UpdatePlan {
+        mode: HelperMode::Rollback,
+        parent_pid: std::process::id(),
+        app_dir,
+        app_exe: app_name,
+        version: None,
+    };
+
+    update_helper::spawn_helper(&plan).map_err(|e| format!("启动更新助手失败: {}", e))?;
+
+    println!("更新助手已启动，应用程序即将退出...");
 
     app_handle.exit(0);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// 读取上一次更新或回滚的结果（读取后即清除）
+#[command]
+pub fn get_update_result() -> Result<Option<UpdateResult>, String> {
+    let app_dir = current_app_dir()?;
+    Ok(update_helper::take_update_result(&app_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_local_package, decode_entry_name, sanitize_entry_path};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn decodes_gbk_entry_names() {
+        // "插件/说明.txt" 的 GBK 编码
+        let raw = [
+            0xB2, 0xE5, 0xBC, 0xFE, b'/', 0xCB, 0xB5, 0xC3, 0xF7, b'.', b't', b'x', b't',
+        ];
+        assert_eq!(decode_entry_name(&raw, "fallback"), "插件/说明.txt");
+        assert_eq!(decode_entry_name("更新.exe".as_bytes(), "fallback"), "更新.exe");
+        assert_eq!(decode_entry_name(&[0xFF, 0xFF], "fallback"), "fallback");
+    }
+
+    #[test]
+    fn copies_the_package_with_its_signature() {
+        let root = std::env::temp_dir().join(format!("cloud-pe-local-update-{}", std::process::id()));
+        let source = root.join("usb");
+        fs::create_dir_all(&source).unwrap();
+        let package = source.join("Cloud-PE-One_1.7.0.zip");
+        fs::write(&package, b"package").unwrap();
+        fs::write(source.join("Cloud-PE-One_1.7.0.zip.minisig"), b"signature").unwrap();
+
+        let dest = root.join("updatePackage");
+        let copy = copy_local_package(&package, &dest).unwrap();
+        assert_eq!(copy, dest.join("Cloud-PE-One_1.7.0.zip"));
+
+        // 复制之后原文件被替换不影响副本
+        fs::write(&package, b"tampered").unwrap();
+        assert_eq!(fs::read(&copy).unwrap(), b"package");
+        assert_eq!(
+            fs::read(dest.join("Cloud-PE-One_1.7.0.zip.minisig")).unwrap(),
+            b"signature"
+        );
+
+        assert!(copy_local_package(&source.join("missing.zip"), &dest).is_err());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn keeps_entries_inside_the_target() {
+        assert_eq!(
+            sanitize_entry_path("app/resources\\icon.png"),
+            Some(PathBuf::from("app").join("resources").join("icon.png"))
+        );
+        assert_eq!(sanitize_entry_path("./a/./b"), Some(PathBuf::from("a").join("b")));
+        // 开头的分隔符被忽略，仍解压到目标目录内
+        assert_eq!(sanitize_entry_path("/etc/passwd"), Some(PathBuf::from("etc").join("passwd")));
+        assert_eq!(
+            sanitize_entry_path("\\Windows\\win.ini"),
+            Some(PathBuf::from("Windows").join("win.ini"))
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_entries() {
+        assert_eq!(sanitize_entry_path("../evil.exe"), None);
+        assert_eq!(sanitize_entry_path("a/../../evil.exe"), None);
+        assert_eq!(sanitize_entry_path("a\\..\\evil.exe"), None);
+        assert_eq!(sanitize_entry_path("C:\\Windows\\evil.exe"), None);
+        assert_eq!(sanitize_entry_path("C:evil.exe"), None);
+        assert_eq!(sanitize_entry_path("file.txt:stream"), None);
+        assert_eq!(sanitize_entry_path(""), None);
+        assert_eq!(sanitize_entry_path("./"), None);
+    }
+}
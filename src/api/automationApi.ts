@@ -0,0 +1,46 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// 本地自动化接口状态
+export interface AutomationStatus {
+  enabled: boolean;
+  running: boolean;
+  address: string;
+  token: string;
+  // 最近一次启动失败的原因（如端口被占用）
+  error: string | null;
+}
+
+// 获取自动化接口状态
+export const getAutomationStatus = async (): Promise<AutomationStatus> => {
+  return await invoke<AutomationStatus>('get_automation_status');
+};
+
+// 开启或关闭自动化接口
+export const setAutomationEnabled = async (enabled: boolean, port?: number): Promise<AutomationStatus> => {
+  try {
+    return await invoke<AutomationStatus>('set_automation_enabled', { enabled, port });
+  } catch (error) {
+    console.error('设置自动化接口失败:', error);
+    throw error;
+  }
+};
+
+// 重新生成访问令牌
+export const regenerateAutomationToken = async (): Promise<AutomationStatus> => {
+  try {
+    return await invoke<AutomationStatus>('regenerate_automation_token');
+  } catch (error) {
+    console.error('重新生成令牌失败:', error);
+    throw error;
+  }
+};
+
+// 监听自动化接口状态变化
+export const onAutomationStatusChange = async (
+  callback: (status: AutomationStatus) => void
+): Promise<UnlistenFn> => {
+  return await listen<AutomationStatus>('automation://status', (event) => {
+    callback(event.payload);
+  });
+};
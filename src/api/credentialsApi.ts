@@ -0,0 +1,42 @@
+import { invoke } from '@tauri-apps/api/core';
+
+// 私有镜像认证方式
+export type MirrorAuth =
+  | { type: 'basic'; username: string; password: string }
+  | { type: 'bearer'; token: string };
+
+// 已保存的镜像认证摘要（不包含密钥）
+export interface MirrorCredentialSummary {
+  host: string;
+  headerNames: string[];
+  authType: 'basic' | 'bearer' | null;
+}
+
+// 获取已保存的镜像认证列表
+export const listMirrorCredentials = async (): Promise<MirrorCredentialSummary[]> => {
+  return await invoke<MirrorCredentialSummary[]>('list_mirror_credentials');
+};
+
+// 保存主机的请求头与认证信息，已存在时覆盖
+export const setMirrorCredential = async (
+  host: string,
+  headers: Record<string, string>,
+  auth: MirrorAuth | null
+): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('set_mirror_credential', { host, headers, auth });
+  } catch (error) {
+    console.error('保存镜像认证失败:', error);
+    throw error;
+  }
+};
+
+// 删除主机的认证信息
+export const removeMirrorCredential = async (host: string): Promise<boolean> => {
+  try {
+    return await invoke<boolean>('remove_mirror_credential', { host });
+  } catch (error) {
+    console.error('删除镜像认证失败:', error);
+    throw error;
+  }
+};
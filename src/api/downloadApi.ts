@@ -63,7 +63,7 @@ export const downloadFileToPath = async (
     const result = await invoke<string>("download_file_to_path", {
       url,
       savePath,
-      thread: thread || 8,
+      thread: thread ?? 8,
     });
     
     return result;
@@ -1,4 +1,3 @@
-import axios from 'axios';
 import { invoke } from '@tauri-apps/api/core';
 
 // 插件信息接口
@@ -11,6 +10,22 @@ export interface Plugin {
   file: string;
   link: string;
   id?: string;
+  category?: string;
+  // 插件信息来源：内嵌清单、附属清单或旧格式文件名
+  source?: 'embedded' | 'sidecar' | 'filename';
+}
+
+// 无法读取的插件文件
+export interface PluginLoadError {
+  file: string;
+  error: string;
+}
+
+// 启动盘上的插件文件列表
+export interface PluginFiles {
+  enabled: Plugin[];
+  disabled: Plugin[];
+  errors: PluginLoadError[];
 }
 
 // 插件分类接口
@@ -19,13 +34,6 @@ export interface PluginCategory {
   list: Plugin[];
 }
 
-// 插件API响应接口
-export interface PluginsResponse {
-  code: number;
-  message: string;
-  data: PluginCategory[];
-}
-
 // 生成插件唯一ID
 export const generatePluginId = (name: string, author: string): string => {
   return `${name}|${author}`;
@@ -49,19 +57,53 @@ export const compareVersions = (version1: string, version2: string): number => {
   return 0;
 };
 
-// 获取插件列表（移除缓存）
+// 插件市场数据（由后端获取，失败时使用本地快照）
+export interface PluginCatalog {
+  categories: PluginCategory[];
+  fetchedAt: number;
+  offline: boolean;
+}
+
+// 已安装插件的可用更新
+export interface PluginUpdate {
+  id: string;
+  name: string;
+  file: string;
+  enabled: boolean;
+  currentVersion: string;
+  latestVersion: string;
+  link: string;
+  fileName: string;
+}
+
+export interface PluginUpdateReport {
+  updates: PluginUpdate[];
+  fetchedAt: number;
+  offline: boolean;
+}
+
+// 获取插件列表
 export const getPlugins = async (): Promise<PluginCategory[]> => {
-  const url = 'https://api.cloud-pe.cn/GetPlugins/';
-  
   try {
-    const response = await axios.get<PluginsResponse>(url);
-    return response.data.data;
+    const catalog = await invoke('get_plugin_catalog') as PluginCatalog;
+    if (catalog.offline) {
+      console.warn('插件市场暂时无法访问，使用本地快照');
+    }
+    return catalog.categories;
   } catch (error) {
     console.error('获取插件列表失败:', error);
     throw new Error('获取插件列表失败');
   }
 };
 
+// 检查启动盘上插件的更新，cached 为 true 时只使用本地快照
+export const checkPluginUpdates = async (
+  driveLetter: string,
+  cached: boolean = true
+): Promise<PluginUpdateReport> => {
+  return await invoke('check_plugin_updates', { driveLetter, cached }) as PluginUpdateReport;
+};
+
 // 下载插件
 export const downloadPlugin = async (
   url: string,
@@ -122,14 +164,11 @@ export const updatePlugin = async (
 };
 
 // 获取插件文件列表
-export const getPluginFiles = async (driveLetter: string): Promise<{enabled: Plugin[], disabled: Plugin[]}> => {
+export const getPluginFiles = async (driveLetter: string): Promise<PluginFiles> => {
   try {
     const result = await invoke('get_plugin_files', {
       driveLetter
-    }) as {
-      enabled: Plugin[],
-      disabled: Plugin[]
-    };
+    }) as PluginFiles;
     
     return result;
   } catch (error) {
@@ -153,12 +192,13 @@ export const enablePlugin = async (driveLetter: string, fileName: string): Promi
   }
 };
 
-// 禁用插件
-export const disablePlugin = async (driveLetter: string, fileName: string): Promise<boolean> => {
+// 禁用插件，仍有插件依赖它时需要 force 才能禁用
+export const disablePlugin = async (driveLetter: string, fileName: string, force: boolean = false): Promise<boolean> => {
   try {
     const result = await invoke('disable_plugin', {
       driveLetter,
-      fileName
+      fileName,
+      force
     }) as boolean;
     
     return result;
@@ -167,3 +207,412 @@ export const disablePlugin = async (driveLetter: string, fileName: string): Prom
     throw new Error('禁用插件失败');
   }
 };
+
+// 计划下载的插件
+export interface PlannedInstall {
+  id: string;
+  name: string;
+  version: string;
+  url: string;
+  fileName: string;
+  replaces?: string;
+  sha256?: string;
+}
+
+// 安装、启用或禁用插件时需要执行的全部操作
+export interface PluginPlan {
+  install: PlannedInstall[];
+  enable: string[];
+  disable: string[];
+  problems: string[];
+  // 生成计划时的请求，执行时后端据此重新解析，前端原样传回
+  request?: unknown;
+}
+
+// 插件市场中的全部插件，供依赖解析使用
+export const flattenPluginCategories = (categories: PluginCategory[]): Plugin[] => {
+  return categories.flatMap(category => category.list);
+};
+
+// 计划只包含目标插件本身时不需要额外确认
+export const isSimplePlan = (plan: PluginPlan): boolean => {
+  const steps = plan.install.length + plan.enable.length + plan.disable.length;
+  return plan.problems.length === 0 && steps <= 1;
+};
+
+// 计算安装插件需要的操作
+export const planPluginInstall = async (
+  driveLetter: string,
+  plugin: Plugin,
+  available: Plugin[]
+): Promise<PluginPlan> => {
+  return await invoke('plan_plugin_install', { driveLetter, plugin, available }) as PluginPlan;
+};
+
+// 计算启用插件需要的操作
+export const planPluginEnable = async (
+  driveLetter: string,
+  fileName: string,
+  available: Plugin[]
+): Promise<PluginPlan> => {
+  return await invoke('plan_plugin_enable', { driveLetter, fileName, available }) as PluginPlan;
+};
+
+// 检查禁用插件是否会影响其他插件
+export const planPluginDisable = async (driveLetter: string, fileName: string): Promise<PluginPlan> => {
+  return await invoke('plan_plugin_disable', { driveLetter, fileName }) as PluginPlan;
+};
+
+// 执行插件计划
+export const applyPluginPlan = async (
+  driveLetter: string,
+  plan: PluginPlan,
+  force: boolean,
+  threads: number = 8
+): Promise<boolean> => {
+  try {
+    return await invoke('apply_plugin_plan', { driveLetter, plan, force, threads }) as boolean;
+  } catch (error) {
+    console.error('执行插件计划失败:', error);
+    throw new Error(`执行插件计划失败: ${error}`);
+  }
+};
+
+// 批量安装的进度
+export interface PluginBatchStatus {
+  stage: 'downloading' | 'verifying' | 'swapping' | 'rollingBack' | 'finished' | 'failed';
+  total: number;
+  completed: number;
+  current?: string;
+  progress: number;
+  error?: string;
+}
+
+export interface PluginBatchResult {
+  installed: string[];
+  replaced: string[];
+  downloadedBytes: number;
+}
+
+// 批量安装或更新插件：全部下载校验通过后才替换，失败时恢复原有插件
+export const installPluginBatch = async (
+  driveLetter: string,
+  items: PlannedInstall[],
+  threads: number = 8
+): Promise<PluginBatchResult> => {
+  try {
+    return await invoke('install_plugin_batch', { driveLetter, items, threads }) as PluginBatchResult;
+  } catch (error) {
+    console.error('批量安装插件失败:', error);
+    throw new Error(`批量安装插件失败: ${error}`);
+  }
+};
+
+export const getPluginBatchStatus = async (): Promise<PluginBatchStatus | null> => {
+  return await invoke('get_plugin_batch_status') as PluginBatchStatus | null;
+};
+
+// 把检查到的更新转换为批量安装项
+export const updatesToBatch = (updates: PluginUpdate[]): PlannedInstall[] => {
+  return updates.map(update => ({
+    id: update.id,
+    name: update.name,
+    version: update.latestVersion,
+    url: update.link,
+    fileName: update.fileName,
+    replaces: update.file,
+  }));
+};
+
+// 同一插件的某个版本文件
+export interface DuplicateFile {
+  file: string;
+  version: string;
+  enabled: boolean;
+}
+
+// 同一插件存在多个版本，winner 为保留的文件
+export interface DuplicateGroup {
+  id: string;
+  name: string;
+  winner: string;
+  files: DuplicateFile[];
+}
+
+export interface DuplicateCleanup {
+  kept: string[];
+  disabled: string[];
+  removed: string[];
+  skipped: string[];
+}
+
+// 检测重复的插件版本
+export const findDuplicatePlugins = async (driveLetter: string): Promise<DuplicateGroup[]> => {
+  return await invoke('find_duplicate_plugins', { driveLetter }) as DuplicateGroup[];
+};
+
+// 只保留每个插件的最新版本，其余禁用或删除
+export const cleanupDuplicatePlugins = async (driveLetter: string, remove: boolean = false): Promise<DuplicateCleanup> => {
+  try {
+    return await invoke('cleanup_duplicate_plugins', { driveLetter, remove }) as DuplicateCleanup;
+  } catch (error) {
+    console.error('清理重复插件失败:', error);
+    throw new Error(`清理重复插件失败: ${error}`);
+  }
+};
+
+// 要卸载的插件
+export interface UninstallTarget {
+  id: string;
+  file: string;
+}
+
+export interface UninstalledPlugin {
+  id: string;
+  file: string;
+  size: number;
+  token?: string;
+}
+
+export interface UninstallResult {
+  removed: UninstalledPlugin[];
+  errors: PluginLoadError[];
+  reclaimedBytes: number;
+  recycledBytes: number;
+}
+
+// 卸载插件，默认放入启动盘回收区以便撤销
+export const uninstallPlugins = async (
+  driveLetter: string,
+  targets: UninstallTarget[],
+  recycle: boolean = true
+): Promise<UninstallResult> => {
+  try {
+    return await invoke('uninstall_plugins', { driveLetter, targets, recycle }) as UninstallResult;
+  } catch (error) {
+    console.error('卸载插件失败:', error);
+    throw new Error(`卸载插件失败: ${error}`);
+  }
+};
+
+// 从回收区恢复插件
+export const restorePlugin = async (driveLetter: string, token: string): Promise<string> => {
+  return await invoke('restore_plugin', { driveLetter, token }) as string;
+};
+
+// 格式化字节数
+export const formatBytes = (bytes: number): string => {
+  if (bytes >= 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024 / 1024).toFixed(2)} GB`;
+  return `${(bytes / 1024 / 1024).toFixed(2)} MB`;
+};
+
+// 插件配置：按用途保存的一组需要启用的插件
+export interface PluginProfile {
+  name: string;
+  description: string;
+  plugins: string[];
+  updatedAt: number;
+}
+
+export interface ProfileApplyResult {
+  enabled: string[];
+  disabled: string[];
+  installed: string[];
+  missing: string[];
+}
+
+export const listPluginProfiles = async (driveLetter: string): Promise<PluginProfile[]> => {
+  return await invoke('list_plugin_profiles', { driveLetter }) as PluginProfile[];
+};
+
+// 不传 plugins 时保存当前已启用的插件
+export const savePluginProfile = async (
+  driveLetter: string,
+  name: string,
+  description?: string,
+  plugins?: string[]
+): Promise<PluginProfile> => {
+  return await invoke('save_plugin_profile', { driveLetter, name, description, plugins }) as PluginProfile;
+};
+
+export const deletePluginProfile = async (driveLetter: string, name: string): Promise<boolean> => {
+  return await invoke('delete_plugin_profile', { driveLetter, name }) as boolean;
+};
+
+export const applyPluginProfile = async (
+  driveLetter: string,
+  name: string,
+  threads: number = 8
+): Promise<ProfileApplyResult> => {
+  try {
+    return await invoke('apply_plugin_profile', { driveLetter, name, threads }) as ProfileApplyResult;
+  } catch (error) {
+    console.error('应用插件配置失败:', error);
+    throw new Error(`应用插件配置失败: ${error}`);
+  }
+};
+
+// 可在多个启动盘之间共享的插件清单
+export interface PluginSetEntry {
+  id: string;
+  name: string;
+  version: string;
+  author: string;
+  fileName: string;
+  enabled: boolean;
+  size: number;
+  sha256: string;
+  link?: string;
+}
+
+export interface PluginSetManifest {
+  format: number;
+  exportedAt: number;
+  plugins: PluginSetEntry[];
+}
+
+export interface PluginSetImportResult {
+  installed: string[];
+  enabled: string[];
+  disabled: string[];
+  unchanged: string[];
+  missing: string[];
+  blocked: string[];
+}
+
+export const exportPluginSet = async (driveLetter: string, path: string): Promise<PluginSetManifest> => {
+  try {
+    return await invoke('export_plugin_set', { driveLetter, path }) as PluginSetManifest;
+  } catch (error) {
+    console.error('导出插件清单失败:', error);
+    throw new Error(`导出插件清单失败: ${error}`);
+  }
+};
+
+// exclusive 为 true 时禁用清单以外的插件；allowLocalSources 为 true 时允许从本机或共享路径复制
+export const importPluginSet = async (
+  driveLetter: string,
+  path: string,
+  exclusive: boolean = false,
+  allowLocalSources: boolean = false,
+  threads: number = 8
+): Promise<PluginSetImportResult> => {
+  try {
+    return await invoke('import_plugin_set', { driveLetter, path, exclusive, allowLocalSources, threads }) as PluginSetImportResult;
+  } catch (error) {
+    console.error('导入插件清单失败:', error);
+    throw new Error(`导入插件清单失败: ${error}`);
+  }
+};
+
+// 本机插件库中的插件
+export interface LibraryPlugin {
+  id: string;
+  name: string;
+  version: string;
+  author: string;
+  describe: string;
+  file: string;
+  size: number;
+}
+
+export interface SyncAction {
+  id: string;
+  name: string;
+  kind: 'add' | 'upgrade';
+  version: string;
+  currentVersion?: string;
+  file: string;
+  targetFile: string;
+  replaces?: string;
+  size: number;
+}
+
+export interface PluginSyncReport {
+  actions: SyncAction[];
+  totalBytes: number;
+  applied: boolean;
+}
+
+export const getPluginLibrary = async (): Promise<LibraryPlugin[]> => {
+  return await invoke('get_plugin_library') as LibraryPlugin[];
+};
+
+// 不传 items 时从插件市场更新插件库中已有的插件
+export const downloadToPluginLibrary = async (
+  items?: PlannedInstall[],
+  threads: number = 8
+): Promise<string[]> => {
+  try {
+    return await invoke('download_to_plugin_library', { items, threads }) as string[];
+  } catch (error) {
+    console.error('下载插件到插件库失败:', error);
+    throw new Error(`下载插件到插件库失败: ${error}`);
+  }
+};
+
+export const addDrivePluginsToLibrary = async (
+  driveLetter: string,
+  dryRun: boolean = false
+): Promise<PluginSyncReport> => {
+  return await invoke('add_drive_plugins_to_library', { driveLetter, dryRun }) as PluginSyncReport;
+};
+
+// dryRun 为 true 时只返回需要复制的插件，不改动启动盘
+export const syncPluginLibrary = async (
+  driveLetter: string,
+  dryRun: boolean = false
+): Promise<PluginSyncReport> => {
+  try {
+    return await invoke('sync_plugin_library', { driveLetter, dryRun }) as PluginSyncReport;
+  } catch (error) {
+    console.error('同步插件库失败:', error);
+    throw new Error(`同步插件库失败: ${error}`);
+  }
+};
+
+export const removeLibraryPlugin = async (fileName: string): Promise<boolean> => {
+  return await invoke('remove_library_plugin', { fileName }) as boolean;
+};
+
+// 插件包中的文件
+export interface PackageEntry {
+  path: string;
+  size: number;
+  compressedSize?: number;
+  isDirectory: boolean;
+}
+
+// 插件包检查结果，problems 为空表示未发现问题，warnings 为无法检查但不影响使用的情况
+export interface PluginInspection {
+  file: string;
+  format: '7z' | 'zip' | 'unknown';
+  fileSize: number;
+  unpackedSize: number;
+  entries: PackageEntry[];
+  manifest?: Record<string, unknown>;
+  tested: boolean;
+  problems: string[];
+  warnings: string[];
+}
+
+export interface DamagedPlugin {
+  file: string;
+  problems: string[];
+}
+
+// test 为 true 时解压全部内容做完整性测试，耗时较长
+export const inspectPlugin = async (
+  driveLetter: string,
+  fileName: string,
+  test: boolean = false
+): Promise<PluginInspection> => {
+  return await invoke('inspect_plugin', { driveLetter, fileName, test }) as PluginInspection;
+};
+
+export const findDamagedPlugins = async (
+  driveLetter: string,
+  test: boolean = false
+): Promise<DamagedPlugin[]> => {
+  return await invoke('find_damaged_plugins', { driveLetter, test }) as DamagedPlugin[];
+};
@@ -0,0 +1,33 @@
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// 重复启动时转发给已运行实例的参数
+export interface ForwardedArgs {
+  args: string[];
+  cwd: string;
+}
+
+// 打开指定页面的链接，如 cloud-pe-one://manage-plugins
+const DEEP_LINK_PREFIX = 'cloud-pe-one://';
+
+// 监听重复启动时转发的参数
+export const onForwardedArgs = async (
+  callback: (payload: ForwardedArgs) => void
+): Promise<UnlistenFn> => {
+  return await listen<ForwardedArgs>('single-instance://args', (event) => {
+    callback(event.payload);
+  });
+};
+
+// 从转发的参数中解析要打开的页面：页面链接直接对应页面，插件文件打开插件管理
+export const pageFromArgs = (args: string[]): string | null => {
+  for (const arg of args) {
+    if (arg.toLowerCase().startsWith(DEEP_LINK_PREFIX)) {
+      const page = arg.slice(DEEP_LINK_PREFIX.length).replace(/\/+$/, '');
+      return page || 'home';
+    }
+    if (/\.(ce|cbk)$/i.test(arg)) {
+      return 'manage-plugins';
+    }
+  }
+  return null;
+};
@@ -1,63 +1,78 @@
-import { unifiedApiService, UnifiedApiResponse } from './unifiedApi';
+import { invoke } from '@tauri-apps/api/core';
 
-// 使用统一API的响应类型
-export type UpdateInfo = UnifiedApiResponse;
+// 更新通道：正式版、测试版、固定版本
+export type UpdateChannel = 'stable' | 'beta' | 'pinned';
 
-// 获取更新信息的API
-export const getUpdateInfo = async (): Promise<UpdateInfo> => {
+// 后端根据通道与管理员策略给出的更新决定
+export interface AppUpdateDecision {
+  available: boolean;
+  currentVersion: string;
+  version: string;
+  updateLog: string;
+  downloadLink: string;
+  appExecutableName: string;
+  canSkip: boolean;
+  channel: UpdateChannel;
+  frozen: boolean;
+  managed: boolean;
+  message: string | null;
+}
+
+export interface UpdateSettings {
+  channel: UpdateChannel;
+  pinnedVersion: string | null;
+}
+
+// 管理员更新策略（只读）
+export interface UpdatePolicy {
+  frozen: boolean;
+  channel: UpdateChannel | null;
+  pinnedVersion: string | null;
+  pinnedUrl: string | null;
+  pinnedAppName: string | null;
+  message: string | null;
+}
+
+export interface UpdateSettingsView {
+  settings: UpdateSettings;
+  policy: UpdatePolicy | null;
+}
+
+// 检查应用更新
+export const checkAppUpdate = async (): Promise<AppUpdateDecision> => {
   try {
-    return await unifiedApiService.getData();
+    return await invoke<AppUpdateDecision>('check_app_update');
   } catch (error) {
     console.error('获取更新信息失败:', error);
     throw new Error('获取更新信息失败');
   }
 };
 
-// 比较版本号，判断是否需要更新
-export const checkNeedsUpdate = (currentVersion: string, latestVersion: string): boolean => {
-  // 移除版本号前的'v'字符
-  const current = currentVersion.replace('v', '');
-  const latest = latestVersion.replace('v', '');
-  
-  // 将版本号拆分为数字数组
-  const currentParts = current.split('.').map(Number);
-  const latestParts = latest.split('.').map(Number);
-  
-  // 比较版本号的每一部分
-  for (let i = 0; i < Math.max(currentParts.length, latestParts.length); i++) {
-    const currentPart = currentParts[i] || 0;
-    const latestPart = latestParts[i] || 0;
-    
-    if (latestPart > currentPart) {
-      return true; // 需要更新
-    } else if (latestPart < currentPart) {
-      return false; // 不需要更新
-    }
-  }
-  
-  return false; // 版本相同，不需要更新
+// 获取更新设置及管理员策略
+export const getUpdateSettings = async (): Promise<UpdateSettingsView> => {
+  return await invoke<UpdateSettingsView>('get_update_settings');
 };
 
-// 检查更新是否可跳过
-export const isUpdateSkippable = (updateInfo: UpdateInfo): boolean => {
-  const latestVersion = updateInfo.hub_new.hub_ver;
-  const canSkip = updateInfo.hub_new.log[latestVersion]?.can_skip;
-  
-  return canSkip === 'true';
+// 保存更新设置
+export const setUpdateSettings = async (
+  channel: UpdateChannel,
+  pinnedVersion?: string | null
+): Promise<UpdateSettingsView> => {
+  try {
+    return await invoke<UpdateSettingsView>('set_update_settings', { channel, pinnedVersion });
+  } catch (error) {
+    console.error('保存更新设置失败:', error);
+    throw error;
+  }
 };
 
-// 获取更新日志
-export const getUpdateLog = (updateInfo: UpdateInfo): string => {
-  const latestVersion = updateInfo.hub_new.hub_ver;
-  return updateInfo.hub_new.log[latestVersion]?.log || '';
-};
+// 本地更新包摘要
+export interface LocalPackageSummary {
+  version: string;
+  currentVersion: string;
+}
 
-// 获取更新下载链接
-export const getUpdateLink = (updateInfo: UpdateInfo): string => {
-  return updateInfo.hub_new.hub_updata_link;
+// 安装前检查本地更新包的签名和版本
+export const inspectUpdatePackage = async (filePath: string): Promise<LocalPackageSummary> => {
+  return await invoke<LocalPackageSummary>('inspect_update_package', { filePath });
 };
-
-// 获取应用程序可执行文件名
-export const getAppExecutableName = (updateInfo: UpdateInfo): string => {
-  return updateInfo.hub_new.app_name_exe;
-};
\ No newline at end of file
@@ -1,8 +1,9 @@
 /* eslint-disable @typescript-eslint/no-unused-vars */
 import "./../App.css";
-import React, { useState, useEffect } from 'react';
+import React, { useState, useEffect, useRef } from 'react';
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
+import { Badge } from "@/components/ui/badge";
 import { toastManager } from "@/components/ui/toast";
 import {
   Minus,
@@ -15,10 +16,13 @@ import {
   Settings,
   ChevronLeft,
   ChevronRight,
-  ChevronDown
+  ChevronDown,
+  Plug
 } from "lucide-react";
 
 import { useAppContext } from '../utils/AppContext';
+import { getAutomationStatus, onAutomationStatusChange } from '../api/automationApi';
+import { onForwardedArgs, pageFromArgs } from '../api/singleInstanceApi';
 
 import { Window } from '@tauri-apps/api/window';
 const appWindow = new Window('main');
@@ -56,6 +60,7 @@ const AppLayout: React.FC<AppLayoutProps> = ({ children, currentPage, onNavigate
   const [selectedKeys, setSelectedKeys] = useState<string[]>([currentPage]);
   const [expandedMenus, setExpandedMenus] = useState<string[]>(['install', 'plugins']);
   const [hoveredMenu, setHoveredMenu] = useState<string | null>(null);
+  const [automationEnabled, setAutomationEnabled] = useState<boolean>(false);
 
   const navItems: NavItem[] = [
     { itemKey: 'home', text: '首页', icon: <Home className="h-4 w-4" /> },
@@ -142,6 +147,19 @@ const AppLayout: React.FC<AppLayoutProps> = ({ children, currentPage, onNavigate
     onNavigate(itemKey);
   };
 
+  // 重复启动时按转发的参数切换页面，仍然遵守上面的切换限制
+  const handleForwardedArgs = (args: string[]) => {
+    const page = pageFromArgs(args);
+    const known = navItems.some(item =>
+      item.itemKey === page || item.items?.some(sub => sub.itemKey === page)
+    );
+    if (page && known) {
+      handleNavSelect(page);
+    }
+  };
+  const forwardedArgsRef = useRef(handleForwardedArgs);
+  forwardedArgsRef.current = handleForwardedArgs;
+
   const toggleMenu = (itemKey: string) => {
     setExpandedMenus(prev =>
       prev.includes(itemKey)
@@ -211,6 +229,31 @@ const AppLayout: React.FC<AppLayoutProps> = ({ children, currentPage, onNavigate
     setSelectedKeys([currentPage]);
   }, [currentPage]);
 
+  // 自动化接口开启时在标题栏显示提示
+  useEffect(() => {
+    getAutomationStatus()
+      .then((status) => setAutomationEnabled(status.enabled))
+      .catch(() => setAutomationEnabled(false));
+
+    const unlistenPromise = onAutomationStatusChange((status) => {
+      setAutomationEnabled(status.enabled);
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlistenPromise = onForwardedArgs(({ args }) => {
+      forwardedArgsRef.current(args);
+    });
+
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
   const shouldShowSearchBox = !isLoadingPlugins && !pluginsError && pluginCategories.length > 0 && isNetworkConnected;
 
   const isItemSelected = (itemKey: string, items?: { itemKey: string }[]) => {
@@ -262,6 +305,12 @@ const AppLayout: React.FC<AppLayoutProps> = ({ children, currentPage, onNavigate
 
         {/* 右侧窗口控制按钮 */}
         <div className="flex items-center gap-2">
+          {automationEnabled && (
+            <Badge variant="warning" title="本地自动化接口已开启，可在设置中关闭">
+              <Plug />
+              自动化接口已开启
+            </Badge>
+          )}
           <Button
             variant="ghost"
             size="icon"
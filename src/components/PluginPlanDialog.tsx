@@ -0,0 +1,76 @@
+import React from 'react';
+import { Button } from '@/components/ui/button';
+import {
+  AlertDialog,
+  AlertDialogPopup,
+  AlertDialogHeader,
+  AlertDialogTitle,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogClose,
+} from '@/components/ui/alert-dialog';
+import { PluginPlan } from '../api/pluginsApi';
+
+interface PluginPlanDialogProps {
+  open: boolean;
+  title: string;
+  plan: PluginPlan | null;
+  onConfirm: (force: boolean) => void;
+  onCancel: () => void;
+}
+
+// 去掉扩展名，只显示插件文件的主体部分
+const displayFileName = (file: string) => file.replace(/\.(ce|CBK)$/i, '');
+
+// 安装、启用或禁用插件前展示完整的操作计划
+const PluginPlanDialog: React.FC<PluginPlanDialogProps> = ({ open, title, plan, onConfirm, onCancel }) => {
+  const hasProblems = !!plan && plan.problems.length > 0;
+
+  const sections = plan
+    ? [
+        { label: '将下载', items: plan.install.map(i => `${i.name} ${i.version}${i.replaces ? '（替换旧版本）' : ''}`) },
+        { label: '将启用', items: plan.enable.map(displayFileName) },
+        { label: '将禁用', items: plan.disable.map(displayFileName) },
+      ].filter(section => section.items.length > 0)
+    : [];
+
+  return (
+    <AlertDialog open={open} onOpenChange={(value) => { if (!value) onCancel(); }}>
+      <AlertDialogPopup>
+        <AlertDialogHeader>
+          <AlertDialogTitle>{title}</AlertDialogTitle>
+          <AlertDialogDescription>
+            {hasProblems ? '检测到以下问题，继续操作可能导致插件无法正常使用：' : '该操作还会同时处理以下插件：'}
+          </AlertDialogDescription>
+        </AlertDialogHeader>
+        <div className="space-y-3 text-sm max-h-64 overflow-y-auto">
+          {hasProblems && (
+            <ul className="list-disc pl-5 text-destructive">
+              {plan!.problems.map((problem, index) => <li key={index}>{problem}</li>)}
+            </ul>
+          )}
+          {sections.map(section => (
+            <div key={section.label}>
+              <div className="font-medium">{section.label}</div>
+              <ul className="list-disc pl-5 text-muted-foreground">
+                {section.items.map((item, index) => <li key={index}>{item}</li>)}
+              </ul>
+            </div>
+          ))}
+        </div>
+        <AlertDialogFooter>
+          <AlertDialogClose>
+            <Button variant="outline" onClick={onCancel}>
+              取消
+            </Button>
+          </AlertDialogClose>
+          <Button variant={hasProblems ? 'destructive' : 'default'} onClick={() => onConfirm(hasProblems)}>
+            {hasProblems ? '仍然继续' : '继续'}
+          </Button>
+        </AlertDialogFooter>
+      </AlertDialogPopup>
+    </AlertDialog>
+  );
+};
+
+export default PluginPlanDialog;
@@ -1,8 +1,9 @@
-import React, { useState } from 'react';
+import React, { useState, useEffect } from 'react';
 import { Dialog, DialogPopup, DialogHeader, DialogTitle, DialogFooter } from '@/components/ui/dialog';
 import { Button } from '@/components/ui/button';
 import { AlertCircle, PartyPopper, Frown } from 'lucide-react';
 import { invoke } from '../utils/tauriApiWrapper';
+import { listen } from '@tauri-apps/api/event';
 import ReactMarkdown from 'react-markdown';
 
 interface UpdateNotificationProps {
@@ -28,6 +29,17 @@ const UpdateNotification: React.FC<UpdateNotificationProps> = ({
   const [downloadProgress, setDownloadProgress] = useState<number>(0);
   const [, setDownloadSpeed] = useState<string>('0.00');
   const [error, setError] = useState<string | null>(null);
+  // 解压进度（下载完成后解压更新包时更新）
+  const [extractProgress, setExtractProgress] = useState<number | null>(null);
+
+  useEffect(() => {
+    const unlistenPromise = listen<{ percent: number }>('update://extract-progress', (event) => {
+      setExtractProgress(Math.floor(event.payload.percent));
+    });
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
 
   // 处理更新按钮点击
   const handleUpdate = async () => {
@@ -35,8 +47,8 @@ const UpdateNotification: React.FC<UpdateNotificationProps> = ({
       setDownloading(true);
       setError(null);
 
-      // 调用Rust下载函数，并将返回值保存到script_path变量
-      const script_path: string = await invoke('download_update', {
+      // 调用Rust下载函数，并将返回值保存到plan_path变量
+      const plan_path: string = await invoke('download_update', {
         url: downloadLink,
         appName: appExecutableName
       });
@@ -53,9 +65,9 @@ const UpdateNotification: React.FC<UpdateNotificationProps> = ({
             // 如果下载完成，清除定时器
             if (progress === 100) {
               clearInterval(progressInterval);
-              // 下载完成后，调用安装函数，传入scriptPath参数
+              // 下载完成后，调用安装函数，传入planPath参数
               await invoke('install_update', {
-                scriptPath: script_path
+                planPath: plan_path
               });
               // 安装后应用会重启，不需要额外处理
             }
@@ -134,7 +146,11 @@ const UpdateNotification: React.FC<UpdateNotificationProps> = ({
             onClick={handleUpdate}
             disabled={downloading}
           >
-            {downloading ? `正在更新 (${downloadProgress}%)` : '立即升级'}
+            {downloading
+              ? extractProgress !== null
+                ? `正在解压 (${extractProgress}%)`
+                : `正在更新 (${downloadProgress}%)`
+              : '立即升级'}
           </Button>
         </DialogFooter>
       </DialogPopup>
@@ -1,8 +1,5 @@
 import { useState, useEffect } from 'react';
-import { getUpdateInfo, checkNeedsUpdate, isUpdateSkippable, getUpdateLog, getUpdateLink, getAppExecutableName } from '../api/updateApi';
-
-// 当前应用版本
-const CURRENT_VERSION = 'v1.6';
+import { checkAppUpdate } from '../api/updateApi';
 
 interface UseUpdateCheckResult {
   isUpdateAvailable: boolean;
@@ -29,23 +26,17 @@ export const useUpdateCheck = (): UseUpdateCheckResult => {
         setIsCheckingUpdate(true);
         setError(null);
 
-        // 获取更新信息
-        const info = await getUpdateInfo();
-        
-        // 获取最新版本号
-        const latestVersion = info.hub_new.hub_ver;
+        // 由后端根据更新通道和管理员策略决定是否需要更新
+        const decision = await checkAppUpdate();
         
-        // 检查是否需要更新
-        const needsUpdate = checkNeedsUpdate(CURRENT_VERSION, latestVersion);
-        
-        if (needsUpdate) {
+        if (decision.available) {
           // 设置更新信息
           setUpdateInfo({
-            version: latestVersion,
-            updateLog: getUpdateLog(info),
-            downloadLink: getUpdateLink(info),
-            appExecutableName: getAppExecutableName(info),
-            canSkip: isUpdateSkippable(info)
+            version: decision.version,
+            updateLog: decision.updateLog,
+            downloadLink: decision.downloadLink,
+            appExecutableName: decision.appExecutableName,
+            canSkip: decision.canSkip
           });
           setIsUpdateAvailable(true);
         }
@@ -67,4 +58,3 @@ export const useUpdateCheck = (): UseUpdateCheckResult => {
     error
   };
 };
-
@@ -1,23 +1,58 @@
 import React, { useState, useEffect } from 'react';
-import { AlertCircle, Info, ChevronDown } from 'lucide-react';
+import { AlertCircle, AlertTriangle, Info, ChevronDown } from 'lucide-react';
 import { useAppContext } from '../utils/AppContext';
-import { getPluginFiles, enablePlugin, disablePlugin, updatePlugin, generatePluginId, compareVersions, Plugin } from '../api/pluginsApi';
+import { confirmDialog, savePluginSetDialog, selectPluginSetFile } from '../utils/tauriApiWrapper';
+import {
+  getPluginFiles, enablePlugin, disablePlugin, updatePlugin, generatePluginId, Plugin,
+  PluginPlan, planPluginEnable, planPluginDisable, applyPluginPlan, isSimplePlan, flattenPluginCategories,
+  DuplicateGroup, findDuplicatePlugins, cleanupDuplicatePlugins,
+  UninstallResult, uninstallPlugins, restorePlugin, formatBytes,
+  PluginUpdate, checkPluginUpdates, PluginBatchStatus, installPluginBatch, getPluginBatchStatus, updatesToBatch,
+  PluginProfile, listPluginProfiles, savePluginProfile, deletePluginProfile, applyPluginProfile,
+  exportPluginSet, importPluginSet,
+  LibraryPlugin, PluginSyncReport, getPluginLibrary, downloadToPluginLibrary, addDrivePluginsToLibrary, syncPluginLibrary,
+  DamagedPlugin, inspectPlugin, findDamagedPlugins,
+} from '../api/pluginsApi';
 import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
 import { Card, CardPanel } from '@/components/ui/card';
 import { Spinner } from '@/components/ui/spinner';
 import { Badge } from '@/components/ui/badge';
+import { Alert, AlertDescription, AlertTitle } from '@/components/ui/alert';
+import { Progress, ProgressTrack, ProgressIndicator } from '@/components/ui/progress';
 import { toastManager } from '@/components/ui/toast';
+import PluginPlanDialog from '@/components/PluginPlanDialog';
+
+// 等待用户确认的插件计划
+interface PendingPlan {
+  title: string;
+  plan: PluginPlan;
+  run: (force: boolean) => Promise<void>;
+}
 
 const PluginsManagePage: React.FC = () => {
-  const { bootDrive, pluginListRefreshTrigger, pluginCategories, isNetworkConnected, config, triggerPluginListRefresh } = useAppContext();
+  const { bootDrive, pluginListRefreshTrigger, pluginCategories, config, triggerPluginListRefresh } = useAppContext();
   const [enabledPlugins, setEnabledPlugins] = useState<Plugin[]>([]);
   const [disabledPlugins, setDisabledPlugins] = useState<Plugin[]>([]);
   const [loading, setLoading] = useState<boolean>(true);
   const [error, setError] = useState<string | null>(null);
   const [processingPlugins, setProcessingPlugins] = useState<Record<string, boolean>>({});
-  const [updatablePlugins, setUpdatablePlugins] = useState<Set<string>>(new Set());
+  const [pluginUpdates, setPluginUpdates] = useState<Record<string, PluginUpdate>>({});
   const [recentlyUpdatedPlugins, setRecentlyUpdatedPlugins] = useState<Set<string>>(new Set());
-  const [expandedSections, setExpandedSections] = useState<Record<string, boolean>>({ enabled: true, disabled: true });
+  const [expandedSections, setExpandedSections] = useState<Record<string, boolean>>({ profiles: false, library: false, enabled: true, disabled: true });
+  const [pendingPlan, setPendingPlan] = useState<PendingPlan | null>(null);
+  const [duplicates, setDuplicates] = useState<DuplicateGroup[]>([]);
+  const [cleaningDuplicates, setCleaningDuplicates] = useState<boolean>(false);
+  const [batchStatus, setBatchStatus] = useState<PluginBatchStatus | null>(null);
+  const [profiles, setProfiles] = useState<PluginProfile[]>([]);
+  const [profileName, setProfileName] = useState<string>('');
+  const [applyingProfile, setApplyingProfile] = useState<string | null>(null);
+  const [transferringSet, setTransferringSet] = useState<boolean>(false);
+  const [library, setLibrary] = useState<LibraryPlugin[]>([]);
+  const [syncPreview, setSyncPreview] = useState<PluginSyncReport | null>(null);
+  const [libraryBusy, setLibraryBusy] = useState<boolean>(false);
+  const [damagedPlugins, setDamagedPlugins] = useState<Record<string, string[]>>({});
+  const [inspectingPlugins, setInspectingPlugins] = useState<Record<string, boolean>>({});
 
   const toggleSection = (key: string) => {
     setExpandedSections(prev => ({ ...prev, [key]: !prev[key] }));
@@ -35,13 +70,44 @@ const PluginsManagePage: React.FC = () => {
       setLoading(true);
       setError(null);
 
-      const { enabled, disabled } = await getPluginFiles(bootDrive.letter);
+      const { enabled, disabled, errors } = await getPluginFiles(bootDrive.letter);
       setEnabledPlugins(enabled);
       setDisabledPlugins(disabled);
 
-      // 如果联网且有插件市场数据，检查更新
-      if (isNetworkConnected && pluginCategories.length > 0 && enabled.length > 0) {
-        checkForUpdates(enabled);
+      // 同一插件的多个版本会被 PE 同时加载，需要提示用户清理
+      const duplicateGroups = await findDuplicatePlugins(bootDrive.letter).catch(err => {
+        console.error('检测重复插件失败:', err);
+        return [] as DuplicateGroup[];
+      });
+      setDuplicates(duplicateGroups);
+
+      // 快速检查插件包结构，损坏的插件在进入 PE 前就提示出来
+      const damaged = await findDamagedPlugins(bootDrive.letter).catch(err => {
+        console.error('检查插件包失败:', err);
+        return [] as DamagedPlugin[];
+      });
+      setDamagedPlugins(Object.fromEntries(damaged.map(d => [d.file, d.problems])));
+
+      const savedProfiles = await listPluginProfiles(bootDrive.letter).catch(err => {
+        console.error('读取插件配置失败:', err);
+        return [] as PluginProfile[];
+      });
+      setProfiles(savedProfiles);
+
+      const libraryPlugins = await getPluginLibrary().catch(err => {
+        console.error('读取插件库失败:', err);
+        return [] as LibraryPlugin[];
+      });
+      setLibrary(libraryPlugins);
+
+      // 无法识别的插件文件不再静默忽略
+      if (errors.length > 0) {
+        console.warn('部分插件无法读取:', errors);
+        toastManager.add({
+          title: '部分插件无法识别',
+          description: errors.map(e => `${e.file}: ${e.error}`).join('\n'),
+          type: 'warning',
+        });
       }
     } catch (err) {
       console.error('加载插件文件失败:', err);
@@ -51,40 +117,31 @@ const PluginsManagePage: React.FC = () => {
     }
   };
 
-  // 检查插件更新
-  const checkForUpdates = (localPlugins: Plugin[]) => {
-    const updatable = new Set<string>();
-
-    // 创建市场插件映射
-    const marketPluginsMap = new Map<string, Plugin>();
-    pluginCategories.forEach(category => {
-      category.list.forEach(plugin => {
-        const id = generatePluginId(plugin.name, plugin.author);
-        marketPluginsMap.set(id, plugin);
-      });
-    });
-
-    // 检查每个本地已启用插件
-    localPlugins.forEach(localPlugin => {
-      if (localPlugin.id) {
-        const marketPlugin = marketPluginsMap.get(localPlugin.id);
-        if (marketPlugin) {
-          const comparison = compareVersions(localPlugin.version, marketPlugin.version);
-          if (comparison < 0) {
-            updatable.add(localPlugin.id);
-          }
-        }
-      }
-    });
+  // 检查插件更新：使用后端保存的插件市场快照，离线时也能显示更新标记
+  const checkForUpdates = async () => {
+    if (!bootDrive) return;
 
-    setUpdatablePlugins(updatable);
+    try {
+      const report = await checkPluginUpdates(bootDrive.letter);
+      const updates: Record<string, PluginUpdate> = {};
+      report.updates
+        .filter(update => update.enabled)
+        .forEach(update => {
+          updates[update.id] = update;
+        });
+      setPluginUpdates(updates);
+    } catch (err) {
+      // 尚未获取过插件市场数据时没有快照，不显示更新
+      console.warn('检查插件更新失败:', err);
+      setPluginUpdates({});
+    }
   };
 
   // 对插件进行排序：有更新的在最上面，刚更新的在中间，其他的在最下面
   const sortPluginsByUpdate = (plugins: Plugin[]): Plugin[] => {
     return [...plugins].sort((a, b) => {
-      const aHasUpdate = a.id && updatablePlugins.has(a.id);
-      const bHasUpdate = b.id && updatablePlugins.has(b.id);
+      const aHasUpdate = a.id && !!pluginUpdates[a.id];
+      const bHasUpdate = b.id && !!pluginUpdates[b.id];
       const aRecentlyUpdated = a.id && recentlyUpdatedPlugins.has(a.id);
       const bRecentlyUpdated = b.id && recentlyUpdatedPlugins.has(b.id);
 
@@ -106,14 +163,14 @@ const PluginsManagePage: React.FC = () => {
     fetchPluginFiles();
   }, [bootDrive, pluginListRefreshTrigger]);
 
-  // 当网络状态或插件市场数据变化时，重新检查更新
+  // 插件列表或插件市场数据变化时，重新检查更新
   useEffect(() => {
-    if (isNetworkConnected && pluginCategories.length > 0 && enabledPlugins.length > 0) {
-      checkForUpdates(enabledPlugins);
+    if (enabledPlugins.length > 0) {
+      checkForUpdates();
     } else {
-      setUpdatablePlugins(new Set());
+      setPluginUpdates({});
     }
-  }, [isNetworkConnected, pluginCategories, enabledPlugins]);
+  }, [bootDrive, pluginCategories, enabledPlugins]);
 
   // 更新插件文件名后缀的辅助函数
   const updatePluginFileName = (plugin: Plugin, newExtension: string): Plugin => {
@@ -132,6 +189,25 @@ const PluginsManagePage: React.FC = () => {
       console.log('启用插件:', plugin.file);
       setProcessingPlugins(prev => ({ ...prev, [plugin.file]: true }));
 
+      // 需要同时处理依赖或冲突时，先让用户确认完整计划
+      const plan = await planPluginEnable(bootDrive.letter, plugin.file, flattenPluginCategories(pluginCategories));
+      if (!isSimplePlan(plan)) {
+        setPendingPlan({
+          title: `启用插件 ${plugin.name}`,
+          plan,
+          run: async (force) => {
+            await applyPluginPlan(bootDrive.letter, plan, force, config.downloadThreads);
+            toastManager.add({
+              title: '成功',
+              description: `插件 ${plugin.name} 及相关插件已处理完成`,
+              type: 'success',
+            });
+            await fetchPluginFiles();
+          },
+        });
+        return;
+      }
+
       const success = await enablePlugin(bootDrive.letter, plugin.file);
 
       if (success) {
@@ -170,6 +246,25 @@ const PluginsManagePage: React.FC = () => {
       console.log('禁用插件:', plugin.file);
       setProcessingPlugins(prev => ({ ...prev, [plugin.file]: true }));
 
+      // 仍有插件依赖它时需要用户确认后强制禁用
+      const plan = await planPluginDisable(bootDrive.letter, plugin.file);
+      if (!isSimplePlan(plan)) {
+        setPendingPlan({
+          title: `禁用插件 ${plugin.name}`,
+          plan,
+          run: async (force) => {
+            await disablePlugin(bootDrive.letter, plugin.file, force);
+            toastManager.add({
+              title: '成功',
+              description: `插件 ${plugin.name} 已禁用`,
+              type: 'success',
+            });
+            await fetchPluginFiles();
+          },
+        });
+        return;
+      }
+
       const success = await disablePlugin(bootDrive.letter, plugin.file);
 
       if (success) {
@@ -204,17 +299,10 @@ const PluginsManagePage: React.FC = () => {
   const handleUpdatePlugin = async (plugin: Plugin) => {
     if (!bootDrive || !plugin.id) return;
 
-    // 从插件市场找到对应的新版本插件
-    let marketPlugin: Plugin | null = null;
-    for (const category of pluginCategories) {
-      const found = category.list.find(p => generatePluginId(p.name, p.author) === plugin.id);
-      if (found) {
-        marketPlugin = found;
-        break;
-      }
-    }
+    // 插件市场中对应的新版本
+    const update = pluginUpdates[plugin.id];
 
-    if (!marketPlugin) {
+    if (!update) {
       toastManager.add({
         title: '错误',
         description: '未找到插件市场中的对应版本',
@@ -224,21 +312,19 @@ const PluginsManagePage: React.FC = () => {
     }
 
     try {
-      console.log('更新插件:', plugin.file, '到版本:', marketPlugin.version);
+      console.log('更新插件:', plugin.file, '到版本:', update.latestVersion);
       setProcessingPlugins(prev => ({ ...prev, [plugin.file]: true }));
 
-      const newFileName = `${marketPlugin.name}_${marketPlugin.version}_${marketPlugin.author}_${marketPlugin.describe}.ce`;
-
       await updatePlugin(
-        marketPlugin.link,
+        update.link,
         plugin.file,
-        newFileName,
+        update.fileName,
         bootDrive.letter,
         config.downloadThreads
       );
 
       // 标记为刚更新的插件
-      const newPluginId = generatePluginId(marketPlugin.name, marketPlugin.author);
+      const newPluginId = update.id;
       setRecentlyUpdatedPlugins(prev => new Set(prev).add(newPluginId));
 
       // 5秒后移除"刚更新"标记
@@ -273,7 +359,7 @@ const PluginsManagePage: React.FC = () => {
   // 渲染插件卡片
   const renderPluginCard = (plugin: Plugin, isEnabled: boolean) => {
     const isProcessing = processingPlugins[plugin.file];
-    const canUpdate = plugin.id && updatablePlugins.has(plugin.id);
+    const canUpdate = plugin.id && !!pluginUpdates[plugin.id];
 
     return (
       <Card key={plugin.file} className="mb-4 shadow-sm">
@@ -283,6 +369,7 @@ const PluginsManagePage: React.FC = () => {
               <div className="flex items-center mb-2">
                 <h5 className="text-base font-semibold mr-2">{plugin.name}</h5>
                 {canUpdate && <Badge variant="warning">有更新</Badge>}
+                {damagedPlugins[plugin.file] && <Badge variant="error">已损坏</Badge>}
               </div>
               <p className="text-sm text-muted-foreground mb-2">{plugin.describe}</p>
               <div className="flex flex-wrap gap-4">
@@ -320,6 +407,19 @@ const PluginsManagePage: React.FC = () => {
                       启用
                     </Button>
                   )}
+                  <Button
+                    variant="outline"
+                    onClick={() => handleUninstallPlugins([plugin])}
+                  >
+                    卸载
+                  </Button>
+                  <Button
+                    variant="outline"
+                    disabled={inspectingPlugins[plugin.file]}
+                    onClick={() => handleInspectPlugin(plugin)}
+                  >
+                    {inspectingPlugins[plugin.file] ? <Spinner className="size-4" /> : '检查'}
+                  </Button>
                 </>
               )}
             </div>
@@ -329,6 +429,567 @@ const PluginsManagePage: React.FC = () => {
     );
   };
 
+  // 清理重复插件：保留最新版本，其余禁用或删除
+  const handleCleanupDuplicates = async (remove: boolean) => {
+    if (!bootDrive) return;
+
+    try {
+      setCleaningDuplicates(true);
+      const result = await cleanupDuplicatePlugins(bootDrive.letter, remove);
+      toastManager.add({
+        title: '成功',
+        description: `已保留 ${result.kept.length} 个最新版本，禁用 ${result.disabled.length} 个、删除 ${result.removed.length} 个旧版本`
+          + (result.removed.length > 0 ? '（可在回收区撤销）' : '')
+          + (result.skipped.length > 0 ? `，${result.skipped.length} 个正在下载的文件未处理` : ''),
+        type: result.skipped.length > 0 ? 'warning' : 'success',
+      });
+      await fetchPluginFiles();
+    } catch (err) {
+      console.error('清理重复插件失败:', err);
+      toastManager.add({
+        title: '错误',
+        description: '清理重复插件失败',
+        type: 'error',
+      });
+    } finally {
+      setCleaningDuplicates(false);
+    }
+  };
+
+  // 渲染重复插件提示
+  // 一次性更新全部插件，任何一个失败都不会改动启动盘上的插件
+  const handleUpdateAll = async () => {
+    if (!bootDrive) return;
+    const updates = Object.values(pluginUpdates);
+    if (updates.length === 0) return;
+
+    setBatchStatus({ stage: 'downloading', total: updates.length, completed: 0, progress: 0 });
+    const intervalId = window.setInterval(async () => {
+      const status = await getPluginBatchStatus().catch(() => null);
+      if (status) setBatchStatus(status);
+    }, 500);
+
+    try {
+      const result = await installPluginBatch(bootDrive.letter, updatesToBatch(updates), config.downloadThreads);
+      triggerPluginListRefresh();
+      toastManager.add({
+        title: '更新成功',
+        description: `已更新 ${result.installed.length} 个插件`,
+        type: 'success',
+      });
+    } catch (err) {
+      console.error('批量更新插件失败:', err);
+      toastManager.add({
+        title: '更新失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      window.clearInterval(intervalId);
+      setBatchStatus(null);
+    }
+  };
+
+  const batchStageText: Record<PluginBatchStatus['stage'], string> = {
+    downloading: '正在下载',
+    verifying: '正在校验',
+    swapping: '正在替换',
+    rollingBack: '正在恢复',
+    finished: '已完成',
+    failed: '失败',
+  };
+
+  const renderUpdateAll = () => {
+    const count = Object.keys(pluginUpdates).length;
+    if (count < 2 && !batchStatus) return null;
+
+    return (
+      <div className="mb-4">
+        {batchStatus ? (
+          <Progress value={batchStatus.progress}>
+            <div className="flex justify-between mb-2">
+              <span className="text-sm font-medium">
+                {batchStageText[batchStatus.stage]} {batchStatus.current ?? ''}（{batchStatus.completed}/{batchStatus.total}）
+              </span>
+              <span className="text-sm tabular-nums">{batchStatus.progress}%</span>
+            </div>
+            <ProgressTrack className="h-2">
+              <ProgressIndicator />
+            </ProgressTrack>
+          </Progress>
+        ) : (
+          <div className="flex justify-end">
+            <Button size="sm" onClick={handleUpdateAll}>
+              全部更新 ({count})
+            </Button>
+          </div>
+        )}
+      </div>
+    );
+  };
+
+  // 把当前已启用的插件保存为配置
+  const handleSaveProfile = async () => {
+    if (!bootDrive || !profileName.trim()) return;
+    try {
+      const profile = await savePluginProfile(bootDrive.letter, profileName.trim());
+      setProfileName('');
+      setProfiles(prev => [...prev.filter(p => p.name !== profile.name), profile].sort((a, b) => a.name.localeCompare(b.name)));
+      toastManager.add({
+        title: '保存成功',
+        description: `配置 ${profile.name} 包含 ${profile.plugins.length} 个插件`,
+        type: 'success',
+      });
+    } catch (err) {
+      console.error('保存插件配置失败:', err);
+      toastManager.add({
+        title: '保存失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    }
+  };
+
+  const handleApplyProfile = async (profile: PluginProfile) => {
+    if (!bootDrive) return;
+    setApplyingProfile(profile.name);
+    try {
+      const result = await applyPluginProfile(bootDrive.letter, profile.name, config.downloadThreads);
+      triggerPluginListRefresh();
+      const summary = `启用 ${result.enabled.length} 个，禁用 ${result.disabled.length} 个，下载 ${result.installed.length} 个`;
+      toastManager.add({
+        title: `已切换到配置 ${profile.name}`,
+        description: result.missing.length > 0
+          ? `${summary}；以下插件未找到：${result.missing.join('、')}`
+          : summary,
+        type: result.missing.length > 0 ? 'warning' : 'success',
+      });
+    } catch (err) {
+      console.error('应用插件配置失败:', err);
+      toastManager.add({
+        title: '应用失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setApplyingProfile(null);
+    }
+  };
+
+  const handleDeleteProfile = async (profile: PluginProfile) => {
+    if (!bootDrive) return;
+    try {
+      await deletePluginProfile(bootDrive.letter, profile.name);
+      setProfiles(prev => prev.filter(p => p.name !== profile.name));
+    } catch (err) {
+      console.error('删除插件配置失败:', err);
+      toastManager.add({
+        title: '删除失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    }
+  };
+
+  const renderProfiles = () => (
+    <>
+      <div className="flex gap-2 mb-4">
+        <Input
+          value={profileName}
+          onChange={e => setProfileName(e.target.value)}
+          placeholder="配置名称，如：数据恢复"
+          maxLength={50}
+        />
+        <Button size="sm" disabled={!profileName.trim()} onClick={handleSaveProfile}>
+          保存当前启用的插件
+        </Button>
+      </div>
+      {profiles.length > 0 ? (
+        profiles.map(profile => (
+          <div key={profile.name} className="flex items-center justify-between py-2 border-b last:border-b-0">
+            <div>
+              <div className="font-medium">{profile.name}</div>
+              <div className="text-sm text-muted-foreground">{profile.plugins.length} 个插件</div>
+            </div>
+            <div className="flex gap-2">
+              <Button
+                size="sm"
+                disabled={applyingProfile !== null}
+                onClick={() => handleApplyProfile(profile)}
+              >
+                {applyingProfile === profile.name ? <Spinner className="size-4" /> : '应用'}
+              </Button>
+              <Button
+                size="sm"
+                variant="outline"
+                disabled={applyingProfile !== null}
+                onClick={() => handleDeleteProfile(profile)}
+              >
+                删除
+              </Button>
+            </div>
+          </div>
+        ))
+      ) : (
+        <div className="text-sm text-muted-foreground">暂无插件配置</div>
+      )}
+    </>
+  );
+
+  // 导出启动盘上的插件及启用状态，供其他启动盘导入
+  const handleExportPluginSet = async () => {
+    if (!bootDrive) return;
+    const path = await savePluginSetDialog('plugins.json');
+    if (!path) return;
+
+    setTransferringSet(true);
+    try {
+      const manifest = await exportPluginSet(bootDrive.letter, path);
+      const withoutLink = manifest.plugins.filter(p => !p.link).length;
+      toastManager.add({
+        title: '导出成功',
+        description: withoutLink > 0
+          ? `已导出 ${manifest.plugins.length} 个插件，其中 ${withoutLink} 个在插件市场中找不到相同版本，导入时无法下载`
+          : `已导出 ${manifest.plugins.length} 个插件`,
+        type: withoutLink > 0 ? 'warning' : 'success',
+      });
+    } catch (err) {
+      toastManager.add({
+        title: '导出失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setTransferringSet(false);
+    }
+  };
+
+  const handleImportPluginSet = async () => {
+    if (!bootDrive) return;
+    const path = await selectPluginSetFile();
+    if (!path) return;
+
+    setTransferringSet(true);
+    try {
+      let result = await importPluginSet(bootDrive.letter, path, false, false, config.downloadThreads);
+      // 本机或共享路径的来源需要用户确认后才复制
+      if (result.blocked.length > 0 && await confirmDialog(
+        `插件清单中以下插件的下载地址不是网络地址，而是本机或共享路径：${result.blocked.join('、')}。只有清单来源可信时才应允许，是否继续？`,
+        '允许本地来源'
+      )) {
+        result = await importPluginSet(bootDrive.letter, path, false, true, config.downloadThreads);
+      }
+      triggerPluginListRefresh();
+      const summary = `下载 ${result.installed.length} 个，启用 ${result.enabled.length} 个，禁用 ${result.disabled.length} 个`;
+      toastManager.add({
+        title: '导入完成',
+        description: [
+          summary,
+          result.missing.length > 0 ? `以下插件无法获取：${result.missing.join('、')}` : '',
+          result.blocked.length > 0 ? `以下插件未允许从本地来源复制：${result.blocked.join('、')}` : '',
+        ].filter(Boolean).join('；'),
+        type: result.missing.length > 0 || result.blocked.length > 0 ? 'warning' : 'success',
+      });
+    } catch (err) {
+      toastManager.add({
+        title: '导入失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setTransferringSet(false);
+    }
+  };
+
+  // 插件库操作：执行后刷新插件库列表，出错时提示
+  const runLibraryTask = async (task: () => Promise<string>) => {
+    setLibraryBusy(true);
+    try {
+      const description = await task();
+      setLibrary(await getPluginLibrary());
+      toastManager.add({
+        title: '操作成功',
+        description,
+        type: 'success',
+      });
+    } catch (err) {
+      console.error('插件库操作失败:', err);
+      toastManager.add({
+        title: '操作失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setLibraryBusy(false);
+    }
+  };
+
+  const handleUpdateLibrary = () => runLibraryTask(async () => {
+    const downloaded = await downloadToPluginLibrary(undefined, config.downloadThreads);
+    return downloaded.length > 0 ? `已下载 ${downloaded.length} 个新版本插件` : '插件库中的插件均为最新版本';
+  });
+
+  const handleAddDriveToLibrary = () => runLibraryTask(async () => {
+    if (!bootDrive) return '';
+    const report = await addDrivePluginsToLibrary(bootDrive.letter);
+    return `已添加 ${report.actions.length} 个插件到插件库`;
+  });
+
+  // 先预览需要复制的插件，确认后再同步
+  const handlePreviewSync = async () => {
+    if (!bootDrive) return;
+    setLibraryBusy(true);
+    try {
+      setSyncPreview(await syncPluginLibrary(bootDrive.letter, true));
+    } catch (err) {
+      toastManager.add({
+        title: '获取同步差异失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setLibraryBusy(false);
+    }
+  };
+
+  const handleConfirmSync = () => runLibraryTask(async () => {
+    if (!bootDrive) return '';
+    const report = await syncPluginLibrary(bootDrive.letter);
+    setSyncPreview(null);
+    triggerPluginListRefresh();
+    return `已同步 ${report.actions.length} 个插件（${formatBytes(report.totalBytes)}）`;
+  });
+
+  const renderLibrary = () => (
+    <>
+      <div className="text-sm text-muted-foreground mb-4">
+        插件库保存在本机，共 {library.length} 个插件。同步到多个启动盘时无需重复下载。
+      </div>
+      <div className="flex flex-wrap gap-2 mb-4">
+        <Button size="sm" disabled={libraryBusy} onClick={handlePreviewSync}>
+          同步到启动盘
+        </Button>
+        <Button size="sm" variant="outline" disabled={libraryBusy} onClick={handleAddDriveToLibrary}>
+          从启动盘添加
+        </Button>
+        <Button size="sm" variant="outline" disabled={libraryBusy || library.length === 0} onClick={handleUpdateLibrary}>
+          更新插件库
+        </Button>
+      </div>
+      {syncPreview && (
+        <Alert>
+          <Info className="h-4 w-4" />
+          <AlertTitle>
+            {syncPreview.actions.length > 0
+              ? `将复制 ${syncPreview.actions.length} 个插件（${formatBytes(syncPreview.totalBytes)}）`
+              : '启动盘上的插件已是最新'}
+          </AlertTitle>
+          <AlertDescription>
+            {syncPreview.actions.length > 0 && (
+              <ul className="list-disc pl-5">
+                {syncPreview.actions.map(action => (
+                  <li key={action.id}>
+                    {action.kind === 'add'
+                      ? `新增 ${action.name} ${action.version}`
+                      : `升级 ${action.name} ${action.currentVersion} → ${action.version}`}
+                  </li>
+                ))}
+              </ul>
+            )}
+            <div className="flex gap-2 mt-2">
+              {syncPreview.actions.length > 0 && (
+                <Button size="sm" disabled={libraryBusy} onClick={handleConfirmSync}>
+                  确认同步
+                </Button>
+              )}
+              <Button size="sm" variant="outline" disabled={libraryBusy} onClick={() => setSyncPreview(null)}>
+                取消
+              </Button>
+            </div>
+          </AlertDescription>
+        </Alert>
+      )}
+    </>
+  );
+
+  // 解压插件包的全部内容做完整性测试
+  const handleInspectPlugin = async (plugin: Plugin) => {
+    if (!bootDrive) return;
+    setInspectingPlugins(prev => ({ ...prev, [plugin.file]: true }));
+    try {
+      const inspection = await inspectPlugin(bootDrive.letter, plugin.file, true);
+      const files = inspection.entries.filter(e => !e.isDirectory).length;
+      const summary = `${inspection.format} 格式，${files} 个文件，解压后 ${formatBytes(inspection.unpackedSize)}`;
+      setDamagedPlugins(prev => {
+        const next = { ...prev };
+        if (inspection.problems.length > 0) {
+          next[plugin.file] = inspection.problems;
+        } else {
+          delete next[plugin.file];
+        }
+        return next;
+      });
+      const damaged = inspection.problems.length > 0;
+      const notes = damaged ? inspection.problems : inspection.warnings;
+      toastManager.add({
+        title: damaged ? `插件 ${plugin.name} 已损坏` : `插件 ${plugin.name} 完好`,
+        description: notes.length > 0 ? `${summary}\n${notes.join('\n')}` : summary,
+        type: damaged ? 'error' : notes.length > 0 ? 'warning' : 'success',
+      });
+    } catch (err) {
+      toastManager.add({
+        title: '检查失败',
+        description: `${err}`,
+        type: 'error',
+      });
+    } finally {
+      setInspectingPlugins(prev => ({ ...prev, [plugin.file]: false }));
+    }
+  };
+
+  const renderDamaged = () => {
+    const files = Object.keys(damagedPlugins);
+    if (files.length === 0) return null;
+
+    return (
+      <div className="mb-4">
+        <Alert variant="error">
+          <AlertCircle className="h-4 w-4" />
+          <AlertTitle>{files.length} 个插件包已损坏，在 PE 中将无法加载</AlertTitle>
+          <AlertDescription>
+            <ul className="list-disc pl-5">
+              {files.map(file => (
+                <li key={file}>{file}：{damagedPlugins[file].join('；')}</li>
+              ))}
+            </ul>
+          </AlertDescription>
+        </Alert>
+      </div>
+    );
+  };
+
+  const renderDuplicates = () => {
+    if (duplicates.length === 0) return null;
+
+    return (
+      <div className="mb-4">
+        <Alert variant="warning">
+          <AlertTriangle className="h-4 w-4" />
+          <AlertTitle>检测到 {duplicates.length} 个插件存在多个版本</AlertTitle>
+          <AlertDescription>
+            <ul className="list-disc pl-5">
+              {duplicates.map(group => {
+                const winner = group.files.find(f => f.file === group.winner);
+                const others = group.files.filter(f => f.file !== group.winner).map(f => f.version);
+                return (
+                  <li key={group.id}>
+                    {group.name}：保留 {winner?.version}，旧版本 {others.join('、')}
+                  </li>
+                );
+              })}
+            </ul>
+            <div className="flex gap-2 mt-2">
+              <Button size="sm" disabled={cleaningDuplicates} onClick={() => handleCleanupDuplicates(false)}>
+                保留最新版本并禁用其他
+              </Button>
+              <Button size="sm" variant="destructive" disabled={cleaningDuplicates} onClick={() => handleCleanupDuplicates(true)}>
+                删除旧版本
+              </Button>
+            </div>
+          </AlertDescription>
+        </Alert>
+      </div>
+    );
+  };
+
+  // 撤销卸载：从回收区恢复
+  const handleRestorePlugins = async (result: UninstallResult) => {
+    if (!bootDrive) return;
+
+    try {
+      for (const item of result.removed) {
+        if (item.token) {
+          await restorePlugin(bootDrive.letter, item.token);
+        }
+      }
+      toastManager.add({
+        title: '成功',
+        description: `已恢复 ${result.removed.length} 个插件`,
+        type: 'success',
+      });
+    } catch (err) {
+      console.error('恢复插件失败:', err);
+      toastManager.add({
+        title: '错误',
+        description: `恢复插件失败: ${err}`,
+        type: 'error',
+      });
+    } finally {
+      await fetchPluginFiles();
+    }
+  };
+
+  // 卸载插件（可批量），文件先移到启动盘回收区
+  const handleUninstallPlugins = async (plugins: Plugin[]) => {
+    if (!bootDrive || plugins.length === 0) return;
+
+    try {
+      setProcessingPlugins(prev => ({ ...prev, ...Object.fromEntries(plugins.map(p => [p.file, true])) }));
+
+      const result = await uninstallPlugins(
+        bootDrive.letter,
+        plugins.map(p => ({ id: p.id ?? generatePluginId(p.name, p.author), file: p.file }))
+      );
+
+      if (result.removed.length > 0) {
+        toastManager.add({
+          title: '已卸载',
+          description: `已卸载 ${result.removed.length} 个插件，清空回收区后可释放 ${formatBytes(result.recycledBytes)}`,
+          type: 'success',
+          actionProps: {
+            children: '撤销',
+            onClick: () => handleRestorePlugins(result),
+          },
+        });
+      }
+      if (result.errors.length > 0) {
+        toastManager.add({
+          title: '部分插件卸载失败',
+          description: result.errors.map(e => `${e.file}: ${e.error}`).join('\n'),
+          type: 'error',
+        });
+      }
+    } catch (err) {
+      console.error('卸载插件失败:', err);
+      toastManager.add({
+        title: '错误',
+        description: '卸载插件失败',
+        type: 'error',
+      });
+    } finally {
+      setProcessingPlugins(prev => ({ ...prev, ...Object.fromEntries(plugins.map(p => [p.file, false])) }));
+      await fetchPluginFiles();
+    }
+  };
+
+  // 用户确认插件计划后执行
+  const handleConfirmPlan = async (force: boolean) => {
+    const pending = pendingPlan;
+    setPendingPlan(null);
+    if (!pending) return;
+
+    try {
+      await pending.run(force);
+    } catch (err) {
+      console.error('执行插件计划失败:', err);
+      toastManager.add({
+        title: '错误',
+        description: `${pending.title}失败`,
+        type: 'error',
+      });
+    }
+  };
+
   // 渲染折叠面板
   const renderCollapsePanel = (key: string, title: string, children: React.ReactNode) => {
     const isExpanded = expandedSections[key];
@@ -388,7 +1049,19 @@ const PluginsManagePage: React.FC = () => {
 
   return (
     <div className="p-6 h-[84vh] flex flex-col overflow-hidden">
-      <h3 className="text-xl font-semibold mb-6 shrink-0">插件管理</h3>
+      <div className="flex items-center justify-between mb-6 shrink-0">
+        <h3 className="text-xl font-semibold">插件管理</h3>
+        {bootDrive && (
+          <div className="flex gap-2">
+            <Button size="sm" variant="outline" disabled={transferringSet} onClick={handleImportPluginSet}>
+              导入插件清单
+            </Button>
+            <Button size="sm" variant="outline" disabled={transferringSet} onClick={handleExportPluginSet}>
+              导出插件清单
+            </Button>
+          </div>
+        )}
+      </div>
 
       {loading ? (
         <div className="flex-1 flex justify-center items-center">
@@ -404,6 +1077,14 @@ const PluginsManagePage: React.FC = () => {
         </div>
       ) : (
         <div className="flex-1 overflow-auto pr-2">
+          {renderDamaged()}
+          {renderDuplicates()}
+          {renderUpdateAll()}
+
+          {renderCollapsePanel('profiles', `插件配置 (${profiles.length})`, renderProfiles())}
+
+          {renderCollapsePanel('library', `本机插件库 (${library.length})`, renderLibrary())}
+
           {renderCollapsePanel(
             'enabled',
             `已启用插件 (${enabledPlugins.length})`,
@@ -422,7 +1103,14 @@ const PluginsManagePage: React.FC = () => {
             'disabled',
             `已禁用插件 (${disabledPlugins.length})`,
             disabledPlugins.length > 0 ? (
-              disabledPlugins.map(plugin => renderPluginCard(plugin, false))
+              <>
+                <div className="flex justify-end mb-4">
+                  <Button variant="destructive-outline" size="sm" onClick={() => handleUninstallPlugins(disabledPlugins)}>
+                    卸载全部已禁用插件
+                  </Button>
+                </div>
+                {disabledPlugins.map(plugin => renderPluginCard(plugin, false))}
+              </>
             ) : (
               renderEmpty(
                 <Info className="size-12" />,
@@ -433,6 +1121,14 @@ const PluginsManagePage: React.FC = () => {
           )}
         </div>
       )}
+
+      <PluginPlanDialog
+        open={pendingPlan !== null}
+        title={pendingPlan?.title ?? ''}
+        plan={pendingPlan?.plan ?? null}
+        onConfirm={handleConfirmPlan}
+        onCancel={() => setPendingPlan(null)}
+      />
     </div>
   );
 };
@@ -7,10 +7,14 @@ import { Badge } from '@/components/ui/badge';
 import { Tabs, TabsList, TabsTab } from '@/components/ui/tabs';
 import { Tooltip, TooltipTrigger, TooltipPopup } from '@/components/ui/tooltip';
 import { toastManager } from '@/components/ui/toast';
-import { downloadPlugin, updatePlugin, getPluginFiles, generatePluginId, compareVersions, Plugin } from '../api/pluginsApi';
+import {
+  downloadPlugin, updatePlugin, getPluginFiles, generatePluginId, compareVersions, Plugin,
+  PluginPlan, planPluginInstall, applyPluginPlan, isSimplePlan, flattenPluginCategories,
+} from '../api/pluginsApi';
 import { useAppContext } from '../utils/AppContext';
 import { cacheService } from '../utils/cacheService';
 import CheckCircle from '@/components/icon/CheckCircle';
+import PluginPlanDialog from '@/components/PluginPlanDialog';
 
 interface PluginStatus {
   installed: boolean;
@@ -44,6 +48,8 @@ const PluginsMarketPage: React.FC = () => {
   const [localPluginsMap, setLocalPluginsMap] = useState<Map<string, Plugin>>(new Map());
   const [processingPlugins, setProcessingPlugins] = useState<Record<string, boolean>>({});
   const [recentlyUpdatedPlugins, setRecentlyUpdatedPlugins] = useState<Set<string>>(new Set());
+  // 安装时需要同时处理依赖或冲突，等待用户确认的计划
+  const [pendingInstall, setPendingInstall] = useState<{ plugin: Plugin; plan: PluginPlan } | null>(null);
 
   const hasPlugins = cacheService.hasPlugins();
 
@@ -208,6 +214,17 @@ const PluginsMarketPage: React.FC = () => {
 
     const pluginId = getPluginUniqueId(plugin);
 
+    try {
+      const plan = await planPluginInstall(bootDrive.letter, plugin, flattenPluginCategories(pluginCategories));
+      if (!isSimplePlan(plan)) {
+        setPendingInstall({ plugin, plan });
+        return;
+      }
+    } catch (err) {
+      // 依赖解析失败不影响直接下载
+      console.error('计算插件依赖失败:', err);
+    }
+
     try {
       setPluginDownloading(pluginId, true);
 
@@ -239,6 +256,39 @@ const PluginsMarketPage: React.FC = () => {
     }
   };
 
+  // 用户确认后按计划下载插件及其依赖
+  const handleConfirmInstall = async (force: boolean) => {
+    const pending = pendingInstall;
+    setPendingInstall(null);
+    if (!pending || !bootDrive) return;
+
+    const { plugin, plan } = pending;
+    const pluginId = getPluginUniqueId(plugin);
+
+    try {
+      setPluginDownloading(pluginId, true);
+      await applyPluginPlan(bootDrive.letter, plan, force, config.downloadThreads);
+
+      toastManager.add({
+        type: 'success',
+        title: '下载成功',
+        description: `插件 ${plugin.name} 及其依赖已安装完成`,
+      });
+
+      triggerPluginListRefresh();
+      await loadLocalPlugins();
+    } catch (err) {
+      console.error('安装插件失败:', err);
+      toastManager.add({
+        type: 'error',
+        title: '错误',
+        description: `插件 ${plugin.name} 安装失败`,
+      });
+    } finally {
+      setPluginDownloading(pluginId, false);
+    }
+  };
+
   // 处理插件更新
   const handleUpdatePlugin = async (plugin: Plugin) => {
     if (!bootDrive) {
@@ -570,6 +620,14 @@ const PluginsMarketPage: React.FC = () => {
           </>
         )}
       </div>
+
+      <PluginPlanDialog
+        open={pendingInstall !== null}
+        title={pendingInstall ? `安装插件 ${pendingInstall.plugin.name}` : ''}
+        plan={pendingInstall?.plan ?? null}
+        onConfirm={handleConfirmInstall}
+        onCancel={() => setPendingInstall(null)}
+      />
     </div>
   );
 };
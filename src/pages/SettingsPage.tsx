@@ -1,11 +1,15 @@
 import React, { useState, useEffect, useCallback, useRef } from 'react';
 import { useAppContext } from '../utils/AppContext';
 import type { ThemeMode, DownloadThreads } from '../utils/theme';
-import { openUrl } from '../utils/tauriApiWrapper';
+import { openUrl, invoke, selectUpdatePackage, confirmDialog } from '../utils/tauriApiWrapper';
 import { openDevTools } from '../utils/devtools';
+import { getAutomationStatus, setAutomationEnabled, regenerateAutomationToken, onAutomationStatusChange, type AutomationStatus } from '../api/automationApi';
+import { listMirrorCredentials, setMirrorCredential, removeMirrorCredential, type MirrorAuth, type MirrorCredentialSummary } from '../api/credentialsApi';
+import { getUpdateSettings, setUpdateSettings, inspectUpdatePackage, type UpdateChannel, type UpdateSettingsView } from '../api/updateApi';
 
 import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
+import { Textarea } from '@/components/ui/textarea';
 import { Switch } from '@/components/ui/switch';
 import { Label } from '@/components/ui/label';
 import { Card, CardHeader, CardTitle, CardPanel } from '@/components/ui/card';
@@ -15,6 +19,12 @@ import { AlertDialog, AlertDialogPopup, AlertDialogHeader, AlertDialogTitle, Ale
 import { toastManager } from '@/components/ui/toast';
 import { Github } from 'lucide-react';
 
+const UPDATE_CHANNEL_LABELS: Record<UpdateChannel, string> = {
+  stable: '正式版',
+  beta: '测试版',
+  pinned: '固定版本',
+};
+
 const SettingsPage: React.FC = () => {
   const {
     config,
@@ -31,6 +41,52 @@ const SettingsPage: React.FC = () => {
   const [showBootDriveModal, setShowBootDriveModal] = useState(false);
   const [pendingBootDrive, setPendingBootDrive] = useState<string>('');
 
+  // 本地自动化接口状态
+  const [automationStatus, setAutomationStatus] = useState<AutomationStatus | null>(null);
+
+  useEffect(() => {
+    getAutomationStatus()
+      .then(setAutomationStatus)
+      .catch((error) => console.error('获取自动化接口状态失败:', error));
+
+    // 启动或重启失败时后端会推送最新状态
+    const unlistenPromise = onAutomationStatusChange(setAutomationStatus);
+    return () => {
+      unlistenPromise.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // 私有镜像认证
+  const [mirrorCredentials, setMirrorCredentials] = useState<MirrorCredentialSummary[]>([]);
+  const [credentialHost, setCredentialHost] = useState('');
+  const [credentialAuthType, setCredentialAuthType] = useState<'none' | 'basic' | 'bearer'>('none');
+  const [credentialUsername, setCredentialUsername] = useState('');
+  const [credentialSecret, setCredentialSecret] = useState('');
+  const [credentialHeaders, setCredentialHeaders] = useState('');
+
+  const refreshMirrorCredentials = useCallback(() => {
+    listMirrorCredentials()
+      .then(setMirrorCredentials)
+      .catch((error) => console.error('获取镜像认证失败:', error));
+  }, []);
+
+  useEffect(() => {
+    refreshMirrorCredentials();
+  }, [refreshMirrorCredentials]);
+
+  // 更新通道设置（可能被管理员策略锁定）
+  const [updateSettingsView, setUpdateSettingsView] = useState<UpdateSettingsView | null>(null);
+  const [pinnedVersion, setPinnedVersion] = useState<string>('');
+
+  useEffect(() => {
+    getUpdateSettings()
+      .then((view) => {
+        setUpdateSettingsView(view);
+        setPinnedVersion(view.settings.pinnedVersion || '');
+      })
+      .catch((error) => console.error('获取更新设置失败:', error));
+  }, []);
+
   // 监听配置变化，同步用户称呼
   useEffect(() => {
     setUserNickname(config.userNickname || '');
@@ -79,6 +135,39 @@ const SettingsPage: React.FC = () => {
     updateConfig({ downloadThreads: value as DownloadThreads });
   };
 
+  const saveUpdateSettings = async (channel: UpdateChannel, version: string) => {
+    try {
+      setUpdateSettingsView(await setUpdateSettings(channel, version || null));
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  // 管理员固定版本时强制使用固定版本通道，与后端一致
+  const effectiveUpdateChannel: UpdateChannel = updateSettingsView?.policy?.pinnedVersion
+    ? 'pinned'
+    : updateSettingsView?.policy?.channel ?? updateSettingsView?.settings.channel ?? 'stable';
+
+  const handleUpdateChannelChange = (value: string) => {
+    const channel = value as UpdateChannel;
+    // 固定版本需要先填写版本号
+    if (channel === 'pinned' && !pinnedVersion.trim()) {
+      setUpdateSettingsView(prev => prev && { ...prev, settings: { ...prev.settings, channel } });
+      return;
+    }
+    saveUpdateSettings(channel, pinnedVersion.trim());
+  };
+
+  const handlePinnedVersionBlur = () => {
+    if (updateSettingsView?.settings.channel === 'pinned' && pinnedVersion.trim()) {
+      saveUpdateSettings('pinned', pinnedVersion.trim());
+    }
+  };
+
   const handleWebSearchToggle = (checked: boolean) => {
     updateConfig({ enablePluginWebSearch: checked });
   };
@@ -182,6 +271,143 @@ const SettingsPage: React.FC = () => {
     }
   };
 
+  // 开启/关闭本地自动化接口
+  const handleAutomationToggle = async (checked: boolean) => {
+    try {
+      const status = await setAutomationEnabled(checked);
+      setAutomationStatus(status);
+      toastManager.add({
+        title: '成功',
+        description: checked ? `自动化接口已开启：${status.address}` : '自动化接口已关闭',
+        type: 'success',
+      });
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  // 重新生成自动化接口令牌
+  const handleRegenerateToken = async () => {
+    try {
+      setAutomationStatus(await regenerateAutomationToken());
+      toastManager.add({
+        title: '成功',
+        description: '已生成新的访问令牌，旧令牌已失效',
+        type: 'success',
+      });
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  // 保存镜像认证，请求头每行一个，格式为 名称: 值
+  const handleSaveMirrorCredential = async () => {
+    const headers: Record<string, string> = {};
+    for (const line of credentialHeaders.split('\n')) {
+      if (!line.trim()) {
+        continue;
+      }
+      const index = line.indexOf(':');
+      if (index <= 0) {
+        toastManager.add({
+          title: '失败',
+          description: `请求头格式错误：${line.trim()}`,
+          type: 'error',
+        });
+        return;
+      }
+      headers[line.slice(0, index).trim()] = line.slice(index + 1).trim();
+    }
+
+    let auth: MirrorAuth | null = null;
+    if (credentialAuthType === 'basic') {
+      auth = { type: 'basic', username: credentialUsername, password: credentialSecret };
+    } else if (credentialAuthType === 'bearer') {
+      auth = { type: 'bearer', token: credentialSecret };
+    }
+
+    try {
+      await setMirrorCredential(credentialHost, headers, auth);
+      setCredentialHost('');
+      setCredentialUsername('');
+      setCredentialSecret('');
+      setCredentialHeaders('');
+      refreshMirrorCredentials();
+      toastManager.add({
+        title: '成功',
+        description: '镜像认证已保存',
+        type: 'success',
+      });
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  const handleRemoveMirrorCredential = async (host: string) => {
+    try {
+      await removeMirrorCredential(host);
+      refreshMirrorCredentials();
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  // 回滚到更新前的版本
+  const handleRollbackUpdate = async () => {
+    try {
+      await invoke('rollback_update');
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
+  // 从本地更新包更新（离线环境）
+  const handleUpdateFromFile = async () => {
+    try {
+      const filePath = await selectUpdatePackage();
+      if (!filePath) {
+        return;
+      }
+      // 先验证签名并显示版本，用户确认后再安装并重启
+      const summary = await inspectUpdatePackage(filePath);
+      const confirmed = await confirmDialog(
+        `更新包版本 ${summary.version}，当前版本 ${summary.currentVersion}。\n安装后程序将退出并重启，是否继续？`,
+        '从本地更新包更新'
+      );
+      if (!confirmed) {
+        return;
+      }
+      const planPath = await invoke<string>('install_update_from_file', { filePath });
+      await invoke('install_update', { planPath });
+    } catch (error) {
+      toastManager.add({
+        title: '失败',
+        description: String(error),
+        type: 'error',
+      });
+    }
+  };
+
   // 处理测试通知
   const handleTestNotification = () => {
     toastManager.add({
@@ -231,6 +457,7 @@ const SettingsPage: React.FC = () => {
                       <SelectValue />
                     </SelectTrigger>
                     <SelectPopup>
+                      <SelectItem value={0}>自动</SelectItem>
                       <SelectItem value={8}>8</SelectItem>
                       <SelectItem value={16}>16</SelectItem>
                       <SelectItem value={32}>32</SelectItem>
@@ -238,6 +465,46 @@ const SettingsPage: React.FC = () => {
                   </Select>
                 </div>
 
+                <div className="mb-4">
+                  <div className="flex items-center gap-3">
+                    <Label>更新通道：</Label>
+                    <Select
+                      value={effectiveUpdateChannel}
+                      onValueChange={(val) => handleUpdateChannelChange(val as string)}
+                      disabled={!!updateSettingsView?.policy}
+                    >
+                      <SelectTrigger className="w-[120px]">
+                        <span className="flex-1 truncate">
+                          {UPDATE_CHANNEL_LABELS[effectiveUpdateChannel]}
+                        </span>
+                      </SelectTrigger>
+                      <SelectPopup>
+                        <SelectItem value="stable">正式版</SelectItem>
+                        <SelectItem value="beta">测试版</SelectItem>
+                        <SelectItem value="pinned">固定版本</SelectItem>
+                      </SelectPopup>
+                    </Select>
+                    {effectiveUpdateChannel === 'pinned' && (
+                      <Input
+                        value={updateSettingsView?.policy?.pinnedVersion ?? pinnedVersion}
+                        onChange={(e) => setPinnedVersion(e.target.value)}
+                        onBlur={handlePinnedVersionBlur}
+                        placeholder="例如 1.6.0"
+                        className="w-[120px]"
+                        disabled={!!updateSettingsView?.policy}
+                      />
+                    )}
+                  </div>
+                  {updateSettingsView?.policy && (
+                    <div className="mt-2 text-sm text-muted-foreground">
+                      {updateSettingsView.policy.frozen
+                        ? '管理员已冻结更新'
+                        : '更新设置由管理员策略管理'}
+                      {updateSettingsView.policy.message ? `：${updateSettingsView.policy.message}` : ''}
+                    </div>
+                  )}
+                </div>
+
                 <div className="mb-4 flex items-center gap-3">
                   <Label>颜色模式：</Label>
                   <Select value={config.themeMode} onValueChange={handleThemeChange}>
@@ -274,13 +541,118 @@ const SettingsPage: React.FC = () => {
                 )}
 
 
-                <div className="flex items-center gap-3">
+                <div className="mb-4 flex items-center gap-3">
                   <Label>开启插件市场"搜索"按钮：</Label>
                   <Switch
                     checked={config.enablePluginWebSearch}
                     onCheckedChange={handleWebSearchToggle}
                   />
                 </div>
+
+                <div className="flex items-center gap-3">
+                  <Label>开启本地自动化接口：</Label>
+                  <Switch
+                    checked={automationStatus?.enabled ?? false}
+                    onCheckedChange={handleAutomationToggle}
+                  />
+                </div>
+                {automationStatus?.enabled && (
+                  <div className="mt-3 p-3 rounded-lg border border-warning/40 bg-warning/10 text-sm space-y-2">
+                    <div>
+                      自动化接口{automationStatus.running ? '运行中' : '未运行'}，仅本机可访问：
+                      <span className="font-mono">{automationStatus.address}</span>
+                    </div>
+                    {automationStatus.error && (
+                      <div className="text-destructive">
+                        启动失败：{automationStatus.error}
+                      </div>
+                    )}
+                    <div className="flex items-center gap-2">
+                      <span>访问令牌：</span>
+                      <Input className="font-mono flex-1" readOnly value={automationStatus.token} />
+                      <Button variant="outline" onClick={handleRegenerateToken}>
+                        重新生成
+                      </Button>
+                    </div>
+                    <div className="text-muted-foreground">
+                      持有令牌的本机程序可以部署U盘、下载文件和管理插件，请勿泄露。
+                    </div>
+                  </div>
+                )}
+              </div>
+            </AccordionPanel>
+          </AccordionItem>
+
+          <AccordionItem value="mirror-credentials">
+            <AccordionTrigger>私有镜像认证</AccordionTrigger>
+            <AccordionPanel>
+              <div className="py-2 space-y-3">
+                <div className="text-sm text-muted-foreground">
+                  从私有镜像下载时按主机附加请求头和认证信息，保存后加密存储在本机，只显示主机和认证类型。
+                </div>
+                {mirrorCredentials.map(credential => (
+                  <div key={credential.host} className="flex items-center gap-3 p-2 rounded-lg border">
+                    <span className="font-mono flex-1 truncate">{credential.host}</span>
+                    <span className="text-sm text-muted-foreground">
+                      {credential.authType === 'basic' ? '用户名密码' : credential.authType === 'bearer' ? '令牌' : '无认证'}
+                      {credential.headerNames.length > 0 ? `，请求头：${credential.headerNames.join('、')}` : ''}
+                    </span>
+                    <Button variant="outline" onClick={() => handleRemoveMirrorCredential(credential.host)}>
+                      删除
+                    </Button>
+                  </div>
+                ))}
+                <div className="flex items-center gap-3">
+                  <Label>主机：</Label>
+                  <Input
+                    value={credentialHost}
+                    onChange={(e) => setCredentialHost(e.target.value)}
+                    placeholder="例如 mirror.example.com:8080"
+                    className="flex-1"
+                  />
+                  <Select value={credentialAuthType} onValueChange={(val) => setCredentialAuthType(val as 'none' | 'basic' | 'bearer')}>
+                    <SelectTrigger className="w-[120px]">
+                      <span className="flex-1 truncate">
+                        {credentialAuthType === 'basic' ? '用户名密码' : credentialAuthType === 'bearer' ? '令牌' : '无认证'}
+                      </span>
+                    </SelectTrigger>
+                    <SelectPopup>
+                      <SelectItem value="none">无认证</SelectItem>
+                      <SelectItem value="basic">用户名密码</SelectItem>
+                      <SelectItem value="bearer">令牌</SelectItem>
+                    </SelectPopup>
+                  </Select>
+                </div>
+                {credentialAuthType !== 'none' && (
+                  <div className="flex items-center gap-3">
+                    {credentialAuthType === 'basic' && (
+                      <Input
+                        value={credentialUsername}
+                        onChange={(e) => setCredentialUsername(e.target.value)}
+                        placeholder="用户名"
+                        className="flex-1"
+                      />
+                    )}
+                    <Input
+                      type="password"
+                      value={credentialSecret}
+                      onChange={(e) => setCredentialSecret(e.target.value)}
+                      placeholder={credentialAuthType === 'basic' ? '密码' : '访问令牌'}
+                      className="flex-1"
+                    />
+                  </div>
+                )}
+                <Textarea
+                  value={credentialHeaders}
+                  onChange={(e) => setCredentialHeaders(e.target.value)}
+                  placeholder={'自定义请求头（可选），每行一个，例如\nX-Api-Key: abc123'}
+                  className="font-mono"
+                />
+                <div className="flex justify-end">
+                  <Button onClick={handleSaveMirrorCredential} disabled={!credentialHost.trim()}>
+                    保存
+                  </Button>
+                </div>
               </div>
             </AccordionPanel>
           </AccordionItem>
@@ -349,6 +721,12 @@ const SettingsPage: React.FC = () => {
                       <Button onClick={handleTestNotification}>
                         测试
                       </Button>
+                      <Button variant="outline" onClick={handleRollbackUpdate}>
+                        回滚到上一版本
+                      </Button>
+                      <Button variant="outline" onClick={handleUpdateFromFile}>
+                        从本地文件更新
+                      </Button>
                     </div>
                   </CardPanel>
                 </Card>
@@ -3,12 +3,8 @@ import { AppConfig, loadConfig, saveConfig, applyTheme, applyWindowEffects, Wind
 import type { DriveInfo } from './system';
 import { cacheService } from './cacheService';
 import { compareVersions } from '../api/bootDriveUpdateApi';
-import { isUpdateSkippable, getUpdateLog, getUpdateLink, getAppExecutableName, checkNeedsUpdate } from '../api/updateApi';
 import type { PluginCategory } from '../api/pluginsApi';
 
-// 当前应用版本
-const CURRENT_VERSION = 'v1.6';
-
 interface UpdateInfo {
   version: string;
   updateLog: string;
@@ -189,22 +185,17 @@ export const AppProvider: React.FC<AppProviderProps> = ({ children }) => {
         setNotificationClosed(true);
       }
       
-      // 处理应用更新
+      // 处理应用更新（是否需要更新由后端根据更新通道和管理员策略决定）
       const cachedUpdateInfo = cacheService.getUpdateInfo();
-      if (cachedUpdateInfo) {
-        const latestVersion = cachedUpdateInfo.hub_new.hub_ver;
-        const needsUpdate = checkNeedsUpdate(CURRENT_VERSION, latestVersion);
-        
-        if (needsUpdate) {
-          setUpdateInfo({
-            version: latestVersion,
-            updateLog: getUpdateLog(cachedUpdateInfo),
-            downloadLink: getUpdateLink(cachedUpdateInfo),
-            appExecutableName: getAppExecutableName(cachedUpdateInfo),
-            canSkip: isUpdateSkippable(cachedUpdateInfo)
-          });
-          setIsUpdateAvailable(true);
-        }
+      if (cachedUpdateInfo?.available) {
+        setUpdateInfo({
+          version: cachedUpdateInfo.version,
+          updateLog: cachedUpdateInfo.updateLog,
+          downloadLink: cachedUpdateInfo.downloadLink,
+          appExecutableName: cachedUpdateInfo.appExecutableName,
+          canSkip: cachedUpdateInfo.canSkip
+        });
+        setIsUpdateAvailable(true);
       }
       
       // 从缓存获取启动盘相关信息
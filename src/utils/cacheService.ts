@@ -2,6 +2,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { unifiedApiService } from '../api/unifiedApi';
 import { getPlugins } from '../api/pluginsApi';
+import { checkAppUpdate, type AppUpdateDecision } from '../api/updateApi';
 import type { DriveInfo } from './system';
 import type { PluginCategory } from '../api/pluginsApi';
 
@@ -25,8 +26,8 @@ interface CacheData {
     cloudPeUpdateList: string[];
   } | null;
   
-  // 应用更新相关
-  updateInfo: any | null;
+  // 应用更新相关（由后端按更新通道和策略决定）
+  updateInfo: AppUpdateDecision | null;
   
   // 插件相关
   pluginCategories: PluginCategory[] | null;
@@ -110,9 +111,12 @@ class CacheService {
         // 启动盘相关
         this.loadAllBootDrivesWithVersion(), // 修改：加载所有启动盘及版本
         
-        // 统一API数据（包含更新信息、通知、启动盘更新信息、ISO链接）
+        // 统一API数据（包含通知、启动盘更新信息、ISO链接）
         this.loadUnifiedApiData(),
         
+        // 应用更新
+        this.loadAppUpdate(),
+        
         // 插件相关
         this.loadPluginCategories(),
       ];
@@ -177,9 +181,6 @@ class CacheService {
         unifiedApiService.getData(true)
       ]);
       
-      // 保存更新信息
-      this.cache.updateInfo = defaultData;
-      
       // 保存启动盘更新信息
       this.cache.bootDriveUpdateInfo = {
         cloudPeVersion: defaultData.data.cloud_pe,
@@ -208,6 +209,15 @@ class CacheService {
     }
   }
   
+  // 加载应用更新决定
+  private async loadAppUpdate(): Promise<void> {
+    try {
+      this.cache.updateInfo = await checkAppUpdate();
+    } catch (error) {
+      console.error('检查应用更新失败:', error);
+    }
+  }
+  
   // 加载Mica支持状态
   private async loadMicaSupport(): Promise<void> {
     try {
@@ -472,7 +482,7 @@ async reloadBootDriveInfo(driveLetter?: string, skipCheck: boolean = false): Pro
     return this.cache.bootDriveUpdateInfo;
   }
   
-  getUpdateInfo(): any | null {
+  getUpdateInfo(): AppUpdateDecision | null {
     return this.cache.updateInfo;
   }
   
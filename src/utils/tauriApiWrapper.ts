@@ -3,7 +3,7 @@ import { invoke as tauriInvoke } from "@tauri-apps/api/core";
 import { appConfigDir } from "@tauri-apps/api/path";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { readTextFile as fsReadTextFile, writeTextFile as fsWriteTextFile, exists as fsExists, mkdir as fsMkdir } from "@tauri-apps/plugin-fs";
-import { save as dialogSave } from "@tauri-apps/plugin-dialog";
+import { save as dialogSave, open as dialogOpen, ask as dialogAsk } from "@tauri-apps/plugin-dialog";
 
 // 获取当前用户名
 export const getCurrentUsername = async (): Promise<string> => {
@@ -150,6 +150,77 @@ export const getDriveInfo = async (
   }
 };
 
+// 选择本地更新包
+export const selectUpdatePackage = async (): Promise<string | null> => {
+  try {
+    const selected = await dialogOpen({
+      multiple: false,
+      filters: [
+        {
+          name: "更新包",
+          extensions: ["zip"],
+        },
+      ],
+    });
+    return typeof selected === "string" ? selected : null;
+  } catch (error) {
+    console.error("打开文件选择对话框失败:", error);
+    throw error;
+  }
+};
+
+// 插件清单保存对话框
+export const savePluginSetDialog = async (
+  defaultFilename: string
+): Promise<string | null> => {
+  try {
+    return await dialogSave({
+      defaultPath: defaultFilename,
+      filters: [
+        {
+          name: "插件清单",
+          extensions: ["json"],
+        },
+      ],
+    });
+  } catch (error) {
+    console.error("打开文件保存对话框失败:", error);
+    throw error;
+  }
+};
+
+// 选择插件清单文件
+export const selectPluginSetFile = async (): Promise<string | null> => {
+  try {
+    const selected = await dialogOpen({
+      multiple: false,
+      filters: [
+        {
+          name: "插件清单",
+          extensions: ["json"],
+        },
+      ],
+    });
+    return typeof selected === "string" ? selected : null;
+  } catch (error) {
+    console.error("打开文件选择对话框失败:", error);
+    throw error;
+  }
+};
+
+// 确认对话框，用户点击确定时返回 true
+export const confirmDialog = async (
+  message: string,
+  title: string
+): Promise<boolean> => {
+  try {
+    return await dialogAsk(message, { title, kind: "warning" });
+  } catch (error) {
+    console.error("打开确认对话框失败:", error);
+    throw error;
+  }
+};
+
 // 文件保存对话框
 export const saveFileDialog = async (
   defaultFilename: string
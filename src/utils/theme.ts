@@ -2,7 +2,7 @@ import { getAppConfigDir, readTextFile, writeTextFile, exists, createDir } from
 import { checkMicaSupport } from './system';
 
 export type ThemeMode = 'system' | 'light' | 'dark';
-export type DownloadThreads = 8 | 16 | 32 | 64 | 128 ;
+export type DownloadThreads = 0 | 8 | 16 | 32 | 64 | 128 ; // 0 表示自动
 export type WindowEffectsMode = 'off' | 'partial' | 'full'; // 新增类型
 
 export interface AppConfig {